@@ -0,0 +1,66 @@
+use std::{hint::black_box, path::Path};
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use infinitier_core::{
+    datasource::{DataSource, Importer},
+    fs::CaseInsensitiveFS,
+    locator::ResourceLocator,
+    resource::{
+        bam::{Bam, BamImporter},
+        key::ResourceType,
+        wed::WedImporter,
+    },
+};
+
+const RESOURCES_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/resources");
+
+/// Reads a fixture in memory, so that the benchmarks do not measure the file system
+fn in_memory(path: &str) -> DataSource {
+    DataSource::new(std::fs::read(Path::new(RESOURCES_DIR).join(path)).unwrap())
+}
+
+fn wed_parse(c: &mut Criterion) {
+    let source = in_memory("bg2/override/ar0072.WED");
+    c.bench_function("wed_parse", |b| {
+        b.iter(|| WedImporter::import(black_box(&source)).unwrap())
+    });
+}
+
+fn bam_v1_render(c: &mut Criterion) {
+    let source = in_memory("resources/BAM_V1/02/SPHEART_decompressed.BAM");
+    let Bam::V1(bam) = BamImporter::import(&source).unwrap() else {
+        panic!("Expected a BAM V1 file");
+    };
+    c.bench_function("bam_v1_render", |b| {
+        b.iter(|| {
+            for frame in &bam.frames {
+                black_box(frame.to_image(&bam.palette).unwrap());
+            }
+        })
+    });
+}
+
+fn bamc_decompress(c: &mut Criterion) {
+    let source = in_memory("resources/BAM_V1/02/SPHEART_compressed.BAM");
+    c.bench_function("bamc_decompress", |b| {
+        b.iter(|| BamImporter::import(black_box(&source)).unwrap())
+    });
+}
+
+fn bifc_extract(c: &mut Criterion) {
+    let fs = CaseInsensitiveFS::new(format!("{RESOURCES_DIR}/bg2")).unwrap();
+    let locator = ResourceLocator::from_fs(fs).unwrap();
+    let entry = locator.entry("AR0714", ResourceType::Wed).unwrap();
+    c.bench_function("bifc_extract", |b| {
+        b.iter(|| locator.open_entry(black_box(entry)).unwrap())
+    });
+}
+
+criterion_group!(
+    benches,
+    wed_parse,
+    bam_v1_render,
+    bamc_decompress,
+    bifc_extract
+);
+criterion_main!(benches);
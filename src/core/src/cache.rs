@@ -0,0 +1,255 @@
+use std::{
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use crate::{
+    datasource::{DataSource, Importer},
+    resource::key::ResourceType,
+};
+
+/// An in-memory cache of parsed resources.
+///
+/// A resource is keyed by its uppercased name, its type and the SHA-1 hash of its content:
+/// importing it again returns the cached value, unless its bytes changed since the last import.
+/// Resources imported from a file can be keyed by the file size and modification time instead, see `get_or_import_file`.
+#[derive(Debug)]
+pub struct ResourceCache<T> {
+    entries: HashMap<(String, ResourceType), CacheEntry<T>>,
+}
+
+#[derive(Debug)]
+struct CacheEntry<T> {
+    version: Version,
+    value: Arc<T>,
+}
+
+/// Identifies the content a resource was imported from
+#[derive(Debug, PartialEq, Eq)]
+enum Version {
+    /// The SHA-1 hash of the content
+    Hash([u8; 20]),
+    /// The path, size and modification time of the file
+    File {
+        path: PathBuf,
+        len: u64,
+        modified: SystemTime,
+    },
+}
+
+impl<T> Default for ResourceCache<T> {
+    fn default() -> Self {
+        ResourceCache {
+            entries: HashMap::new(),
+        }
+    }
+}
+
+impl<T> ResourceCache<T> {
+    /// Creates an empty cache
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the cached resource if the content of the source did not change,
+    /// otherwise imports it with `I` and caches the result.
+    /// Import errors are not cached.
+    pub fn get_or_import<I: Importer<T = T>>(
+        &mut self,
+        name: &str,
+        ty: ResourceType,
+        source: &DataSource,
+    ) -> io::Result<Arc<T>> {
+        let version = Version::Hash(source.sha1()?);
+        self.get_or_insert(name, ty, version, || I::import(source))
+    }
+
+    /// Returns the cached resource if the file it was imported from did not change,
+    /// otherwise imports the file with `I` and caches the result.
+    /// The file is considered unchanged if its path, size and modification time are the same,
+    /// so a cache hit reads the file metadata only, not its content.
+    /// Import errors are not cached.
+    pub fn get_or_import_file<I: Importer<T = T>>(
+        &mut self,
+        name: &str,
+        ty: ResourceType,
+        path: &Path,
+    ) -> io::Result<Arc<T>> {
+        let metadata = std::fs::metadata(path)?;
+        let version = Version::File {
+            path: path.to_path_buf(),
+            len: metadata.len(),
+            modified: metadata.modified()?,
+        };
+        self.get_or_insert(name, ty, version, || I::import(&DataSource::new(path)))
+    }
+
+    /// Returns the cached resource if its version matches, otherwise imports and caches it
+    fn get_or_insert(
+        &mut self,
+        name: &str,
+        ty: ResourceType,
+        version: Version,
+        import: impl FnOnce() -> io::Result<T>,
+    ) -> io::Result<Arc<T>> {
+        let key = (name.to_uppercase(), ty);
+        if let Some(entry) = self
+            .entries
+            .get(&key)
+            .filter(|entry| entry.version == version)
+        {
+            return Ok(entry.value.clone());
+        }
+
+        let value = Arc::new(import()?);
+        self.entries.insert(
+            key,
+            CacheEntry {
+                version,
+                value: value.clone(),
+            },
+        );
+        Ok(value)
+    }
+
+    /// Removes a resource from the cache
+    pub fn remove(&mut self, name: &str, ty: ResourceType) -> Option<Arc<T>> {
+        self.entries
+            .remove(&(name.to_uppercase(), ty))
+            .map(|entry| entry.value)
+    }
+
+    /// Removes all the resources from the cache
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Returns the number of cached resources
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resource::src::{Src, SrcImporter};
+
+    fn src_bytes(weight: u32) -> Vec<u8> {
+        [1u32, 1000, weight]
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn test_identical_bytes_hit_the_cache() {
+        let mut cache = ResourceCache::<Src>::new();
+
+        let first = cache
+            .get_or_import::<SrcImporter>(
+                "morte1",
+                ResourceType::Src,
+                &DataSource::new(src_bytes(3)),
+            )
+            .unwrap();
+        // A different source with the same bytes, the name is matched case insensitively
+        let second = cache
+            .get_or_import::<SrcImporter>(
+                "MORTE1",
+                ResourceType::Src,
+                &DataSource::new(src_bytes(3)),
+            )
+            .unwrap();
+
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_changed_bytes_invalidate_the_cache() {
+        let mut cache = ResourceCache::<Src>::new();
+
+        let first = cache
+            .get_or_import::<SrcImporter>(
+                "MORTE1",
+                ResourceType::Src,
+                &DataSource::new(src_bytes(3)),
+            )
+            .unwrap();
+        let second = cache
+            .get_or_import::<SrcImporter>(
+                "MORTE1",
+                ResourceType::Src,
+                &DataSource::new(src_bytes(5)),
+            )
+            .unwrap();
+
+        assert!(!Arc::ptr_eq(&first, &second));
+        assert_eq!(first.entries[0].weight, 3);
+        assert_eq!(second.entries[0].weight, 5);
+        assert_eq!(cache.len(), 1);
+
+        // Errors are not cached
+        assert!(
+            cache
+                .get_or_import::<SrcImporter>(
+                    "MORTE1",
+                    ResourceType::Src,
+                    &DataSource::new(vec![9])
+                )
+                .is_err()
+        );
+        assert_eq!(cache.len(), 1);
+
+        assert!(cache.remove("morte1", ResourceType::Src).is_some());
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_file_hit_does_not_read_the_file() {
+        let path =
+            std::env::temp_dir().join(format!("infinitier_cache_{}.src", std::process::id()));
+        std::fs::write(&path, src_bytes(3)).unwrap();
+        let modified = std::fs::metadata(&path).unwrap().modified().unwrap();
+
+        let mut cache = ResourceCache::<Src>::new();
+        let first = cache
+            .get_or_import_file::<SrcImporter>("MORTE1", ResourceType::Src, &path)
+            .unwrap();
+
+        // Same size and modification time: the new content is not read
+        std::fs::write(&path, src_bytes(5)).unwrap();
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(modified).unwrap();
+        let second = cache
+            .get_or_import_file::<SrcImporter>("MORTE1", ResourceType::Src, &path)
+            .unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(second.entries[0].weight, 3);
+
+        // A new modification time invalidates the cache
+        file.set_modified(modified + std::time::Duration::from_secs(1))
+            .unwrap();
+        let third = cache
+            .get_or_import_file::<SrcImporter>("MORTE1", ResourceType::Src, &path)
+            .unwrap();
+        assert_eq!(third.entries[0].weight, 5);
+        assert_eq!(cache.len(), 1);
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(
+            cache
+                .get_or_import_file::<SrcImporter>("MORTE1", ResourceType::Src, &path)
+                .is_err()
+        );
+    }
+}
@@ -2,3 +2,6 @@
 pub const FILE_FOLDERS: [&str; 9] = [
     "data", "cache", "cd1", "cd2", "cd3", "cd4", "cd5", "cd6", "cd7",
 ];
+
+/// The folder holding the loose files that override the resources in the BIF files
+pub const OVERRIDE_FOLDER: &str = "override";
@@ -7,6 +7,10 @@ use std::{
 
 use encoding_rs::{Encoding, WINDOWS_1252};
 use flate2::bufread::ZlibDecoder;
+use sha1::{Digest, Sha1};
+
+/// The maximum number of records allocated before reading a table, see `Reader::read_table`
+const MAX_TABLE_PREALLOCATION: usize = 4096;
 
 /// A data importer.
 /// Parses data from a data source and returns the parsed data
@@ -16,6 +20,75 @@ pub trait Importer {
     fn import(source: &DataSource) -> std::io::Result<Self::T>;
 }
 
+/// The options of the importers supporting them
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImportOptions {
+    lenient_truncation: bool,
+}
+
+impl ImportOptions {
+    /// Creates the default options: any error fails the import
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether a truncated source returns what was parsed before the end of the data,
+    /// instead of failing the import
+    pub fn with_lenient_truncation(self, lenient_truncation: bool) -> Self {
+        ImportOptions { lenient_truncation }
+    }
+
+    /// Returns whether a truncated source returns what was parsed before the end of the data
+    pub fn lenient_truncation(&self) -> bool {
+        self.lenient_truncation
+    }
+
+    /// Completes an import that parsed `value` up to the end or up to an error.
+    /// A truncation error is returned with the partial value if the truncation is lenient.
+    pub(crate) fn complete<T>(
+        &self,
+        value: T,
+        result: std::io::Result<()>,
+    ) -> std::io::Result<PartialResult<T>> {
+        match result {
+            Ok(()) => Ok(PartialResult { value, error: None }),
+            Err(error)
+                if self.lenient_truncation && error.kind() == std::io::ErrorKind::UnexpectedEof =>
+            {
+                Ok(PartialResult {
+                    value,
+                    error: Some(error),
+                })
+            }
+            Err(error) => Err(error),
+        }
+    }
+}
+
+/// The result of an import which may have stopped before the end of the data.
+/// `value` holds the fields parsed before `error`, the other ones are empty.
+#[derive(Debug)]
+pub struct PartialResult<T> {
+    pub value: T,
+    /// The error that stopped the import, if any
+    pub error: Option<std::io::Error>,
+}
+
+impl<T> PartialResult<T> {
+    /// Returns whether the whole data was parsed
+    pub fn is_complete(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// Returns the value, or the error if the import stopped before the end of the data
+    pub fn into_result(self) -> std::io::Result<T> {
+        match self.error {
+            None => Ok(self.value),
+            Some(error) => Err(error),
+        }
+    }
+}
+
 /// A data source
 #[derive(Debug, Clone)]
 pub enum Data {
@@ -66,6 +139,15 @@ impl DataTrait for Cursor<&[u8]> {}
 impl<D: DataTrait> DataTrait for Take<D> {}
 
 impl Data {
+    /// Returns whether both are the same file, or the same shared buffer
+    fn is_same_as(&self, other: &Data) -> bool {
+        match (self, other) {
+            (Data::FileSource(path), Data::FileSource(other)) => path == other,
+            (Data::MemorySource(data), Data::MemorySource(other)) => Arc::ptr_eq(data, other),
+            _ => false,
+        }
+    }
+
     /// Returns a reader for the data
     pub fn data(
         &self,
@@ -171,6 +253,16 @@ impl DataSource {
         }
     }
 
+    /// Returns a data source over `limit` bytes of this one starting at `offset`, e.g. a file embedded in another one.
+    /// The offset is relative to the start of this data source.
+    pub fn embedded(&self, offset: u64, limit: Option<u64>) -> Self {
+        let base = match self {
+            DataSource::Full { .. } => 0,
+            DataSource::Embedded { offset, .. } => *offset,
+        };
+        self.clone().with_offset(base + offset, limit)
+    }
+
     /// Returns the encoding
     pub fn encoding(&self) -> &'static Encoding {
         match self {
@@ -197,6 +289,61 @@ impl DataSource {
             }),
         }
     }
+
+    /// Returns the SHA-1 hash of the content.
+    /// The content is streamed through the hasher without loading it in memory.
+    pub fn sha1(&self) -> std::io::Result<[u8; 20]> {
+        let mut hasher = Sha1::new();
+        self.for_each_chunk(|chunk| hasher.update(chunk))?;
+        Ok(hasher.finalize().into())
+    }
+
+    /// Returns whether the content of both data sources is the same.
+    /// Data sources over the same range of the same file or buffer are equal without reading them,
+    /// the other ones are compared by their SHA-1 hash.
+    /// As the same file is not opened, a missing or unreadable file is not detected in that case.
+    pub fn same_bytes_as(&self, other: &DataSource) -> std::io::Result<bool> {
+        let (data, offset, limit) = self.range();
+        let (other_data, other_offset, other_limit) = other.range();
+        if data.is_same_as(other_data) && offset == other_offset && limit == other_limit {
+            return Ok(true);
+        }
+        Ok(self.sha1()? == other.sha1()?)
+    }
+
+    /// Returns the data with the offset and the limit of the content
+    fn range(&self) -> (&Data, u64, Option<u64>) {
+        match self {
+            DataSource::Full { data, .. } => (data, 0, None),
+            DataSource::Embedded {
+                data,
+                offset,
+                limit,
+                ..
+            } => (data, *offset, *limit),
+        }
+    }
+
+    /// Returns the CRC-32 checksum of the content.
+    /// The content is streamed through the hasher without loading it in memory.
+    pub fn crc32(&self) -> std::io::Result<u32> {
+        let mut hasher = crc32fast::Hasher::new();
+        self.for_each_chunk(|chunk| hasher.update(chunk))?;
+        Ok(hasher.finalize())
+    }
+
+    /// Reads the content in chunks and passes them to the given function
+    fn for_each_chunk(&self, mut f: impl FnMut(&[u8])) -> std::io::Result<()> {
+        let mut reader = self.reader()?;
+        let mut buf = [0u8; 8192];
+        loop {
+            let n = reader.read(&mut buf)?;
+            if n == 0 {
+                return Ok(());
+            }
+            f(&buf[..n]);
+        }
+    }
 }
 
 /// A reader that reads a byte array with a specific encoding
@@ -252,6 +399,40 @@ impl<T: Read> Reader<T> {
         Ok(buf)
     }
 
+    /// Reads the data from the current position up to the end into memory and returns a seekable reader over it.
+    /// The position 0 of the returned reader is the current position of this one.
+    ///
+    /// This works for any reader, e.g. the non seekable one returned by `as_zip_reader`,
+    /// but the remaining data is always copied, even if the reader is already seekable.
+    pub fn into_seekable(mut self) -> std::io::Result<Reader<std::io::Cursor<Vec<u8>>>> {
+        let mut data = Vec::new();
+        self.data.read_to_end(&mut data)?;
+        Ok(Reader {
+            data: std::io::Cursor::new(data),
+            charset: self.charset,
+        })
+    }
+
+    /// Decodes the text from the current position up to the end and returns a reader over it, encoded as UTF-8.
+    /// A UTF-8 or UTF-16 byte order mark selects the encoding of the text and is removed,
+    /// otherwise the text is decoded with the Reader `charset`.
+    pub fn into_text_reader(mut self) -> std::io::Result<Reader<std::io::Cursor<Vec<u8>>>> {
+        let mut bytes = Vec::new();
+        self.data.read_to_end(&mut bytes)?;
+        // `decode` sniffs the BOM, which overrides the charset
+        let (text, encoding, had_errors) = self.charset.decode(&bytes);
+        if had_errors {
+            return Err(std::io::Error::other(format!(
+                "Decoding error: input is not valid {}",
+                encoding.name()
+            )));
+        }
+        Ok(Reader {
+            data: std::io::Cursor::new(text.into_owned().into_bytes()),
+            charset: encoding_rs::UTF_8,
+        })
+    }
+
     /// Read the first `n_bytes` bytes from a byte array and fill a buffer with them.
     pub fn read_to_end(&mut self, buf: &mut Vec<u8>, bytes: u64) -> std::io::Result<usize> {
         (&mut self.data).take(bytes).read_to_end(buf)
@@ -327,6 +508,23 @@ impl<T: Read + Seek> Reader<T> {
         self.data.seek(std::io::SeekFrom::Start(pos)) //.map(|pos| pos - self.offset)
     }
 
+    /// Reads a table of `count` fixed size records starting at the offset position, parsing each record with `parse`.
+    /// The cursor is left after the last record.
+    /// The count usually comes from the file, so at most `MAX_TABLE_PREALLOCATION` records are allocated upfront.
+    pub fn read_table<R>(
+        &mut self,
+        offset: u64,
+        count: usize,
+        mut parse: impl FnMut(&mut Self) -> std::io::Result<R>,
+    ) -> std::io::Result<Vec<R>> {
+        self.set_position(offset)?;
+        let mut records = Vec::with_capacity(count.min(MAX_TABLE_PREALLOCATION));
+        for _ in 0..count {
+            records.push(parse(self)?);
+        }
+        Ok(records)
+    }
+
     /// Reads a string from the offset position
     pub fn read_string_at(&mut self, offset: u64, size: u64) -> std::io::Result<String> {
         self.set_position(offset)?;
@@ -353,7 +551,10 @@ impl<T: Read + Seek> Reader<T> {
 }
 
 impl<T: BufRead> Reader<T> {
-    /// Returns a zip reader
+    /// Returns a reader decompressing the zlib stream starting at the current position.
+    ///
+    /// The returned reader is not seekable, so the formats that jump to absolute offsets
+    /// must be decoded in memory first with `decode_all` or `into_seekable`.
     pub fn as_zip_reader(&mut self) -> Reader<ZlibDecoder<&mut T>> {
         Reader {
             data: ZlibDecoder::new(&mut self.data),
@@ -398,6 +599,7 @@ impl<R: BufRead> Reader<ZlibDecoder<R>> {
 mod tests {
 
     use super::*;
+    use std::io::Write;
 
     #[test]
     fn test_read_string() {
@@ -428,6 +630,162 @@ mod tests {
         assert_eq!(reader.read_string(5).unwrap(), "orld!");
     }
 
+    #[test]
+    fn test_hashes() {
+        let source = DataSource::new("abc".as_bytes());
+        assert_eq!(
+            source.sha1().unwrap(),
+            [
+                0xa9, 0x99, 0x3e, 0x36, 0x47, 0x06, 0x81, 0x6a, 0xba, 0x3e, 0x25, 0x71, 0x78, 0x50,
+                0xc2, 0x6c, 0x9c, 0xd0, 0xd8, 0x9d
+            ]
+        );
+        assert_eq!(source.crc32().unwrap(), 0x352441c2);
+
+        // Only the embedded content is hashed
+        let embedded = DataSource::new_with_offset("xxabcxx".as_bytes(), 2, Some(3));
+        assert_eq!(embedded.sha1().unwrap(), source.sha1().unwrap());
+        assert_eq!(embedded.crc32().unwrap(), source.crc32().unwrap());
+    }
+
+    #[test]
+    fn test_hashes_of_file_and_memory_sources() {
+        let path = format!(
+            "{}/resources/WFX/FIREBAL1.WFX",
+            crate::test_utils::RESOURCES_DIR
+        );
+        let file = DataSource::new(path.as_str());
+        let memory = DataSource::new(std::fs::read(&path).unwrap());
+
+        assert_eq!(file.sha1().unwrap(), memory.sha1().unwrap());
+        assert_eq!(file.crc32().unwrap(), memory.crc32().unwrap());
+
+        let file = DataSource::new_with_offset(path.as_str(), 8, None);
+        let memory = DataSource::new_with_offset(std::fs::read(&path).unwrap(), 8, None);
+        assert_eq!(file.sha1().unwrap(), memory.sha1().unwrap());
+        assert_ne!(
+            file.sha1().unwrap(),
+            DataSource::new(path.as_str()).sha1().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_same_bytes_as() {
+        let path = format!(
+            "{}/resources/WFX/FIREBAL1.WFX",
+            crate::test_utils::RESOURCES_DIR
+        );
+        let file = DataSource::new(path.as_str());
+
+        // Same file, compared without reading it
+        assert!(file.same_bytes_as(&file.clone()).unwrap());
+        assert!(
+            file.same_bytes_as(&DataSource::new_with_offset(path.as_str(), 0, None))
+                .unwrap()
+        );
+
+        // Same content from a different origin, compared by hash
+        let memory = DataSource::new(std::fs::read(&path).unwrap());
+        assert!(file.same_bytes_as(&memory).unwrap());
+
+        // Different offsets of the same file
+        let header = file.embedded(0, Some(8));
+        assert!(!file.same_bytes_as(&header).unwrap());
+        assert!(!header.same_bytes_as(&file.embedded(8, Some(8))).unwrap());
+
+        // Different offsets with the same content
+        let source = DataSource::new("abcabc".as_bytes());
+        assert!(
+            source
+                .embedded(0, Some(3))
+                .same_bytes_as(&source.embedded(3, Some(3)))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_into_seekable_from_seekable_reader() {
+        let source = DataSource::new("Hello, world!".as_bytes());
+        let mut reader = source.reader().unwrap();
+        reader.skip(7).unwrap();
+
+        let mut reader = reader.into_seekable().unwrap();
+        assert_eq!(reader.read_string_at(0, 5).unwrap(), "world");
+        assert_eq!(reader.read_string_at(5, 1).unwrap(), "!");
+    }
+
+    #[test]
+    fn test_into_seekable_from_zlib_reader() {
+        let mut encoder =
+            flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&[1, 0, 0, 0, 2, 0, 0, 0]).unwrap();
+        let mut data = b"ZLIB".to_vec();
+        data.extend(encoder.finish().unwrap());
+
+        let source = DataSource::new(data);
+        let mut reader = source.reader().unwrap();
+        assert_eq!(reader.read_string(4).unwrap(), "ZLIB");
+
+        let mut reader = reader.as_zip_reader().into_seekable().unwrap();
+        assert_eq!(reader.read_u32_at(4).unwrap(), 2);
+        assert_eq!(reader.read_u32_at(0).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_into_text_reader_detects_bom() {
+        let windows_1252 = DataSource::new(b"Caf\xe9\r\n".as_slice());
+        let mut reader = windows_1252.reader().unwrap().into_text_reader().unwrap();
+        assert_eq!(reader.read_line().unwrap().0, "Café\r\n");
+
+        let utf8 = DataSource::new(b"\xef\xbb\xbfCaf\xc3\xa9".as_slice());
+        let mut reader = utf8.reader().unwrap().into_text_reader().unwrap();
+        assert_eq!(reader.read_line().unwrap().0, "Café");
+
+        let utf16 = [0xfeff, 'C' as u16, 'a' as u16, 'f' as u16, 'é' as u16]
+            .iter()
+            .flat_map(|unit| unit.to_le_bytes())
+            .collect::<Vec<_>>();
+        let mut reader = DataSource::new(utf16)
+            .reader()
+            .unwrap()
+            .into_text_reader()
+            .unwrap();
+        assert_eq!(reader.read_line().unwrap().0, "Café");
+    }
+
+    #[test]
+    fn test_read_table() {
+        let mut bytes = vec![0xff; 3];
+        for value in [1u32, 2, 3] {
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes.push(0xee);
+        let source = DataSource::new(bytes);
+        let mut reader = source.reader().unwrap();
+
+        assert_eq!(
+            reader.read_table(3, 3, |reader| reader.read_u32()).unwrap(),
+            vec![1, 2, 3]
+        );
+        assert_eq!(reader.position().unwrap(), 15);
+        assert_eq!(reader.read_u8().unwrap(), 0xee);
+
+        assert!(
+            reader
+                .read_table(3, 0, |reader| reader.read_u32())
+                .unwrap()
+                .is_empty()
+        );
+        // The table overflows the data
+        assert!(reader.read_table(3, 4, |reader| reader.read_u32()).is_err());
+        // A bogus count fails on the missing data instead of allocating it
+        assert!(
+            reader
+                .read_table(3, usize::MAX, |reader| reader.read_u32())
+                .is_err()
+        );
+    }
+
     #[test]
     fn test_read_string_at() {
         let reader = DataSource::new("Hello, world!".as_bytes());
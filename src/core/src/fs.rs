@@ -7,13 +7,57 @@ use std::sync::Arc;
 use serde::Deserialize;
 use serde::Serialize;
 
-use crate::constants::FILE_FOLDERS;
+use crate::constants::{FILE_FOLDERS, OVERRIDE_FOLDER};
+use crate::datasource::{Data, DataSource};
+use crate::resource::key::ResourceType;
+
+/// The lookup surface shared by the case insensitive file systems
+pub trait FileSystem {
+    /// Returns the absolute path of the file or directory with the given path relative to root.
+    /// The path is matched case insensitively
+    fn get_path_opt(&self, path: &CaseInsensitivePath) -> Option<PathBuf>;
+
+    /// Searches for a path in the root directory, if it does not exists, it search in a set of predefined folders
+    fn search_path_opt(&self, path: &CaseInsensitivePath) -> Option<PathBuf>;
+
+    /// Tries to get the absolute path of the file or directory with the given path relative to root.
+    /// The path is matched case insensitively. If the path is not found, an `io::Error` is returned.
+    fn get_path(&self, path: &CaseInsensitivePath) -> io::Result<PathBuf> {
+        match self.get_path_opt(path) {
+            Some(path) => Ok(path),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("File not found: {}", path.path),
+            )),
+        }
+    }
+
+    /// Returns the data of the file with the given path relative to root.
+    /// The path is matched case insensitively
+    fn open_opt(&self, path: &CaseInsensitivePath) -> Option<DataSource> {
+        self.get_path_opt(path).map(DataSource::new)
+    }
+
+    /// Tries to get the data of the file with the given path relative to root.
+    /// The path is matched case insensitively. If the path is not found, an `io::Error` is returned.
+    fn open(&self, path: &CaseInsensitivePath) -> io::Result<DataSource> {
+        match self.open_opt(path) {
+            Some(source) => Ok(source),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("File not found: {}", path.path),
+            )),
+        }
+    }
+}
 
 /// A file system that is case insensitive
 #[derive(Debug, Clone)]
 pub struct CaseInsensitiveFS {
     root: PathBuf,
     paths: Arc<BTreeMap<String, PathBuf>>,
+    /// The lowercased folders searched by `resolve_override`, in order of precedence
+    override_dirs: Vec<String>,
 }
 
 impl CaseInsensitiveFS {
@@ -28,7 +72,59 @@ impl CaseInsensitiveFS {
         let root = root.as_ref().canonicalize()?;
         let paths = Arc::new(list_real_entries_recursive(&root)?);
         // println!("paths: \n{:#?}", paths);
-        Ok(CaseInsensitiveFS { root, paths })
+        Ok(CaseInsensitiveFS {
+            root,
+            paths,
+            override_dirs: vec![OVERRIDE_FOLDER.to_owned()],
+        })
+    }
+
+    /// Adds folders, relative to root, searched by `resolve_override` after the `override` folder
+    pub fn with_override_dirs(mut self, dirs: &[&str]) -> Self {
+        self.override_dirs.extend(dirs.iter().map(|dir| {
+            CaseInsensitivePath::new(dir)
+                .as_str()
+                .trim_end_matches('/')
+                .to_owned()
+        }));
+        self
+    }
+
+    /// Returns the lowercased folders searched by `resolve_override`, in order of precedence
+    pub fn override_dirs(&self) -> &[String] {
+        &self.override_dirs
+    }
+
+    /// Rescans the whole directory tree, picking up the entries created or removed after the construction.
+    /// The clones of this file system keep the previous snapshot.
+    pub fn refresh(&mut self) -> io::Result<()> {
+        self.paths = Arc::new(list_real_entries_recursive(&self.root)?);
+        Ok(())
+    }
+
+    /// Rescans only the given directory relative to root, and everything underneath it.
+    /// If the directory no longer exists, its entries are removed.
+    pub fn refresh_dir(&mut self, rel_dir: &str) -> io::Result<()> {
+        let dir = CaseInsensitivePath::new(rel_dir);
+        let dir = dir.as_str().trim_end_matches('/');
+        if dir.is_empty() {
+            return self.refresh();
+        }
+
+        let real_dir = match self.paths.get(dir) {
+            Some(path) => path.clone(),
+            None => self.root.join(dir),
+        };
+        let prefix = format!("{dir}/");
+        let paths = Arc::make_mut(&mut self.paths);
+        paths.retain(|path, _| path != dir && !path.starts_with(&prefix));
+
+        if real_dir.is_dir() {
+            let real_dir = real_dir.canonicalize()?;
+            recurse(&self.root, &real_dir, paths)?;
+            paths.insert(dir.to_owned(), real_dir);
+        }
+        Ok(())
     }
 
     /// Returns the root directory of the file system
@@ -68,6 +164,193 @@ impl CaseInsensitiveFS {
         }
         None
     }
+
+    /// Returns the path of a loose resource in the override folders.
+    /// The engine prefers these files to the resources with the same name stored in the BIF files,
+    /// so they must be searched before them.
+    pub fn resolve_override(&self, name: &str, ty: ResourceType) -> Option<PathBuf> {
+        let ext = ty.file_extension()?;
+        let file_name = CaseInsensitivePath::new(&format!("{name}.{ext}"));
+        self.override_dirs.iter().find_map(|dir| {
+            self.paths
+                .get(&format!("{}/{}", dir, file_name.as_str()))
+                .cloned()
+        })
+    }
+
+    /// Returns the absolute paths of all the entries with the given extension, matched case insensitively.
+    /// The extension can be given with or without the leading dot.
+    pub fn list_by_extension(&self, ext: &str) -> Vec<PathBuf> {
+        let suffix = format!(".{}", ext.trim().trim_start_matches('.').to_lowercase());
+        self.paths
+            .iter()
+            .filter(|(path, _)| path.ends_with(&suffix))
+            .map(|(_, path)| path.clone())
+            .collect()
+    }
+
+    /// Returns the absolute paths of the entries directly contained in the given directory relative to root.
+    /// The directory is matched case insensitively, an empty path lists the root directory.
+    pub fn list_in_dir(&self, rel_dir: &str) -> Vec<PathBuf> {
+        let dir = CaseInsensitivePath::new(rel_dir);
+        let dir = dir.as_str().trim_end_matches('/');
+        let prefix = if dir.is_empty() {
+            String::new()
+        } else {
+            format!("{dir}/")
+        };
+        // The keys are sorted, so the entries of the directory are contiguous
+        self.paths
+            .range(prefix.clone()..)
+            .take_while(|(path, _)| path.starts_with(&prefix))
+            .filter(|(path, _)| !path[prefix.len()..].contains('/'))
+            .map(|(_, path)| path.clone())
+            .collect()
+    }
+}
+
+impl FileSystem for CaseInsensitiveFS {
+    fn get_path_opt(&self, path: &CaseInsensitivePath) -> Option<PathBuf> {
+        CaseInsensitiveFS::get_path_opt(self, path)
+    }
+
+    fn search_path_opt(&self, path: &CaseInsensitivePath) -> Option<PathBuf> {
+        CaseInsensitiveFS::search_path_opt(self, path)
+    }
+}
+
+/// A file system keeping its files in memory, e.g. generated or edited resources not yet written to disk.
+/// Its files have no path, so they can only be read through `FileSystem::open_opt`.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryFS {
+    files: BTreeMap<String, Arc<Vec<u8>>>,
+}
+
+impl MemoryFS {
+    /// Creates an empty `MemoryFS`
+    pub fn new() -> Self {
+        MemoryFS::default()
+    }
+
+    /// Inserts a file with the given path, replacing the previous one if present
+    pub fn insert(&mut self, name: &str, data: Vec<u8>) {
+        self.files
+            .insert(CaseInsensitivePath::new(name).path, Arc::new(data));
+    }
+
+    /// Removes the file with the given path, returns whether it was present
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.files
+            .remove(CaseInsensitivePath::new(name).as_str())
+            .is_some()
+    }
+}
+
+impl FileSystem for MemoryFS {
+    fn get_path_opt(&self, _path: &CaseInsensitivePath) -> Option<PathBuf> {
+        None
+    }
+
+    fn search_path_opt(&self, _path: &CaseInsensitivePath) -> Option<PathBuf> {
+        None
+    }
+
+    fn open_opt(&self, path: &CaseInsensitivePath) -> Option<DataSource> {
+        self.files
+            .get(path.as_str())
+            .map(|data| DataSource::new(Data::MemorySource(data.clone())))
+    }
+}
+
+/// A layer of a `LayeredFS`
+#[derive(Debug, Clone)]
+pub enum FsLayer {
+    Disk(CaseInsensitiveFS),
+    Memory(MemoryFS),
+}
+
+impl From<CaseInsensitiveFS> for FsLayer {
+    fn from(value: CaseInsensitiveFS) -> Self {
+        FsLayer::Disk(value)
+    }
+}
+
+impl From<MemoryFS> for FsLayer {
+    fn from(value: MemoryFS) -> Self {
+        FsLayer::Memory(value)
+    }
+}
+
+impl FileSystem for FsLayer {
+    fn get_path_opt(&self, path: &CaseInsensitivePath) -> Option<PathBuf> {
+        match self {
+            FsLayer::Disk(fs) => fs.get_path_opt(path),
+            FsLayer::Memory(fs) => fs.get_path_opt(path),
+        }
+    }
+
+    fn search_path_opt(&self, path: &CaseInsensitivePath) -> Option<PathBuf> {
+        match self {
+            FsLayer::Disk(fs) => fs.search_path_opt(path),
+            FsLayer::Memory(fs) => fs.search_path_opt(path),
+        }
+    }
+
+    fn open_opt(&self, path: &CaseInsensitivePath) -> Option<DataSource> {
+        match self {
+            FsLayer::Disk(fs) => fs.open_opt(path),
+            FsLayer::Memory(fs) => fs.open_opt(path),
+        }
+    }
+}
+
+/// A file system made of several layers, e.g. a game installation, its mods and the in-memory edits.
+/// The layers are ordered by priority, so a file in a later layer shadows the one with the same path in the earlier ones.
+#[derive(Debug, Clone, Default)]
+pub struct LayeredFS {
+    layers: Vec<FsLayer>,
+}
+
+impl LayeredFS {
+    /// Creates a new `LayeredFS` from the given layers, the first one being the base
+    pub fn new<L: Into<FsLayer>>(layers: impl IntoIterator<Item = L>) -> Self {
+        LayeredFS {
+            layers: layers.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Adds a layer on top of the existing ones
+    pub fn push_layer<L: Into<FsLayer>>(&mut self, layer: L) {
+        self.layers.push(layer.into());
+    }
+
+    /// Returns the layers, from the base to the top one
+    pub fn layers(&self) -> &[FsLayer] {
+        &self.layers
+    }
+}
+
+impl FileSystem for LayeredFS {
+    fn get_path_opt(&self, path: &CaseInsensitivePath) -> Option<PathBuf> {
+        self.layers
+            .iter()
+            .rev()
+            .find_map(|layer| layer.get_path_opt(path))
+    }
+
+    fn search_path_opt(&self, path: &CaseInsensitivePath) -> Option<PathBuf> {
+        self.layers
+            .iter()
+            .rev()
+            .find_map(|layer| layer.search_path_opt(path))
+    }
+
+    fn open_opt(&self, path: &CaseInsensitivePath) -> Option<DataSource> {
+        self.layers
+            .iter()
+            .rev()
+            .find_map(|layer| layer.open_opt(path))
+    }
 }
 
 // fn find_bif_file(fs: &CaseInsensitiveFS, file_name: &str) -> Option<PathBuf> {
@@ -142,7 +425,9 @@ fn recurse(root: &Path, path: &Path, results: &mut BTreeMap<String, PathBuf>) ->
 
 #[cfg(test)]
 mod tests {
-    use crate::test_utils::{BG_RESOURCES_DIR, IWD_RESOURCES_DIR};
+    use crate::datasource::Importer;
+    use crate::resource::two_da::TwoDAImporter;
+    use crate::test_utils::{BG_RESOURCES_DIR, IWD_RESOURCES_DIR, RESOURCES_DIR};
 
     use super::*;
 
@@ -223,4 +508,227 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn test_resolve_override() {
+        let root = format!("{RESOURCES_DIR}/resources/FS");
+        let fs = CaseInsensitiveFS::new(&root).unwrap();
+
+        // The override copy wins over the one in the data folder
+        assert_eq!(
+            fs.resolve_override("sw1h99", ResourceType::Itm),
+            Some(
+                PathBuf::from(&root)
+                    .join("override/SW1H99.ITM")
+                    .canonicalize()
+                    .unwrap()
+            )
+        );
+        assert_eq!(
+            fs.resolve_override("XPCAP", ResourceType::TwoDA),
+            Some(
+                PathBuf::from(&root)
+                    .join("override/XPCAP.2DA")
+                    .canonicalize()
+                    .unwrap()
+            )
+        );
+        assert_eq!(fs.resolve_override("sw1h98", ResourceType::Itm), None);
+        assert_eq!(fs.resolve_override("sw1h99", ResourceType::Spl), None);
+        assert_eq!(
+            fs.resolve_override("sw1h99", ResourceType::Unknown(0)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_resolve_override_in_extra_dirs() {
+        let root = format!("{RESOURCES_DIR}/resources/FS");
+        let fs = CaseInsensitiveFS::new(&root)
+            .unwrap()
+            .with_override_dirs(&["mymod/"]);
+
+        // The extra folders are searched after the override folder
+        assert_eq!(
+            fs.resolve_override("SW1H99", ResourceType::Itm),
+            Some(
+                PathBuf::from(&root)
+                    .join("override/SW1H99.ITM")
+                    .canonicalize()
+                    .unwrap()
+            )
+        );
+        assert_eq!(
+            fs.resolve_override("SW1H98", ResourceType::Itm),
+            Some(
+                PathBuf::from(&root)
+                    .join("MyMod/SW1H98.ITM")
+                    .canonicalize()
+                    .unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn test_list_by_extension() {
+        let root = format!("{RESOURCES_DIR}/resources/FS");
+        let fs = CaseInsensitiveFS::new(&root).unwrap();
+
+        assert_eq!(fs.list_by_extension("itm").len(), 4);
+        assert_eq!(fs.list_by_extension(".ITM").len(), 4);
+        assert_eq!(
+            fs.list_by_extension("2da"),
+            vec![
+                PathBuf::from(&root)
+                    .join("override/XPCAP.2DA")
+                    .canonicalize()
+                    .unwrap()
+            ]
+        );
+        assert!(fs.list_by_extension("spl").is_empty());
+    }
+
+    #[test]
+    fn test_list_in_dir() {
+        let root = format!("{RESOURCES_DIR}/resources/FS");
+        let fs = CaseInsensitiveFS::new(&root).unwrap();
+
+        assert_eq!(fs.list_in_dir("").len(), 3);
+        assert_eq!(fs.list_in_dir("/").len(), 3);
+        assert_eq!(fs.list_in_dir("OVERRIDE").len(), 2);
+        assert_eq!(
+            fs.list_in_dir("mymod/"),
+            vec![
+                PathBuf::from(&root)
+                    .join("MyMod/SW1H98.ITM")
+                    .canonicalize()
+                    .unwrap(),
+                PathBuf::from(&root)
+                    .join("MyMod/sw1h99.itm")
+                    .canonicalize()
+                    .unwrap(),
+            ]
+        );
+        assert!(fs.list_in_dir("missing").is_empty());
+    }
+
+    #[test]
+    fn test_refresh() {
+        let root =
+            std::env::temp_dir().join(format!("infinitier_fs_refresh_{}", std::process::id()));
+        fs::create_dir_all(root.join("Override")).unwrap();
+        let mut fs = CaseInsensitiveFS::new(&root).unwrap();
+        let path = CaseInsensitivePath::new("override/NEW.ITM");
+        assert!(fs.get_path_opt(&path).is_none());
+
+        fs::write(root.join("Override/NEW.ITM"), b"ITM V1  ").unwrap();
+        assert!(fs.get_path_opt(&path).is_none());
+
+        fs.refresh_dir("OVERRIDE").unwrap();
+        assert_eq!(
+            fs.get_path_opt(&path),
+            Some(root.join("Override/NEW.ITM").canonicalize().unwrap())
+        );
+
+        fs::remove_file(root.join("Override/NEW.ITM")).unwrap();
+        fs::write(root.join("chitin.key"), b"KEY V1  ").unwrap();
+        fs.refresh().unwrap();
+        assert!(fs.get_path_opt(&path).is_none());
+        assert!(
+            fs.get_path_opt(&CaseInsensitivePath::new("CHITIN.KEY"))
+                .is_some()
+        );
+        assert_eq!(fs.get_root(), root.canonicalize().unwrap());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_layered_fs_shadows_base_files() {
+        let base_root = format!("{RESOURCES_DIR}/resources/FS");
+        let mod_root = format!("{RESOURCES_DIR}/resources/FS_MOD");
+        let fs = LayeredFS::new(vec![
+            CaseInsensitiveFS::new(&base_root).unwrap(),
+            CaseInsensitiveFS::new(&mod_root).unwrap(),
+        ]);
+
+        // The top layer shadows the base one
+        assert_eq!(
+            fs.get_path_opt(&CaseInsensitivePath::new("override/sw1h99.itm")),
+            Some(
+                PathBuf::from(&mod_root)
+                    .join("Override/SW1H99.ITM")
+                    .canonicalize()
+                    .unwrap()
+            )
+        );
+        // The files missing from the top layer are found in the base one
+        assert_eq!(
+            fs.get_path(&CaseInsensitivePath::new("OVERRIDE/XPCAP.2DA"))
+                .unwrap(),
+            PathBuf::from(&base_root)
+                .join("override/XPCAP.2DA")
+                .canonicalize()
+                .unwrap()
+        );
+        assert_eq!(
+            fs.search_path_opt(&CaseInsensitivePath::new("sw1h99.itm")),
+            Some(
+                PathBuf::from(&base_root)
+                    .join("data/SW1H99.ITM")
+                    .canonicalize()
+                    .unwrap()
+            )
+        );
+        assert_eq!(
+            fs.search_path_opt(&CaseInsensitivePath::new("sw1h97.itm")),
+            Some(
+                PathBuf::from(&mod_root)
+                    .join("Data/SW1H97.ITM")
+                    .canonicalize()
+                    .unwrap()
+            )
+        );
+        assert!(
+            fs.get_path(&CaseInsensitivePath::new("override/missing.itm"))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_layered_fs_with_memory_layer() {
+        let base_root = format!("{RESOURCES_DIR}/resources/FS");
+        let mut memory = MemoryFS::new();
+        memory.insert(
+            "Override/XPCAP.2da",
+            b"2DA V1.0\n0\n      VALUE\nLEVEL  42\n".to_vec(),
+        );
+        let mut fs = LayeredFS::new([CaseInsensitiveFS::new(&base_root).unwrap()]);
+        fs.push_layer(memory);
+
+        // The memory file shadows the one on disk, but it has no path
+        let path = CaseInsensitivePath::new("override/xpcap.2da");
+        assert_eq!(
+            fs.get_path_opt(&path),
+            Some(
+                PathBuf::from(&base_root)
+                    .join("override/XPCAP.2DA")
+                    .canonicalize()
+                    .unwrap()
+            )
+        );
+        let two_da = TwoDAImporter::import(&fs.open(&path).unwrap()).unwrap();
+        assert_eq!(two_da.headers, vec!["VALUE".to_string()]);
+        assert_eq!(two_da.rows.get("LEVEL"), Some(&vec!["42".to_string()]));
+
+        // The files missing from memory are read from disk
+        assert!(
+            fs.open_opt(&CaseInsensitivePath::new("data/sw1h99.itm"))
+                .is_some()
+        );
+        assert!(
+            fs.open(&CaseInsensitivePath::new("override/missing.2da"))
+                .is_err()
+        );
+    }
 }
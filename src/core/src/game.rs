@@ -0,0 +1,204 @@
+use std::{io, path::Path};
+
+use crate::{
+    datasource::DataSource,
+    fs::CaseInsensitiveFS,
+    locator::ResourceLocator,
+    resource::key::{Key, ResourceType},
+    strings::{Gender, StringResolver},
+};
+
+/// The locale of the talk tables loaded by `GameResources::open` for the Enhanced Editions
+pub const DEFAULT_LOCALE: &str = "en_US";
+
+/// The Infinity Engine games
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameVersion {
+    Bg,
+    BgEe,
+    Bg2,
+    Bg2Ee,
+    Iwd,
+    IwdEe,
+    Iwd2,
+    Pst,
+    PstEe,
+}
+
+impl GameVersion {
+    /// Returns whether the game is an Enhanced Edition
+    pub fn is_enhanced_edition(&self) -> bool {
+        matches!(
+            self,
+            GameVersion::BgEe | GameVersion::Bg2Ee | GameVersion::IwdEe | GameVersion::PstEe
+        )
+    }
+
+    /// Returns whether the game can ship a female talk table.
+    /// Planescape: Torment only has a male one, as its protagonist is always male.
+    pub fn has_female_tlk(&self) -> bool {
+        !matches!(self, GameVersion::Pst)
+    }
+
+    /// Returns the paths of the male and female talk tables, relative to the game root.
+    /// The Enhanced Editions store a pair of talk tables for each locale in `lang/<locale>`, e.g. `lang/en_US`,
+    /// the other games store a single pair in the root folder and ignore the locale.
+    pub fn tlk_paths(&self, locale: &str) -> (String, String) {
+        if self.is_enhanced_edition() {
+            (
+                format!("lang/{locale}/dialog.tlk"),
+                format!("lang/{locale}/dialogf.tlk"),
+            )
+        } else {
+            ("dialog.tlk".to_string(), "dialogf.tlk".to_string())
+        }
+    }
+
+    /// Detects the game from the resources listed in its KEY file.
+    /// The Enhanced Editions are told apart by their menu resources, the games by an area only they have.
+    pub fn detect(key: &Key) -> Option<GameVersion> {
+        let has_resource = |name: &str, ty: ResourceType| {
+            key.resource_entries
+                .iter()
+                .any(|entry| entry.r#type == ty && entry.resource_name.eq_ignore_ascii_case(name))
+        };
+        let enhanced_edition = key
+            .resource_entries
+            .iter()
+            .any(|entry| entry.r#type == ResourceType::Menu);
+
+        let area = |name: &str| has_resource(name, ResourceType::Are);
+        let version = if area("AR0104A") {
+            GameVersion::Pst
+        } else if area("AR4102") {
+            GameVersion::Iwd2
+        } else if area("AR1012") {
+            GameVersion::Iwd
+        } else if area("AR0072") {
+            GameVersion::Bg2
+        } else if area("AR0002") {
+            GameVersion::Bg
+        } else {
+            return None;
+        };
+
+        Some(match (version, enhanced_edition) {
+            (GameVersion::Bg, true) => GameVersion::BgEe,
+            (GameVersion::Bg2, true) => GameVersion::Bg2Ee,
+            (GameVersion::Iwd, true) => GameVersion::IwdEe,
+            (GameVersion::Pst, true) => GameVersion::PstEe,
+            (version, _) => version,
+        })
+    }
+}
+
+/// The resources and the strings of a game installation
+#[derive(Debug)]
+pub struct GameResources {
+    version: GameVersion,
+    locator: ResourceLocator,
+    strings: StringResolver,
+}
+
+impl GameResources {
+    /// Opens the game installed in the given folder, loading the talk tables of `DEFAULT_LOCALE`
+    pub fn open(game_dir: &Path) -> io::Result<GameResources> {
+        GameResources::open_with_locale(game_dir, DEFAULT_LOCALE)
+    }
+
+    /// Opens the game installed in the given folder, loading the talk tables of the given locale.
+    /// The locale is only used by the Enhanced Editions.
+    pub fn open_with_locale(game_dir: &Path, locale: &str) -> io::Result<GameResources> {
+        let locator = ResourceLocator::from_fs(CaseInsensitiveFS::new(game_dir)?)?;
+        let version = GameVersion::detect(locator.key())
+            .ok_or_else(|| io::Error::other(format!("Unknown game in {}", game_dir.display())))?;
+        let strings = StringResolver::load(locator.fs(), version, locale)?;
+        Ok(GameResources {
+            version,
+            locator,
+            strings,
+        })
+    }
+
+    /// Returns the detected game
+    pub fn game_version(&self) -> GameVersion {
+        self.version
+    }
+
+    /// Opens a resource, preferring the loose file in the override folders to the one stored in the BIF files
+    pub fn open_resource(&self, name: &str, ty: ResourceType) -> io::Result<DataSource> {
+        self.locator.open(name, ty)
+    }
+
+    /// Returns the text of a StrRef for the given gender
+    pub fn resolve_string(&self, strref: u32, gender: Gender) -> Option<String> {
+        self.strings.resolve(strref, gender)
+    }
+
+    /// Returns the resource locator
+    pub fn locator(&self) -> &ResourceLocator {
+        &self.locator
+    }
+
+    /// Returns the string resolver
+    pub fn strings(&self) -> &StringResolver {
+        &self.strings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        datasource::Importer,
+        fs::CaseInsensitivePath,
+        resource::key::KeyImporter,
+        test_utils::{
+            BG_EE_RESOURCES_DIR, BG_RESOURCES_DIR, BG2_EE_RESOURCES_DIR, BG2_RESOURCES_DIR,
+            IWD_EE_RESOURCES_DIR, IWD_RESOURCES_DIR, IWD2_RESOURCES_DIR, PST_EE_RESOURCES_DIR,
+            PST_RESOURCES_DIR,
+        },
+    };
+
+    #[test]
+    fn test_detect_game_version() {
+        for (dir, expected) in [
+            (BG_RESOURCES_DIR, GameVersion::Bg),
+            (BG_EE_RESOURCES_DIR, GameVersion::BgEe),
+            (BG2_RESOURCES_DIR, GameVersion::Bg2),
+            (BG2_EE_RESOURCES_DIR, GameVersion::Bg2Ee),
+            (IWD_RESOURCES_DIR, GameVersion::Iwd),
+            (IWD_EE_RESOURCES_DIR, GameVersion::IwdEe),
+            (IWD2_RESOURCES_DIR, GameVersion::Iwd2),
+            (PST_RESOURCES_DIR, GameVersion::Pst),
+            (PST_EE_RESOURCES_DIR, GameVersion::PstEe),
+        ] {
+            let fs = CaseInsensitiveFS::new(dir).unwrap();
+            let key = fs
+                .get_path(&CaseInsensitivePath::new("chitin.key"))
+                .map(DataSource::new)
+                .and_then(|source| KeyImporter::import(&source))
+                .unwrap();
+            assert_eq!(GameVersion::detect(&key), Some(expected), "{dir}");
+        }
+    }
+
+    #[test]
+    fn test_open_game_resources() {
+        let game = GameResources::open(Path::new(BG_RESOURCES_DIR)).unwrap();
+
+        assert_eq!(game.game_version(), GameVersion::Bg);
+
+        let wed = game.open_resource("AR3802", ResourceType::Wed).unwrap();
+        assert_eq!(wed.reader().unwrap().read_string(8).unwrap(), "WED V1.3");
+
+        // BG has no female talk table
+        assert_eq!(
+            game.resolve_string(1, Gender::Female).as_deref(),
+            Some("Hello, sir.")
+        );
+
+        // The talk table is missing
+        assert!(GameResources::open(Path::new(BG2_RESOURCES_DIR)).is_err());
+    }
+}
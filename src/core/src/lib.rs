@@ -1,7 +1,11 @@
+pub mod cache;
 pub mod constants;
 pub mod datasource;
 pub mod fs;
+pub mod game;
+pub mod locator;
 pub mod resource;
+pub mod strings;
 
 #[cfg(test)]
 pub mod test_utils;
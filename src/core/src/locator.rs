@@ -0,0 +1,559 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::{
+    datasource::{DataSource, Importer},
+    fs::{CaseInsensitiveFS, CaseInsensitivePath},
+    resource::{
+        bif::BifImporter,
+        key::{BifEntry, Key, KeyImporter, ResourceEntry, ResourceType},
+    },
+};
+
+/// The KEY file of a game installation
+const KEY_FILE_NAME: &str = "chitin.key";
+
+/// Locates the resources of a game installation.
+/// A resource is read from the override folders if present there, otherwise from the BIF file referenced by the KEY file.
+#[derive(Debug)]
+pub struct ResourceLocator {
+    fs: CaseInsensitiveFS,
+    key: Key,
+    /// The index of the KEY resource entries by uppercased name and type
+    entries: HashMap<(String, ResourceType), usize>,
+    /// The paths of the BIF files of the KEY file, `None` if the file is not installed
+    bif_paths: Vec<Option<PathBuf>>,
+}
+
+impl ResourceLocator {
+    /// Creates a new `ResourceLocator` from the game file system and its KEY file
+    pub fn new(fs: CaseInsensitiveFS, key: Key) -> Self {
+        let entries = key
+            .resource_entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| ((entry.resource_name.to_uppercase(), entry.r#type), index))
+            .collect();
+        let bif_paths = key
+            .bif_entries
+            .iter()
+            .map(|entry| search_bif(&fs, &entry.file_name))
+            .collect();
+        ResourceLocator {
+            fs,
+            key,
+            entries,
+            bif_paths,
+        }
+    }
+
+    /// Creates a new `ResourceLocator` reading the `chitin.key` file in the root of the game file system
+    pub fn from_fs(fs: CaseInsensitiveFS) -> io::Result<Self> {
+        let path = fs.get_path(&CaseInsensitivePath::new(KEY_FILE_NAME))?;
+        let key = KeyImporter::import(&DataSource::new(path))?;
+        Ok(ResourceLocator::new(fs, key))
+    }
+
+    /// Creates a new `ResourceLocator` merging several KEY files, given by their paths in the game file system.
+    /// The KEY files are applied in order: a resource listed by more than one of them is read from the last one,
+    /// e.g. `&["chitin.key", "mod.key"]` lets `mod.key` override the resources of `chitin.key`.
+    pub fn from_keys(fs: CaseInsensitiveFS, keys: &[&str]) -> io::Result<Self> {
+        let keys = keys
+            .iter()
+            .map(|key| {
+                let path = fs.get_path(&CaseInsensitivePath::new(key))?;
+                KeyImporter::import(&DataSource::new(path))
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        let key = merge_keys(keys)?
+            .ok_or_else(|| io::Error::other("At least one KEY file is required"))?;
+        Ok(ResourceLocator::new(fs, key))
+    }
+
+    /// Returns the game file system
+    pub fn fs(&self) -> &CaseInsensitiveFS {
+        &self.fs
+    }
+
+    /// Returns the KEY file
+    pub fn key(&self) -> &Key {
+        &self.key
+    }
+
+    /// Returns the BIF files of the KEY file found in the game folders, with their paths
+    pub fn resolved_bifs(&self) -> impl Iterator<Item = (&BifEntry, &Path)> {
+        self.key
+            .bif_entries
+            .iter()
+            .zip(&self.bif_paths)
+            .filter_map(|(entry, path)| Some((entry, path.as_deref()?)))
+    }
+
+    /// Returns the BIF files of the KEY file not found in the game folders, e.g. the ones of a broken installation
+    pub fn missing_bifs(&self) -> Vec<&BifEntry> {
+        self.key
+            .bif_entries
+            .iter()
+            .zip(&self.bif_paths)
+            .filter(|(_, path)| path.is_none())
+            .map(|(entry, _)| entry)
+            .collect()
+    }
+
+    /// Returns the KEY entry of a resource, the name is matched case insensitively
+    pub fn entry(&self, name: &str, ty: ResourceType) -> Option<&ResourceEntry> {
+        self.entries
+            .get(&(name.to_uppercase(), ty))
+            .map(|index| &self.key.resource_entries[*index])
+    }
+
+    /// Opens a resource, preferring the loose file in the override folders to the one stored in the BIF files
+    pub fn open(&self, name: &str, ty: ResourceType) -> io::Result<DataSource> {
+        if let Some(path) = self.fs.resolve_override(name, ty) {
+            return Ok(DataSource::new(path));
+        }
+        match self.entry(name, ty) {
+            Some(entry) => self.open_entry(entry),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Resource not found: {} ({})", name, ty),
+            )),
+        }
+    }
+
+    /// Returns the name and type of all the resources of the installation, from the KEY file and the override folders.
+    /// The names are uppercased and every resource is listed once, sorted by name and type.
+    pub fn resource_list(&self) -> Vec<(String, ResourceType)> {
+        let loose_files = self
+            .fs
+            .override_dirs()
+            .iter()
+            .flat_map(|dir| self.fs.list_in_dir(dir))
+            .filter_map(|path| {
+                let ty = ResourceType::from_path(&path);
+                let name = path.file_stem()?.to_str()?.to_uppercase();
+                (!matches!(ty, ResourceType::Unknown(_))).then_some((name, ty))
+            });
+
+        let mut resources = self
+            .entries
+            .keys()
+            .cloned()
+            .chain(loose_files)
+            .collect::<Vec<_>>();
+        resources.sort_by(|(name, ty), (other_name, other_ty)| {
+            name.cmp(other_name)
+                .then(ty.to_u16().cmp(&other_ty.to_u16()))
+        });
+        resources.dedup();
+        resources
+    }
+
+    /// Extracts all the resources of the installation in `out_dir`, as `NAME.EXT` files.
+    /// Every resource is written once, in the order of `resource_list`, and is read with the precedence of `open`:
+    /// a loose file in the override folders wins over the copy stored in the BIF files.
+    /// The resources that cannot be read are reported and skipped, errors writing the files stop the extraction.
+    pub fn extract_install(&self, out_dir: &Path) -> io::Result<ExtractionReport> {
+        std::fs::create_dir_all(out_dir)?;
+        let mut report = ExtractionReport::default();
+        for (name, ty) in self.resource_list() {
+            let Some(ext) = ty.file_extension() else {
+                let err = io::Error::other(format!("Unknown resource type: {ty}"));
+                report.failed.push((name, ty, err));
+                continue;
+            };
+            let data = self.open(&name, ty).and_then(|source| {
+                let mut data = Vec::new();
+                source.reader()?.read_to_end(&mut data, u64::MAX)?;
+                Ok(data)
+            });
+            match data {
+                Ok(data) => {
+                    let path = out_dir.join(format!("{name}.{}", ext.to_uppercase()));
+                    std::fs::write(&path, data)?;
+                    report.extracted.push(path);
+                }
+                Err(err) => report.failed.push((name, ty, err)),
+            }
+        }
+        Ok(report)
+    }
+
+    /// Opens a resource stored in a BIF file.
+    /// The tilesets are returned without the TIS header, as they are stored in the BIF file.
+    pub fn open_entry(&self, entry: &ResourceEntry) -> io::Result<DataSource> {
+        let bif_entry = self
+            .key
+            .bif_entries
+            .get(entry.bif_entries_index as usize)
+            .ok_or_else(|| {
+                io::Error::other(format!(
+                    "Invalid BIF index {} for resource {}",
+                    entry.bif_entries_index, entry.resource_name
+                ))
+            })?;
+        let path = self.bif_paths[entry.bif_entries_index as usize]
+            .as_ref()
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("BIF file not found: {}", bif_entry.file_name.as_str()),
+                )
+            })?;
+
+        let source = DataSource::new(path.as_path());
+        let bif = BifImporter::import(&source)?;
+        let resource = bif.resource_by_locator(entry.locator).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "Resource {} not found in BIF file {}",
+                    entry.resource_name,
+                    bif_entry.file_name.as_str()
+                ),
+            )
+        })?;
+
+        BifImporter::extract(&source, resource.offset(), resource.data_size())
+    }
+}
+
+/// The outcome of `ResourceLocator::extract_install`
+#[derive(Debug, Default)]
+pub struct ExtractionReport {
+    /// The paths of the extracted files, in extraction order
+    pub extracted: Vec<PathBuf>,
+    /// The resources that could not be extracted, e.g. the ones of a BIF file that is not installed
+    pub failed: Vec<(String, ResourceType, io::Error)>,
+}
+
+/// Merges KEY files into the first one, the resources of the later files replacing the ones with the same name and type.
+/// The BIF entries are appended, a BIF file referenced by more than one KEY file, compared case insensitively, is listed once.
+/// The BIF index of the resources, both `bif_entries_index` and bits 20-31 of `locator`, is remapped to the merged BIF entries.
+/// Returns `None` if there are no KEY files, and an error if a resource references a BIF entry missing from its KEY file.
+fn merge_keys(keys: Vec<Key>) -> io::Result<Option<Key>> {
+    let mut keys = keys.into_iter();
+    let Some(mut merged) = keys.next() else {
+        return Ok(None);
+    };
+    let mut entries: HashMap<(String, ResourceType), usize> = merged
+        .resource_entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| ((entry.resource_name.to_uppercase(), entry.r#type), index))
+        .collect();
+
+    for key in keys {
+        let bif_indexes = key
+            .bif_entries
+            .into_iter()
+            .map(|mut bif_entry| {
+                match merged.bif_entries.iter().position(|merged_entry| {
+                    merged_entry
+                        .file_name
+                        .as_str()
+                        .eq_ignore_ascii_case(bif_entry.file_name.as_str())
+                }) {
+                    Some(index) => index as u64,
+                    None => {
+                        bif_entry.index = merged.bif_entries.len() as u64;
+                        merged.bif_entries.push(bif_entry);
+                        merged.bif_entries.len() as u64 - 1
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+
+        for mut entry in key.resource_entries {
+            let bif_index = bif_indexes
+                .get(entry.bif_entries_index as usize)
+                .copied()
+                .ok_or_else(|| {
+                    io::Error::other(format!(
+                        "Invalid BIF index {} for resource {}",
+                        entry.bif_entries_index, entry.resource_name
+                    ))
+                })?;
+            entry.bif_entries_index = bif_index;
+            entry.locator = (entry.locator & 0xfffff) | ((bif_index as u32) << 20);
+            let id = (entry.resource_name.to_uppercase(), entry.r#type);
+            match entries.get(&id) {
+                Some(index) => merged.resource_entries[*index] = entry,
+                None => {
+                    entries.insert(id, merged.resource_entries.len());
+                    merged.resource_entries.push(entry);
+                }
+            }
+        }
+    }
+
+    Ok(Some(merged))
+}
+
+/// Searches a BIF file in the game folders.
+/// Some games store the compressed BIF files with the `cbf` extension, even if the KEY file references them as `bif`.
+fn search_bif(fs: &CaseInsensitiveFS, file_name: &CaseInsensitivePath) -> Option<PathBuf> {
+    fs.search_path_opt(file_name).or_else(|| {
+        let cbf_name = file_name.as_str().strip_suffix(".bif")?;
+        fs.search_path_opt(&CaseInsensitivePath::new(&format!("{cbf_name}.cbf")))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{
+        BG_RESOURCES_DIR, BG2_RESOURCES_DIR, IWD_RESOURCES_DIR, PST_RESOURCES_DIR,
+    };
+
+    fn open_locator(dir: &str) -> ResourceLocator {
+        ResourceLocator::from_fs(CaseInsensitiveFS::new(dir).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_open_from_biff() {
+        let locator = open_locator(BG_RESOURCES_DIR);
+
+        let wed = locator.open("ar3802", ResourceType::Wed).unwrap();
+        assert_eq!(wed.reader().unwrap().read_string(8).unwrap(), "WED V1.3");
+
+        let bmp = locator.open("AR3802HT", ResourceType::Bmp).unwrap();
+        assert_eq!(bmp.reader().unwrap().read_string(2).unwrap(), "BM");
+
+        let bcs = open_locator(PST_RESOURCES_DIR)
+            .open("0511ING1", ResourceType::Bcs)
+            .unwrap();
+        assert_eq!(bcs.reader().unwrap().read_string(2).unwrap(), "SC");
+    }
+
+    #[test]
+    fn test_open_from_compressed_bif() {
+        // BIFC V1.0
+        let wed = open_locator(BG2_RESOURCES_DIR)
+            .open("AR0714", ResourceType::Wed)
+            .unwrap();
+        assert_eq!(wed.reader().unwrap().read_string(8).unwrap(), "WED V1.3");
+
+        // BIF V1.0, stored with the cbf extension
+        let locator = open_locator(IWD_RESOURCES_DIR);
+        let wed = locator.open("AR3603", ResourceType::Wed).unwrap();
+        assert_eq!(wed.reader().unwrap().read_string(8).unwrap(), "WED V1.3");
+
+        // The tileset is 300 tiles of 5120 bytes
+        let tis = locator.open("AR3603", ResourceType::Tis).unwrap();
+        let mut data = Vec::new();
+        tis.reader()
+            .unwrap()
+            .read_to_end(&mut data, u64::MAX)
+            .unwrap();
+        assert_eq!(data.len(), 300 * 5120);
+    }
+
+    #[test]
+    fn test_open_prefers_override() {
+        let locator = open_locator(BG2_RESOURCES_DIR);
+
+        let source = locator.open("ABCLASRQ", ResourceType::TwoDA).unwrap();
+        assert!(matches!(
+            source,
+            DataSource::Full {
+                data: crate::datasource::Data::FileSource(_),
+                ..
+            }
+        ));
+        assert!(locator.entry("abclasrq", ResourceType::TwoDA).is_some());
+
+        assert!(locator.open("MISSING", ResourceType::Itm).is_err());
+        // The resource is in the KEY file, but its BIF is not installed
+        assert!(locator.open("SW1H01", ResourceType::Itm).is_err());
+    }
+
+    #[test]
+    fn test_from_keys() {
+        let fs = CaseInsensitiveFS::new(BG_RESOURCES_DIR).unwrap();
+        let chitin = open_locator(BG_RESOURCES_DIR);
+        let locator = ResourceLocator::from_keys(fs, &["chitin.key", "mod.key"]).unwrap();
+
+        // mod.key adds the data/mod.bif file, which is not installed
+        let bif_count = chitin.key().bif_entries.len();
+        assert_eq!(locator.key().bif_entries.len(), bif_count + 1);
+        assert_eq!(
+            locator.key().bif_entries[bif_count].file_name.as_str(),
+            "data/mod.bif"
+        );
+
+        // AR3802 is overridden by mod.key
+        let wed = locator.entry("AR3802", ResourceType::Wed).unwrap();
+        assert_eq!(wed.bif_entries_index, bif_count as u64);
+        for entry in &locator.key().resource_entries {
+            assert_eq!(entry.bif_index() as u64, entry.bif_entries_index);
+        }
+        assert!(chitin.open("AR3802", ResourceType::Wed).is_ok());
+        assert!(locator.open("AR3802", ResourceType::Wed).is_err());
+
+        // The other resources of chitin.key are kept
+        let bmp = locator.open("AR3802HT", ResourceType::Bmp).unwrap();
+        assert_eq!(bmp.reader().unwrap().read_string(2).unwrap(), "BM");
+        assert!(locator.entry("MODITM", ResourceType::Itm).is_some());
+        assert_eq!(
+            locator.key().resource_entries.len(),
+            chitin.key().resource_entries.len() + 1
+        );
+
+        let fs = CaseInsensitiveFS::new(BG_RESOURCES_DIR).unwrap();
+        assert!(ResourceLocator::from_keys(fs, &[]).is_err());
+    }
+
+    fn key(bifs: &[&str], resources: &[(&str, u32)]) -> Key {
+        Key {
+            signature: "KEY".to_string(),
+            version: "V1".to_string(),
+            resources_offset: 0,
+            bif_offset: 0,
+            bif_entries: bifs
+                .iter()
+                .enumerate()
+                .map(|(index, name)| BifEntry {
+                    index: index as u64,
+                    file_name: CaseInsensitivePath::new(name),
+                    file_size: None,
+                    directory: crate::resource::key::BifDirectory::Root,
+                })
+                .collect(),
+            resource_entries: resources
+                .iter()
+                .map(|(name, locator)| ResourceEntry {
+                    resource_name: name.to_string(),
+                    r#type: ResourceType::Itm,
+                    bif_entries_index: (*locator >> 20) as u64,
+                    index_into_bif_file: 0,
+                    locator: *locator,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_merge_keys_remaps_bif_index() {
+        let first = key(&["data/area000a.bif"], &[("FIRST", 0x0000_0003)]);
+        let second = key(
+            &["data/items.bif", "DATA/AREA000A.BIF"],
+            &[("SECOND", 0x0000_0001), ("THIRD", 0x0010_0002)],
+        );
+        let merged = merge_keys(vec![first, second]).unwrap().unwrap();
+
+        // The BIF files are compared case insensitively
+        assert_eq!(merged.bif_entries.len(), 2);
+        assert_eq!(merged.bif_entries[1].file_name.as_str(), "data/items.bif");
+
+        let locators = merged
+            .resource_entries
+            .iter()
+            .map(|entry| {
+                assert_eq!(entry.bif_index() as u64, entry.bif_entries_index);
+                (entry.resource_name.as_str(), entry.locator)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            locators,
+            vec![
+                ("FIRST", 0x0000_0003),
+                ("SECOND", 0x0010_0001),
+                ("THIRD", 0x0000_0002)
+            ]
+        );
+
+        assert!(merge_keys(vec![]).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_merge_keys_rejects_invalid_bif_index() {
+        let first = key(&["data/area000a.bif"], &[("FIRST", 0)]);
+        let second = key(&["data/items.bif"], &[("SECOND", 0x0010_0000)]);
+        let err = merge_keys(vec![first, second]).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Invalid BIF index 1 for resource SECOND")
+        );
+    }
+
+    #[test]
+    fn test_extract_install() {
+        // ABCLASRQ.2DA is listed in the KEY file and stored in the override folder
+        let locator = open_locator(BG2_RESOURCES_DIR);
+        let resources = locator.resource_list();
+        assert_eq!(
+            resources
+                .iter()
+                .filter(|(name, ty)| name == "ABCLASRQ" && *ty == ResourceType::TwoDA)
+                .count(),
+            1
+        );
+        assert!(
+            resources.is_sorted_by(|(name, ty), (other_name, other_ty)| {
+                (name, ty.to_u16()) < (other_name, other_ty.to_u16())
+            })
+        );
+
+        let out_dir =
+            std::env::temp_dir().join(format!("infinitier_extract_{}", std::process::id()));
+        let report = locator.extract_install(&out_dir).unwrap();
+
+        let extracted = report
+            .extracted
+            .iter()
+            .filter(|path| path.ends_with("ABCLASRQ.2DA"))
+            .collect::<Vec<_>>();
+        assert_eq!(extracted.len(), 1);
+        let override_path = locator
+            .fs()
+            .resolve_override("ABCLASRQ", ResourceType::TwoDA)
+            .unwrap();
+        assert_eq!(
+            std::fs::read(extracted[0]).unwrap(),
+            std::fs::read(override_path).unwrap()
+        );
+
+        // The WED of the installed BIF file is extracted, the resources of the missing ones are reported
+        assert!(out_dir.join("AR0714.WED").exists());
+        assert!(
+            report
+                .failed
+                .iter()
+                .any(|(name, ty, _)| name == "SW1H01" && *ty == ResourceType::Itm)
+        );
+        assert_eq!(
+            report.extracted.len() + report.failed.len(),
+            resources.len()
+        );
+
+        // The extraction is deterministic
+        let again = locator.extract_install(&out_dir).unwrap();
+        assert_eq!(again.extracted, report.extracted);
+
+        std::fs::remove_dir_all(&out_dir).unwrap();
+    }
+
+    #[test]
+    fn test_resolved_and_missing_bifs() {
+        // Only one of the BIF files referenced by the KEY file is installed
+        let locator = open_locator(BG_RESOURCES_DIR);
+
+        let resolved = locator.resolved_bifs().collect::<Vec<_>>();
+        assert_eq!(resolved.len(), 1);
+        let (entry, path) = resolved[0];
+        assert_eq!(entry.file_name.as_str(), "data/area380x.bif");
+        assert!(path.ends_with("data/AREA380X.BIF"));
+
+        let missing = locator.missing_bifs();
+        assert_eq!(missing.len(), locator.key().bif_entries.len() - 1);
+        assert!(missing.iter().all(|missing| missing.index != entry.index));
+
+        // BIF V1.0, stored with the cbf extension
+        let locator = open_locator(IWD_RESOURCES_DIR);
+        let resolved = locator.resolved_bifs().collect::<Vec<_>>();
+        assert_eq!(resolved.len(), 1);
+        assert!(resolved[0].1.ends_with("CD2/Data/AR3603.cbf"));
+    }
+}
@@ -0,0 +1,84 @@
+use std::ops::Range;
+
+use serde::Serialize;
+
+use crate::resource::effect::EffectV1;
+
+/// An ITM or SPL ability, i.e. one of the ways an item or a spell can be used, with its effects
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct Ability<'a> {
+    pub attack_type: AttackType,
+    pub target: AbilityTarget,
+    pub range: u16,
+    /// The maximum charges of an item ability, the charges of a spell ability
+    pub charges: u16,
+    /// The range of the ability effects in the feature blocks table of the file
+    pub effect_range: Range<usize>,
+    pub effects: &'a [EffectV1],
+}
+
+/// How an ability reaches its target.
+/// The spell abilities are always magical.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+pub enum AttackType {
+    None,
+    Melee,
+    Ranged,
+    Magical,
+    Launcher,
+    Unknown(u8),
+}
+
+impl AttackType {
+    pub fn from_u8(value: u8) -> AttackType {
+        match value {
+            0 => AttackType::None,
+            1 => AttackType::Melee,
+            2 => AttackType::Ranged,
+            3 => AttackType::Magical,
+            4 => AttackType::Launcher,
+            value => AttackType::Unknown(value),
+        }
+    }
+}
+
+/// What an ability can be used on
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+pub struct AbilityTarget {
+    pub target_type: TargetType,
+    pub count: u8,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+pub enum TargetType {
+    Invalid,
+    LivingActor,
+    Inventory,
+    DeadActor,
+    /// Any point within range
+    Area,
+    Caster,
+    /// The caster, without the casting animation
+    CasterInstant,
+    Unknown(u8),
+}
+
+impl TargetType {
+    pub fn from_u8(value: u8) -> TargetType {
+        match value {
+            0 => TargetType::Invalid,
+            1 => TargetType::LivingActor,
+            2 => TargetType::Inventory,
+            3 => TargetType::DeadActor,
+            4 => TargetType::Area,
+            5 => TargetType::Caster,
+            7 => TargetType::CasterInstant,
+            value => TargetType::Unknown(value),
+        }
+    }
+}
+
+/// Returns the range of `count` feature blocks starting at `index`
+pub(crate) fn feature_blocks_range(index: u16, count: u16) -> Range<usize> {
+    index as usize..index as usize + count as usize
+}
@@ -0,0 +1,467 @@
+use std::io::{Read, Write};
+
+use crate::datasource::{DataSource, Importer, Reader};
+
+/// The signature at the start of an ACM stream
+const ACM_SIGNATURE: u32 = 0x01032897;
+
+/// The number of entries in the amplitudes table
+const AMPLITUDES_SIZE: usize = 0x10000;
+
+const MAP_1BIT: [i32; 2] = [-1, 1];
+const MAP_2BIT_NEAR: [i32; 4] = [-2, -1, 1, 2];
+const MAP_2BIT_FAR: [i32; 4] = [-3, -2, 2, 3];
+const MAP_3BIT: [i32; 8] = [-4, -3, -2, -1, 1, 2, 3, 4];
+
+/// An ACM file importer
+pub struct AcmImporter;
+
+impl Importer for AcmImporter {
+    type T = AcmAudio;
+
+    fn import(source: &DataSource) -> std::io::Result<AcmAudio> {
+        let mut reader = source.reader()?;
+        AcmAudio::read(&mut reader)
+    }
+}
+
+/// An audio clip decoded to 16 bit PCM
+#[derive(Debug, PartialEq, Eq)]
+pub struct AcmAudio {
+    /// The sample rate stored in the file.
+    /// The games often ignore it and play the clips at 22050Hz.
+    pub sample_rate_hint: u32,
+    pub channels: u16,
+    /// The samples, with the channels interleaved
+    pub samples: Vec<i16>,
+}
+
+impl AcmAudio {
+    /// Reads and decodes an ACM stream, the reader must be at the start of the ACM header
+    pub(crate) fn read<R: Read>(reader: &mut Reader<R>) -> std::io::Result<AcmAudio> {
+        let signature = reader.read_u32()?;
+        if signature != ACM_SIGNATURE {
+            return Err(std::io::Error::other(format!(
+                "Wrong file type: {:#x}",
+                signature
+            )));
+        }
+
+        let samples_count = reader.read_u32()? as usize;
+        let channels = reader.read_u16()?;
+        let sample_rate_hint = reader.read_u16()? as u32;
+        let packed = reader.read_u16()?;
+        let level = (packed & 0xf) as u32;
+        let rows = (packed >> 4) as usize;
+        if rows == 0 && samples_count > 0 {
+            return Err(std::io::Error::other("Invalid ACM block size: 0 rows"));
+        }
+
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data, u64::MAX)?;
+
+        // Each block decodes to `rows * cols` samples, the last one is truncated to the samples count.
+        // The samples grow block by block, the header count is not trusted for the allocation.
+        let mut decoder = AcmDecoder::new(&data, level, rows);
+        let mut samples = Vec::new();
+        while samples.len() < samples_count {
+            decoder.decode_block()?;
+            let remaining = samples_count - samples.len();
+            samples.extend(
+                decoder
+                    .block
+                    .iter()
+                    .take(remaining)
+                    .map(|value| (value >> level) as i16),
+            );
+        }
+
+        Ok(AcmAudio {
+            sample_rate_hint,
+            channels,
+            samples,
+        })
+    }
+
+    /// Writes the audio as a RIFF/WAVE file with 16 bit PCM samples.
+    /// Returns an error if the sizes or rates of the audio do not fit in the WAVE header fields.
+    pub fn to_wav<W: Write>(&self, w: &mut W) -> std::io::Result<()> {
+        let data_size = u32::try_from(self.samples.len() * 2)
+            .ok()
+            .filter(|size| *size <= u32::MAX - 36)
+            .ok_or_else(|| std::io::Error::other("Too many samples for a WAV file"))?;
+        let block_align = self.channels.checked_mul(2).ok_or_else(|| {
+            std::io::Error::other(format!("Too many channels: {}", self.channels))
+        })?;
+        let byte_rate = self
+            .sample_rate_hint
+            .checked_mul(block_align as u32)
+            .ok_or_else(|| {
+                std::io::Error::other(format!("Sample rate too high: {}", self.sample_rate_hint))
+            })?;
+
+        w.write_all(b"RIFF")?;
+        w.write_all(&(36 + data_size).to_le_bytes())?;
+        w.write_all(b"WAVE")?;
+
+        w.write_all(b"fmt ")?;
+        w.write_all(&16u32.to_le_bytes())?;
+        w.write_all(&1u16.to_le_bytes())?; // PCM
+        w.write_all(&self.channels.to_le_bytes())?;
+        w.write_all(&self.sample_rate_hint.to_le_bytes())?;
+        w.write_all(&byte_rate.to_le_bytes())?;
+        w.write_all(&block_align.to_le_bytes())?;
+        w.write_all(&16u16.to_le_bytes())?; // bits per sample
+
+        w.write_all(b"data")?;
+        w.write_all(&data_size.to_le_bytes())?;
+        for sample in &self.samples {
+            w.write_all(&sample.to_le_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads a bit stream, starting from the least significant bit of each byte
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl BitReader<'_> {
+    /// Reads `count` bits, at most 32
+    fn read(&mut self, count: u32) -> std::io::Result<u32> {
+        let mut value = 0;
+        for bit in 0..count {
+            let byte = self.data.get(self.position / 8).ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "ACM data ended before the expected samples count",
+                )
+            })?;
+            value |= ((*byte as u32 >> (self.position % 8)) & 1) << bit;
+            self.position += 1;
+        }
+        Ok(value)
+    }
+}
+
+/// The state of the Interplay ACM decoder.
+/// Each block is a matrix of `rows` x `cols` values, filled column by column
+/// and then transformed row by row into samples.
+struct AcmDecoder<'a> {
+    bits: BitReader<'a>,
+    level: u32,
+    rows: usize,
+    cols: usize,
+    block: Vec<i32>,
+    /// The values carried over between the transformations of consecutive blocks
+    wrap: Vec<i32>,
+    /// The amplitudes indexed by the packed values, centered at `AMPLITUDES_SIZE / 2`
+    amplitudes: Vec<i32>,
+}
+
+impl<'a> AcmDecoder<'a> {
+    fn new(data: &'a [u8], level: u32, rows: usize) -> Self {
+        let cols = 1 << level;
+        AcmDecoder {
+            bits: BitReader { data, position: 0 },
+            level,
+            rows,
+            cols,
+            block: vec![0; rows * cols],
+            wrap: vec![0; 2 * cols - 2],
+            amplitudes: vec![0; AMPLITUDES_SIZE],
+        }
+    }
+
+    /// Decodes the next block into `self.block`
+    fn decode_block(&mut self) -> std::io::Result<()> {
+        let power = self.bits.read(4)?;
+        let step = self.bits.read(16)? as i32;
+
+        // The amplitudes are the multiples of the step, each block redefines the ones it uses
+        let middle = AMPLITUDES_SIZE / 2;
+        let count = 1usize << power;
+        for i in 0..count {
+            self.amplitudes[middle + i] = step.wrapping_mul(i as i32);
+        }
+        for i in 1..=count {
+            self.amplitudes[middle - i] = step.wrapping_mul(-(i as i32));
+        }
+
+        for col in 0..self.cols {
+            let filler = self.bits.read(5)?;
+            match filler {
+                0 => self.fill_zero(col),
+                3..=16 => self.fill_linear(col, filler)?,
+                17 => self.fill_sparse(col, true, read_1bit)?,
+                18 => self.fill_sparse(col, false, read_1bit)?,
+                19 => self.fill_packed(col, 5, 3, 3)?,
+                20 => self.fill_sparse(col, true, read_2bit_near)?,
+                21 => self.fill_sparse(col, false, read_2bit_near)?,
+                22 => self.fill_packed(col, 7, 5, 3)?,
+                23 => self.fill_sparse(col, true, read_2bit_far)?,
+                24 => self.fill_sparse(col, false, read_2bit_far)?,
+                26 => self.fill_sparse(col, true, read_3bit)?,
+                27 => self.fill_sparse(col, false, read_3bit)?,
+                29 => self.fill_packed(col, 7, 11, 2)?,
+                filler => {
+                    return Err(std::io::Error::other(format!(
+                        "Invalid ACM filler: {}",
+                        filler
+                    )));
+                }
+            }
+        }
+
+        self.juggle_block();
+        Ok(())
+    }
+
+    /// Sets a block value to the amplitude of a packed value
+    fn set(&mut self, row: usize, col: usize, value: i32) {
+        let index = (AMPLITUDES_SIZE / 2) as i32 + value;
+        self.block[row * self.cols + col] = self.amplitudes[index as usize];
+    }
+
+    fn fill_zero(&mut self, col: usize) {
+        for row in 0..self.rows {
+            self.set(row, col, 0);
+        }
+    }
+
+    /// Fills a column with values stored in `bits` bits each
+    fn fill_linear(&mut self, col: usize, bits: u32) -> std::io::Result<()> {
+        let middle = 1 << (bits - 1);
+        for row in 0..self.rows {
+            let value = self.bits.read(bits)? as i32;
+            self.set(row, col, value - middle);
+        }
+        Ok(())
+    }
+
+    /// Fills a column that is mostly zeros.
+    /// A 0 bit encodes a zero, or two zeros if `double_zeros` is set, in which case
+    /// a single zero is encoded by the 10 bits.
+    /// Otherwise the value is read by `read_value`.
+    fn fill_sparse(
+        &mut self,
+        col: usize,
+        double_zeros: bool,
+        read_value: fn(&mut BitReader) -> std::io::Result<i32>,
+    ) -> std::io::Result<()> {
+        let mut row = 0;
+        while row < self.rows {
+            if self.bits.read(1)? == 0 {
+                self.set(row, col, 0);
+                row += 1;
+                if double_zeros && row < self.rows {
+                    self.set(row, col, 0);
+                    row += 1;
+                }
+                continue;
+            }
+            if double_zeros && self.bits.read(1)? == 0 {
+                self.set(row, col, 0);
+                row += 1;
+                continue;
+            }
+            let value = read_value(&mut self.bits)?;
+            self.set(row, col, value);
+            row += 1;
+        }
+        Ok(())
+    }
+
+    /// Fills a column with groups of `count` values in base `base`, packed in `bits` bits
+    fn fill_packed(&mut self, col: usize, bits: u32, base: u32, count: u32) -> std::io::Result<()> {
+        let mut row = 0;
+        while row < self.rows {
+            let mut packed = self.bits.read(bits)?;
+            if packed >= base.pow(count) {
+                return Err(std::io::Error::other(format!(
+                    "Invalid ACM packed value: {}",
+                    packed
+                )));
+            }
+            for _ in 0..count {
+                if row >= self.rows {
+                    break;
+                }
+                self.set(row, col, (packed % base) as i32 - (base / 2) as i32);
+                packed /= base;
+                row += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the inverse transform to the block, in chunks of rows
+    fn juggle_block(&mut self) {
+        if self.level == 0 {
+            return;
+        }
+
+        let step_count = if self.level > 9 {
+            1
+        } else {
+            (2048 >> self.level) - 2
+        };
+
+        let mut todo_count = self.rows;
+        let mut offset = 0;
+        loop {
+            let mut wrap_offset = 0;
+            let mut sub_len = self.cols / 2;
+            let mut sub_count = step_count.min(todo_count) * 2;
+
+            juggle(
+                &mut self.wrap[wrap_offset..],
+                &mut self.block[offset..],
+                sub_len,
+                sub_count,
+            );
+            wrap_offset += sub_len * 2;
+
+            for i in 0..sub_count {
+                self.block[offset + i * sub_len] = self.block[offset + i * sub_len].wrapping_add(1);
+            }
+
+            while sub_len > 1 {
+                sub_len /= 2;
+                sub_count *= 2;
+                juggle(
+                    &mut self.wrap[wrap_offset..],
+                    &mut self.block[offset..],
+                    sub_len,
+                    sub_count,
+                );
+                wrap_offset += sub_len * 2;
+            }
+
+            if todo_count <= step_count {
+                break;
+            }
+            todo_count -= step_count;
+            offset += step_count << self.level;
+        }
+    }
+}
+
+fn read_1bit(bits: &mut BitReader) -> std::io::Result<i32> {
+    Ok(MAP_1BIT[bits.read(1)? as usize])
+}
+
+fn read_2bit_near(bits: &mut BitReader) -> std::io::Result<i32> {
+    Ok(MAP_2BIT_NEAR[bits.read(2)? as usize])
+}
+
+/// Reads either a 1 bit value or a far 2 bits value, selected by a leading bit
+fn read_2bit_far(bits: &mut BitReader) -> std::io::Result<i32> {
+    if bits.read(1)? == 0 {
+        read_1bit(bits)
+    } else {
+        Ok(MAP_2BIT_FAR[bits.read(2)? as usize])
+    }
+}
+
+fn read_3bit(bits: &mut BitReader) -> std::io::Result<i32> {
+    Ok(MAP_3BIT[bits.read(3)? as usize])
+}
+
+/// Applies one level of the inverse transform to `sub_count` subblocks of `sub_len` values
+fn juggle(wrap: &mut [i32], block: &mut [i32], sub_len: usize, sub_count: usize) {
+    for i in 0..sub_len {
+        let mut position = i;
+        let mut r0 = wrap[i * 2];
+        let mut r1 = wrap[i * 2 + 1];
+        for _ in 0..sub_count / 2 {
+            let r2 = block[position];
+            block[position] = r1.wrapping_mul(2).wrapping_add(r0.wrapping_add(r2));
+            position += sub_len;
+            let r3 = block[position];
+            block[position] = r2.wrapping_mul(2).wrapping_sub(r1.wrapping_add(r3));
+            position += sub_len;
+            r0 = r2;
+            r1 = r3;
+        }
+        wrap[i * 2] = r0;
+        wrap[i * 2 + 1] = r1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::RESOURCES_DIR;
+    use std::path::Path;
+
+    /// The first samples of TEST.ACM, as decoded by a port of the libavcodec Interplay ACM decoder
+    const EXPECTED_SAMPLES: [i16; 16] = [
+        0, 5, -13, 14, -22, 31, -30, 24, -34, 53, -55, 38, -55, 77, -68, 1,
+    ];
+
+    #[test]
+    fn test_decode_acm() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/ACM/TEST.ACM"
+        )));
+
+        let audio = AcmImporter::import(&data).unwrap();
+
+        assert_eq!(audio.channels, 2);
+        assert_eq!(audio.sample_rate_hint, 22050);
+        assert_eq!(audio.samples.len(), 4084);
+        assert_eq!(audio.samples[..16], EXPECTED_SAMPLES);
+        // The last block is truncated to the samples count
+        assert_eq!(audio.samples[3594], 1165);
+        assert_eq!(audio.samples[4080..], [943, 1846, -1039, 7997]);
+    }
+
+    #[test]
+    fn test_decode_acm_with_huge_samples_count() {
+        let mut bytes = ACM_SIGNATURE.to_le_bytes().to_vec();
+        bytes.extend(u32::MAX.to_le_bytes());
+        bytes.extend(2u16.to_le_bytes());
+        bytes.extend(22050u16.to_le_bytes());
+        bytes.extend((1u16 << 4).to_le_bytes());
+
+        assert!(AcmImporter::import(&DataSource::new(bytes)).is_err());
+    }
+
+    #[test]
+    fn test_acm_to_wav() {
+        let audio = AcmAudio {
+            sample_rate_hint: 22050,
+            channels: 2,
+            samples: vec![1, -1, 256, -256],
+        };
+
+        let mut wav = Vec::new();
+        audio.to_wav(&mut wav).unwrap();
+
+        assert_eq!(wav.len(), 44 + 8);
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(u32::from_le_bytes(wav[4..8].try_into().unwrap()), 44);
+        assert_eq!(&wav[8..16], b"WAVEfmt ");
+        // Byte rate and block align
+        assert_eq!(u32::from_le_bytes(wav[28..32].try_into().unwrap()), 88200);
+        assert_eq!(u16::from_le_bytes(wav[32..34].try_into().unwrap()), 4);
+        assert_eq!(&wav[36..40], b"data");
+        assert_eq!(u32::from_le_bytes(wav[40..44].try_into().unwrap()), 8);
+        assert_eq!(&wav[44..], &[1, 0, 0xff, 0xff, 0, 1, 0, 0xff]);
+
+        let too_many_channels = AcmAudio {
+            channels: 40000,
+            ..audio
+        };
+        assert!(too_many_channels.to_wav(&mut Vec::new()).is_err());
+        let too_fast = AcmAudio {
+            sample_rate_hint: u32::MAX,
+            channels: 2,
+            ..too_many_channels
+        };
+        assert!(too_fast.to_wav(&mut Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,663 @@
+use std::io::Read;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    datasource::{DataSource, Importer, Reader},
+    resource::{
+        cre::{Cre, CreImporter},
+        key::ResourceType,
+        wed::ResourceReference,
+    },
+};
+
+/// An ARE file importer
+pub struct AreImporter;
+
+impl Importer for AreImporter {
+    type T = Are;
+
+    fn import(source: &DataSource) -> std::io::Result<Are> {
+        let mut reader = source.reader()?;
+
+        let signature = reader.read_string(8)?;
+        if signature != "AREAV1.0" {
+            return Err(std::io::Error::other(format!(
+                "Wrong file type: {}",
+                signature
+            )));
+        }
+
+        let wed = ResourceReference {
+            name: reader.read_string(8)?,
+            r#type: ResourceType::Wed,
+        };
+        let _last_saved = reader.read_u32()?;
+        let flags = reader.read_u32()?;
+        let mut neighbours = Vec::with_capacity(4);
+        for _ in 0..4 {
+            neighbours.push(AreNeighbour {
+                area: reader.read_string(8)?,
+                flags: reader.read_u32()?,
+            });
+        }
+        let area_type = reader.read_u16()?;
+        let weather = AreWeather {
+            rain_probability: reader.read_u16()?,
+            snow_probability: reader.read_u16()?,
+            fog_probability: reader.read_u16()?,
+            lightning_probability: reader.read_u16()?,
+            wind_speed: reader.read_u16()?,
+        };
+
+        let actors_offset = reader.read_u32()? as u64;
+        let actors_count = reader.read_u16()? as usize;
+        let regions_count = reader.read_u16()? as usize;
+        let regions_offset = reader.read_u32()? as u64;
+        let spawn_points = AreTable::read(&mut reader)?;
+        let entrances_offset = reader.read_u32()? as u64;
+        let entrances_count = reader.read_u32()? as usize;
+        let containers_offset = reader.read_u32()? as u64;
+        let containers_count = reader.read_u16()? as usize;
+        let items_count = reader.read_u16()? as usize;
+        let items_offset = reader.read_u32()? as u64;
+        let vertices_offset = reader.read_u32()? as u64;
+        let vertices_count = reader.read_u16()? as usize;
+        let ambients_count = reader.read_u16()? as u32;
+        let ambients = AreTable {
+            offset: reader.read_u32()? as u64,
+            count: ambients_count,
+        };
+        let variables = AreTable::read(&mut reader)?;
+        let _tiled_object_flags_offset = reader.read_u16()?;
+        let _tiled_object_flags_count = reader.read_u16()?;
+        let script = reader.read_string(8)?;
+        let _explored_bitmask_size = reader.read_u32()?;
+        let _explored_bitmask_offset = reader.read_u32()?;
+        let doors_count = reader.read_u32()? as usize;
+        let doors_offset = reader.read_u32()? as u64;
+        let animations_count = reader.read_u32()?;
+        let animations = AreTable {
+            offset: reader.read_u32()? as u64,
+            count: animations_count,
+        };
+
+        // Read the actors
+        let actors = reader.read_table(actors_offset, actors_count, |reader| {
+            let name = reader.read_string(32)?;
+            let position = ArePoint::read(reader)?;
+            let destination = ArePoint::read(reader)?;
+            let flags = reader.read_u32()?;
+            reader.skip(4)?; // spawned flag, first letter of the CRE resref, unused
+            let animation = reader.read_u32()?;
+            let orientation = reader.read_u16()?;
+            reader.skip(2)?; // unused
+            let removal_timer = reader.read_u32()?;
+            reader.skip(4)?; // movement restriction distances
+            let appearance_schedule = reader.read_u32()?;
+            let _times_talked = reader.read_u32()?;
+            let dialog = reader.read_string(8)?;
+            reader.skip(6 * 8)?; // scripts
+            let cre_name = reader.read_string(8)?;
+            let cre_offset = reader.read_u32()? as u64;
+            let cre_size = reader.read_u32()? as u64;
+            reader.skip(128)?; // unused
+            let cre = if cre_offset != 0 && cre_size != 0 {
+                ActorCre::Embedded(Box::new(CreImporter::import(
+                    &source.embedded(cre_offset, Some(cre_size)),
+                )?))
+            } else {
+                ActorCre::External(cre_name)
+            };
+            Ok(AreActor {
+                name,
+                position,
+                destination,
+                flags,
+                animation,
+                orientation,
+                removal_timer,
+                appearance_schedule,
+                dialog,
+                cre,
+            })
+        })?;
+
+        // Read the regions
+        let regions = reader.read_table(regions_offset, regions_count, |reader| {
+            let name = reader.read_string(32)?;
+            let region_type = AreRegionType::from_u16(reader.read_u16()?);
+            let bounding_box = AreBoundingBox::read(reader)?;
+            let vertex_count = reader.read_u16()?;
+            let vertex_index = reader.read_u32()?;
+            let _trigger_value = reader.read_u32()?;
+            let cursor = reader.read_u32()?;
+            let destination_area = reader.read_string(8)?;
+            let destination_entrance = reader.read_string(32)?;
+            let flags = reader.read_u32()?;
+            let info_text = reader.read_u32()?;
+            reader.skip(0x74 - 0x68)?; // trap fields
+            let key_item = reader.read_string(8)?;
+            let script = reader.read_string(8)?;
+            reader.skip(0xc4 - 0x84)?; // use point, PST sound and dialog fields
+            Ok(AreRegion {
+                name,
+                region_type,
+                bounding_box,
+                vertex_index,
+                vertex_count,
+                cursor,
+                destination_area,
+                destination_entrance,
+                flags,
+                info_text,
+                key_item,
+                script,
+            })
+        })?;
+
+        // Read the entrances
+        let entrances = reader.read_table(entrances_offset, entrances_count, |reader| {
+            let entrance = AreEntrance {
+                name: reader.read_string(32)?,
+                position: ArePoint::read(reader)?,
+                orientation: reader.read_u16()?,
+            };
+            reader.skip(66)?; // unused
+            Ok(entrance)
+        })?;
+
+        // Read the containers
+        let containers = reader.read_table(containers_offset, containers_count, |reader| {
+            let name = reader.read_string(32)?;
+            let position = ArePoint::read(reader)?;
+            let container_type = reader.read_u16()?;
+            let lock_difficulty = reader.read_u16()?;
+            let flags = AreContainerFlags::from_bits_retain(reader.read_u32()?);
+            let trap = AreTrap::read(reader)?;
+            let bounding_box = AreBoundingBox::read(reader)?;
+            let item_index = reader.read_u32()?;
+            let item_count = reader.read_u32()?;
+            let trap_script = reader.read_string(8)?;
+            let vertex_index = reader.read_u32()?;
+            let vertex_count = reader.read_u16()?;
+            let _trigger_range = reader.read_u16()?;
+            let _owner = reader.read_string(32)?;
+            let key_item = reader.read_string(8)?;
+            let _break_difficulty = reader.read_u32()?;
+            let _lockpick_string = reader.read_u32()?;
+            reader.skip(56)?; // unused
+            Ok(AreContainer {
+                name,
+                position,
+                container_type,
+                lock_difficulty,
+                flags,
+                trap,
+                bounding_box,
+                item_index,
+                item_count,
+                trap_script,
+                vertex_index,
+                vertex_count,
+                key_item,
+            })
+        })?;
+
+        // Read the container items
+        let items = reader.read_table(items_offset, items_count, |reader| {
+            Ok(AreItem {
+                resref: reader.read_string(8)?,
+                expiration_time: reader.read_u16()?,
+                charges: [reader.read_u16()?, reader.read_u16()?, reader.read_u16()?],
+                flags: reader.read_u32()?,
+            })
+        })?;
+
+        // Read the vertices shared by regions, containers and doors
+        let vertices = reader.read_table(vertices_offset, vertices_count, ArePoint::read)?;
+
+        // Read the doors
+        let doors = reader.read_table(doors_offset, doors_count, |reader| {
+            let name = reader.read_string(32)?;
+            let wed_door = reader.read_string(8)?;
+            let flags = reader.read_u32()?;
+            let open_vertex_index = reader.read_u32()?;
+            let open_vertex_count = reader.read_u16()?;
+            let closed_vertex_count = reader.read_u16()?;
+            let closed_vertex_index = reader.read_u32()?;
+            let open_bounding_box = AreBoundingBox::read(reader)?;
+            let closed_bounding_box = AreBoundingBox::read(reader)?;
+            reader.skip(0x54 - 0x48)?; // impeded search map cells
+            let hit_points = reader.read_u16()?;
+            let armor_class = reader.read_u16()?;
+            let open_sound = reader.read_string(8)?;
+            let close_sound = reader.read_string(8)?;
+            let cursor = reader.read_u32()?;
+            reader.skip(0x78 - 0x6c)?; // trap fields
+            let key_item = reader.read_string(8)?;
+            let script = reader.read_string(8)?;
+            let _detection_difficulty = reader.read_u32()?;
+            let lock_difficulty = reader.read_u32()?;
+            let open_location = ArePoint::read(reader)?;
+            let closed_location = ArePoint::read(reader)?;
+            let _lockpick_string = reader.read_u32()?;
+            let travel_trigger = reader.read_string(24)?;
+            let _dialog_speaker_name = reader.read_u32()?;
+            let dialog = reader.read_string(8)?;
+            reader.skip(8)?; // unknown
+            Ok(AreDoor {
+                name,
+                wed_door,
+                flags,
+                open_vertex_index,
+                open_vertex_count,
+                closed_vertex_index,
+                closed_vertex_count,
+                open_bounding_box,
+                closed_bounding_box,
+                hit_points,
+                armor_class,
+                open_sound,
+                close_sound,
+                cursor,
+                key_item,
+                script,
+                lock_difficulty,
+                open_location,
+                closed_location,
+                travel_trigger,
+                dialog,
+            })
+        })?;
+
+        Ok(Are {
+            wed,
+            flags,
+            neighbours,
+            area_type,
+            weather,
+            script,
+            actors,
+            regions,
+            spawn_points,
+            entrances,
+            containers,
+            items,
+            vertices,
+            ambients,
+            variables,
+            doors,
+            animations,
+        })
+    }
+}
+
+impl Are {
+    /// Returns the vertices of a polygon referenced by a region, a container or a door.
+    /// Returns `None` if the polygon lies past the end of the vertices table.
+    pub fn polygon(&self, vertex_index: u32, vertex_count: u16) -> Option<&[ArePoint]> {
+        let start = vertex_index as usize;
+        self.vertices.get(start..start + vertex_count as usize)
+    }
+
+    /// Returns the items stored in a container.
+    /// Returns `None` if the items lie past the end of the items table.
+    pub fn container_items(&self, container: &AreContainer) -> Option<&[AreItem]> {
+        let start = container.item_index as usize;
+        self.items.get(start..start + container.item_count as usize)
+    }
+}
+
+/// An ARE file
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Are {
+    /// The WED file with the area graphics, walls and door polygons
+    pub wed: ResourceReference,
+    pub flags: u32,
+    /// The areas reached from the north, east, south and west edges
+    pub neighbours: Vec<AreNeighbour>,
+    pub area_type: u16,
+    pub weather: AreWeather,
+    pub script: String,
+    pub actors: Vec<AreActor>,
+    pub regions: Vec<AreRegion>,
+    pub spawn_points: AreTable,
+    pub entrances: Vec<AreEntrance>,
+    pub containers: Vec<AreContainer>,
+    /// The items of all the containers
+    pub items: Vec<AreItem>,
+    /// The vertices of all the regions, containers and doors polygons
+    pub vertices: Vec<ArePoint>,
+    pub ambients: AreTable,
+    pub variables: AreTable,
+    pub doors: Vec<AreDoor>,
+    pub animations: AreTable,
+}
+
+/// The location of a table that is not parsed yet
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AreTable {
+    pub offset: u64,
+    pub count: u32,
+}
+
+impl AreTable {
+    /// Reads a table offset followed by its count
+    fn read<R: Read>(reader: &mut Reader<R>) -> std::io::Result<AreTable> {
+        Ok(AreTable {
+            offset: reader.read_u32()? as u64,
+            count: reader.read_u32()?,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AreNeighbour {
+    pub area: String,
+    pub flags: u32,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AreWeather {
+    pub rain_probability: u16,
+    pub snow_probability: u16,
+    pub fog_probability: u16,
+    pub lightning_probability: u16,
+    pub wind_speed: u16,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArePoint {
+    pub x: u16,
+    pub y: u16,
+}
+
+impl ArePoint {
+    fn read<R: Read>(reader: &mut Reader<R>) -> std::io::Result<ArePoint> {
+        Ok(ArePoint {
+            x: reader.read_u16()?,
+            y: reader.read_u16()?,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AreBoundingBox {
+    pub left: u16,
+    pub top: u16,
+    pub right: u16,
+    pub bottom: u16,
+}
+
+impl AreBoundingBox {
+    fn read<R: Read>(reader: &mut Reader<R>) -> std::io::Result<AreBoundingBox> {
+        Ok(AreBoundingBox {
+            left: reader.read_u16()?,
+            top: reader.read_u16()?,
+            right: reader.read_u16()?,
+            bottom: reader.read_u16()?,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AreActor {
+    pub name: String,
+    pub position: ArePoint,
+    pub destination: ArePoint,
+    pub flags: u32,
+    pub animation: u32,
+    pub orientation: u16,
+    pub removal_timer: u32,
+    /// Bitmask of the hours of the day when the actor is present
+    pub appearance_schedule: u32,
+    pub dialog: String,
+    pub cre: ActorCre,
+}
+
+/// The creature of an actor
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ActorCre {
+    /// The name of the CRE resource
+    External(String),
+    /// The CRE file stored in the ARE file
+    Embedded(Box<Cre>),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum AreRegionType {
+    Trap,
+    Info,
+    Travel,
+    Unknown(u16),
+}
+
+impl AreRegionType {
+    pub fn from_u16(value: u16) -> AreRegionType {
+        match value {
+            0 => AreRegionType::Trap,
+            1 => AreRegionType::Info,
+            2 => AreRegionType::Travel,
+            value => AreRegionType::Unknown(value),
+        }
+    }
+}
+
+/// A region, also known as info point or trigger
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AreRegion {
+    pub name: String,
+    pub region_type: AreRegionType,
+    pub bounding_box: AreBoundingBox,
+    /// Index into `Are::vertices` of the first vertex of the region polygon
+    pub vertex_index: u32,
+    pub vertex_count: u16,
+    pub cursor: u32,
+    pub destination_area: String,
+    pub destination_entrance: String,
+    pub flags: u32,
+    /// The StrRef displayed by info points
+    pub info_text: u32,
+    pub key_item: String,
+    pub script: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AreEntrance {
+    pub name: String,
+    pub position: ArePoint,
+    pub orientation: u16,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AreContainer {
+    pub name: String,
+    pub position: ArePoint,
+    pub container_type: u16,
+    pub lock_difficulty: u16,
+    pub flags: AreContainerFlags,
+    pub trap: AreTrap,
+    pub bounding_box: AreBoundingBox,
+    /// Index into `Are::items` of the first item of the container
+    pub item_index: u32,
+    pub item_count: u32,
+    pub trap_script: String,
+    /// Index into `Are::vertices` of the first vertex of the container polygon
+    pub vertex_index: u32,
+    pub vertex_count: u16,
+    pub key_item: String,
+}
+
+bitflags::bitflags! {
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    pub struct AreContainerFlags: u32 {
+        const Locked = 1 << 0;
+        const DisableIfNoOwner = 1 << 1;
+        const MagicalLock = 1 << 2;
+        const TrapResets = 1 << 3;
+        const RemoveOnly = 1 << 4;
+        const Disabled = 1 << 5;
+    }
+}
+
+/// The trap of a container
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AreTrap {
+    pub detection_difficulty: u16,
+    pub removal_difficulty: u16,
+    pub trapped: bool,
+    pub detected: bool,
+    /// The point the trap projectile is launched from
+    pub launch_point: ArePoint,
+}
+
+impl AreTrap {
+    fn read<R: Read>(reader: &mut Reader<R>) -> std::io::Result<AreTrap> {
+        Ok(AreTrap {
+            detection_difficulty: reader.read_u16()?,
+            removal_difficulty: reader.read_u16()?,
+            trapped: reader.read_u16()? != 0,
+            detected: reader.read_u16()? != 0,
+            launch_point: ArePoint::read(reader)?,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AreItem {
+    pub resref: String,
+    pub expiration_time: u16,
+    /// The charges of each of the item abilities
+    pub charges: [u16; 3],
+    pub flags: u32,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AreDoor {
+    pub name: String,
+    /// The name of the matching door in the WED file
+    pub wed_door: String,
+    pub flags: u32,
+    pub open_vertex_index: u32,
+    pub open_vertex_count: u16,
+    pub closed_vertex_index: u32,
+    pub closed_vertex_count: u16,
+    pub open_bounding_box: AreBoundingBox,
+    pub closed_bounding_box: AreBoundingBox,
+    pub hit_points: u16,
+    pub armor_class: u16,
+    pub open_sound: String,
+    pub close_sound: String,
+    pub cursor: u32,
+    pub key_item: String,
+    pub script: String,
+    pub lock_difficulty: u32,
+    /// Where a character moves to open the door from the open side
+    pub open_location: ArePoint,
+    /// Where a character moves to open the door from the closed side
+    pub closed_location: ArePoint,
+    pub travel_trigger: String,
+    pub dialog: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::RESOURCES_DIR;
+    use insta::assert_json_snapshot;
+    use std::path::Path;
+
+    #[test]
+    fn test_parse_are() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/ARE/AR9999.ARE"
+        )));
+
+        let are = AreImporter::import(&data).unwrap();
+
+        assert_eq!(are.wed.name, "AR9999");
+        assert_eq!(are.actors.len(), 2);
+        assert_eq!(are.doors.len(), 3);
+        assert_eq!(are.regions.len(), 1);
+        assert_eq!(are.containers.len(), 1);
+        assert_eq!(are.container_items(&are.containers[0]).unwrap().len(), 2);
+
+        let door = &are.doors[0];
+        assert_eq!(door.wed_door, "DOOR01");
+        assert_eq!(
+            are.polygon(door.open_vertex_index, door.open_vertex_count)
+                .unwrap()
+                .len(),
+            4
+        );
+
+        assert_eq!(are.actors[0].cre, ActorCre::External("MINSC6".to_string()));
+
+        assert_json_snapshot!(are);
+    }
+
+    #[test]
+    fn test_parse_are_with_embedded_cre() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/ARE/AR9998.ARE"
+        )));
+
+        let are = AreImporter::import(&data).unwrap();
+
+        let (external, embedded): (Vec<_>, Vec<_>) = are
+            .actors
+            .iter()
+            .partition(|actor| matches!(actor.cre, ActorCre::External(_)));
+        assert_eq!((external.len(), embedded.len()), (1, 1));
+        assert_eq!(external[0].cre, ActorCre::External("MINSC6".to_string()));
+
+        let cre = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/CRE/MINSC6.CRE"
+        )));
+        assert_eq!(
+            embedded[0].cre,
+            ActorCre::Embedded(Box::new(CreImporter::import(&cre).unwrap()))
+        );
+
+        assert_json_snapshot!(are.actors);
+    }
+
+    #[test]
+    fn test_parse_are_trapped_container() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/ARE/AR9998.ARE"
+        )));
+
+        let are = AreImporter::import(&data).unwrap();
+
+        assert_eq!((are.containers.len(), are.items.len()), (1, 2));
+        let container = &are.containers[0];
+        assert_eq!(container.flags, AreContainerFlags::Locked);
+        assert!(container.trap.trapped && !container.trap.detected);
+        assert_eq!(container.trap.launch_point, ArePoint { x: 310, y: 210 });
+
+        let items = are.container_items(container).unwrap();
+        assert_eq!(items.len(), 2);
+
+        assert_json_snapshot!((&are.containers, items));
+    }
+
+    #[test]
+    fn test_invalid_are_ranges() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/ARE/AR9998.ARE"
+        )));
+
+        let mut are = AreImporter::import(&data).unwrap();
+        are.containers[0].item_index = 1;
+        assert!(are.container_items(&are.containers[0]).is_none());
+        are.containers[0].item_index = u32::MAX;
+        assert!(are.container_items(&are.containers[0]).is_none());
+
+        let vertices_count = are.vertices.len() as u32;
+        assert!(are.polygon(vertices_count, 0).is_some());
+        assert!(are.polygon(vertices_count, 1).is_none());
+        assert!(are.polygon(u32::MAX, u16::MAX).is_none());
+    }
+}
@@ -1,10 +1,13 @@
-use std::io::{BufRead, Seek};
+use std::io::{BufRead, Seek, Write};
 
-use image::{ImageBuffer, Rgba};
+use image::{Delay, Frame, ImageBuffer, Rgba, RgbaImage, imageops};
 
 use crate::{
     datasource::Reader,
-    resource::{bam::Type, common::Rgb},
+    resource::{
+        bam::{BamBounds, Type},
+        common::Rgb,
+    },
 };
 
 #[derive(Debug, PartialEq, Eq)]
@@ -21,6 +24,154 @@ pub struct BamV1 {
     pub rle_compressed_color_index: u8,
 }
 
+impl BamV1 {
+    /// Returns the bounding box of the frames of a cycle aligned on their centers.
+    /// Returns `None` if the cycle does not exist or it has no frames.
+    pub fn cycle_bounds(&self, cycle_index: usize) -> Option<BamBounds> {
+        let cycle = self.cycles.get(cycle_index)?;
+        BamBounds::of_frames(
+            cycle
+                .frame_indices
+                .iter()
+                .filter_map(|index| self.frames.get(*index))
+                .map(|frame| (frame.width, frame.height, frame.center_x, frame.center_y)),
+        )
+    }
+
+    /// Renders the frames of a cycle as an animation played at `fps` frames per second.
+    /// Every frame is drawn on a transparent canvas as large as the cycle bounds, so the frames are aligned on their centers.
+    /// Returns an error if the cycle does not exist or `fps` is 0.
+    pub fn cycle_to_frames(&self, cycle_index: usize, fps: u32) -> image::ImageResult<Vec<Frame>> {
+        let cycle = self
+            .cycles
+            .get(cycle_index)
+            .ok_or_else(|| std::io::Error::other(format!("Cycle {} not found.", cycle_index)))?;
+        if fps == 0 {
+            return Err(std::io::Error::other("The frame rate must be positive"))?;
+        }
+        let Some(bounds) = self.cycle_bounds(cycle_index) else {
+            return Ok(Vec::new());
+        };
+
+        cycle
+            .frame_indices
+            .iter()
+            .filter_map(|index| self.frames.get(*index))
+            .map(|frame| {
+                let mut canvas = RgbaImage::new(bounds.width, bounds.height);
+                let (x, y) = bounds.frame_position(frame.center_x, frame.center_y);
+                imageops::replace(
+                    &mut canvas,
+                    &frame.to_image(&self.palette)?,
+                    x as i64,
+                    y as i64,
+                );
+                Ok(Frame::from_parts(
+                    canvas,
+                    0,
+                    0,
+                    Delay::from_numer_denom_ms(1000, fps),
+                ))
+            })
+            .collect()
+    }
+
+    /// Exports the BAM as an uncompressed BAM V1 file.
+    ///
+    /// The file is laid out as: header, frame entries, cycle entries, palette, frame lookup table and frames data.
+    /// Frames data is RLE compressed with `rle_compressed_color_index` when the frame is flagged as `compressed`.
+    pub fn export(&self, w: &mut impl Write) -> std::io::Result<()> {
+        let frames_count = u16::try_from(self.frames.len()).map_err(|_| {
+            std::io::Error::other(format!("Too many frames: {}", self.frames.len()))
+        })?;
+        let cycles_count = u8::try_from(self.cycles.len()).map_err(|_| {
+            std::io::Error::other(format!("Too many cycles: {}", self.cycles.len()))
+        })?;
+
+        let header_size = 24;
+        let frames_offset = header_size;
+        let cycles_offset = frames_offset + 12 * self.frames.len();
+        let palette_offset = cycles_offset + 4 * self.cycles.len();
+        let lookup_offset = palette_offset + 4 * self.palette.len();
+        let lookup_size: usize = self.cycles.iter().map(|c| c.frame_indices.len()).sum();
+        let frames_data_offset = lookup_offset + 2 * lookup_size;
+
+        // header
+        w.write_all(Type::BamV1.signature().as_bytes())?;
+        w.write_all(&frames_count.to_le_bytes())?;
+        w.write_all(&[cycles_count, self.rle_compressed_color_index])?;
+        w.write_all(&(frames_offset as u32).to_le_bytes())?;
+        w.write_all(&(palette_offset as u32).to_le_bytes())?;
+        w.write_all(&(lookup_offset as u32).to_le_bytes())?;
+
+        // frame entries
+        let mut frames_data = Vec::new();
+        for frame in &self.frames {
+            let data_offset = (frames_data_offset + frames_data.len()) as u32;
+            let data_bits = if frame.compressed {
+                frames_data.extend_from_slice(&rle_encode(
+                    &frame.pixel_palette_indexes,
+                    self.rle_compressed_color_index,
+                ));
+                data_offset
+            } else {
+                frames_data.extend_from_slice(&frame.pixel_palette_indexes);
+                data_offset | 0x80000000
+            };
+
+            w.write_all(&(frame.width as u16).to_le_bytes())?;
+            w.write_all(&(frame.height as u16).to_le_bytes())?;
+            w.write_all(&(frame.center_x as i16).to_le_bytes())?;
+            w.write_all(&(frame.center_y as i16).to_le_bytes())?;
+            w.write_all(&data_bits.to_le_bytes())?;
+        }
+
+        // cycle entries
+        let mut lookup_table_index = 0;
+        for cycle in &self.cycles {
+            w.write_all(&(cycle.frame_indices.len() as u16).to_le_bytes())?;
+            w.write_all(&(lookup_table_index as u16).to_le_bytes())?;
+            lookup_table_index += cycle.frame_indices.len();
+        }
+
+        // palette
+        for p in &self.palette {
+            // An alpha of 0 is read as fully opaque for backwards compatibility
+            let alpha = if p.alpha == 255 { 0 } else { p.alpha };
+            w.write_all(&[p.b, p.g, p.r, alpha])?;
+        }
+
+        // frame lookup table
+        for cycle in &self.cycles {
+            for frame_index in &cycle.frame_indices {
+                w.write_all(&(*frame_index as u16).to_le_bytes())?;
+            }
+        }
+
+        // frames data
+        w.write_all(&frames_data)
+    }
+}
+
+/// RLE compresses the palette indexes of a frame.
+/// Only runs of `rle_compressed_color_index` are compressed, each run storing up to 256 pixels.
+fn rle_encode(pixel_palette_indexes: &[u8], rle_compressed_color_index: u8) -> Vec<u8> {
+    let mut compressed = Vec::with_capacity(pixel_palette_indexes.len());
+    let mut iter = pixel_palette_indexes.iter().peekable();
+    while let Some(&pixel_index) = iter.next() {
+        compressed.push(pixel_index);
+        if pixel_index == rle_compressed_color_index {
+            let mut additional_pixels = 0u8;
+            while additional_pixels < u8::MAX && iter.next_if_eq(&&pixel_index).is_some() {
+                additional_pixels += 1;
+            }
+            compressed.push(additional_pixels);
+        }
+    }
+
+    compressed
+}
+
 /// A BAM V1 file importer
 pub struct BamV1Parser;
 
@@ -51,9 +202,7 @@ impl BamV1Parser {
             let mut palette = Vec::with_capacity(palette_entries);
             reader.set_position(palette_offset)?;
 
-            let mut transparency_index = 0;
-
-            for i in 0..palette_entries {
+            for _ in 0..palette_entries {
                 let b = reader.read_u8()?;
                 let g = reader.read_u8()?;
                 let r = reader.read_u8()?;
@@ -62,25 +211,9 @@ impl BamV1Parser {
                     x => x, // Alpha values of 01h .. FFh indicate transparency ranging from almost completely transparent to fully opaque. Full transparency can be realized by using palette index 0.
                 };
 
-                // The transparency index is set to the first occurence of RGB(0,255,0).
-                // If RGB(0,255,0) does not exist in the palette then transparency index is set to 0
-                if transparency_index == 0 && r == 0 && g == 255 && b == 0 {
-                    transparency_index = i;
-                }
-
                 palette.push(Rgb { r, g, b, alpha });
             }
 
-            let _ = std::mem::replace(
-                &mut palette[transparency_index],
-                Rgb {
-                    r: 0,
-                    g: 255,
-                    b: 0,
-                    alpha: 0,
-                },
-            );
-
             palette
         };
 
@@ -88,11 +221,11 @@ impl BamV1Parser {
         let frames = {
             reader.set_position(frames_offset)?;
             let mut frames = Vec::with_capacity(frames_count);
-            for _ in 0..frames_count {
+            for frame_index in 0..frames_count {
                 let width = reader.read_u16()? as u32;
                 let height = reader.read_u16()? as u32;
-                let center_x = reader.read_u16()? as u32;
-                let center_y = reader.read_u16()? as u32;
+                let center_x = reader.read_i16()? as i32;
+                let center_y = reader.read_i16()? as i32;
                 let data_bits = reader.read_u32()?;
                 let data_offset = (data_bits & 0x7fffffff) as u64;
                 let compressed = (data_bits & 0x80000000) == 0;
@@ -106,10 +239,15 @@ impl BamV1Parser {
                     let pixel_index = reader.read_u8()?;
 
                     if compressed && (pixel_index == rle_compressed_color_index) {
-                        let pixels_count = reader.read_u8()?;
-                        for _ in 0..=pixels_count {
-                            pixel_palette_indexes.push(pixel_index);
+                        // The run length byte stores the number of additional pixels
+                        let run_length = reader.read_u8()? as usize + 1;
+                        let decoded_size = pixel_palette_indexes.len() + run_length;
+                        if decoded_size > size {
+                            return Err(std::io::Error::other(format!(
+                                "Frame {frame_index}: RLE run of {run_length} pixels overflows the frame size of {size} pixels"
+                            )));
                         }
+                        pixel_palette_indexes.resize(decoded_size, pixel_index);
                     } else {
                         pixel_palette_indexes.push(pixel_index);
                     }
@@ -122,6 +260,7 @@ impl BamV1Parser {
                     height,
                     center_x,
                     center_y,
+                    compressed,
                     pixel_palette_indexes,
                 });
             }
@@ -165,6 +304,58 @@ impl BamV1Parser {
     }
 }
 
+/// Options to export a BAM V1 frame to an image
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BamV1ExportOptions {
+    /// How the transparent color of the palette is selected
+    pub transparency: Transparency,
+    /// How the pixels referencing an entry past the end of the palette are exported
+    pub invalid_index: InvalidPaletteIndex,
+}
+
+/// How a pixel referencing an entry past the end of the palette is exported,
+/// e.g. in a corrupted file or in a file with a truncated palette
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InvalidPaletteIndex {
+    /// The pixel is exported as a fully transparent RGB(0,255,0), like the transparent palette entry
+    #[default]
+    Transparent,
+    /// The pixel is exported with the given color
+    Color(Rgb),
+    /// The export fails
+    Strict,
+}
+
+/// The strategy used to select the transparent palette entry of a BAM V1 file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Transparency {
+    /// The transparency index is set to the first occurence of RGB(0,255,0).
+    /// If RGB(0,255,0) does not exist in the palette then transparency index is set to 0.
+    /// This is the rule used by the engine.
+    #[default]
+    Auto,
+    /// The palette entry at the given index is transparent
+    Index(u8),
+    /// The palette is used as is, without keying any color
+    None,
+}
+
+impl Transparency {
+    /// Returns the index of the transparent palette entry, if any
+    pub fn transparent_index(&self, palette: &[Rgb]) -> Option<usize> {
+        match self {
+            Transparency::Auto => Some(
+                palette
+                    .iter()
+                    .position(|p| p.r == 0 && p.g == 255 && p.b == 0)
+                    .unwrap_or(0),
+            ),
+            Transparency::Index(index) => Some(*index as usize),
+            Transparency::None => None,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct BamV1Cycle {
     pub frame_indices: Vec<usize>,
@@ -174,20 +365,90 @@ pub struct BamV1Cycle {
 pub struct BamV1Frame {
     pub width: u32,
     pub height: u32,
-    pub center_x: u32,
-    pub center_y: u32,
+    /// The position of the anchor of the frame relative to its top left corner.
+    /// The anchor can be outside the frame, so the coordinates can be negative.
+    pub center_x: i32,
+    pub center_y: i32,
+    /// Whether the frame data is RLE compressed in the file.
+    /// `pixel_palette_indexes` are always decoded, the flag only drives how the frame is exported.
+    pub compressed: bool,
     /// The indexes of the pixels in the palette
     pub pixel_palette_indexes: Vec<u8>,
 }
 
 impl BamV1Frame {
+    /// Returns the frame mirrored left to right, e.g. a west-facing frame from an east-facing one.
+    /// The anchor is mirrored too, the palette indexes are kept.
+    pub fn flipped_horizontal(&self) -> BamV1Frame {
+        let pixel_palette_indexes = self
+            .pixel_palette_indexes
+            .chunks(self.width.max(1) as usize)
+            .flat_map(|row| row.iter().rev().copied())
+            .collect();
+        BamV1Frame {
+            center_x: self.width as i32 - self.center_x,
+            pixel_palette_indexes,
+            ..*self
+        }
+    }
+
+    /// Returns the frame mirrored top to bottom.
+    /// The anchor is mirrored too, the palette indexes are kept.
+    pub fn flipped_vertical(&self) -> BamV1Frame {
+        let pixel_palette_indexes = self
+            .pixel_palette_indexes
+            .chunks(self.width.max(1) as usize)
+            .rev()
+            .flatten()
+            .copied()
+            .collect();
+        BamV1Frame {
+            center_y: self.height as i32 - self.center_y,
+            pixel_palette_indexes,
+            ..*self
+        }
+    }
+
     /// Exports the frame to an image.
-    /// The image type is determined by the file extension.
+    /// The transparent color is selected with `Transparency::Auto`.
     pub fn to_image(&self, palette: &[Rgb]) -> image::ImageResult<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+        self.to_image_with(&BamV1ExportOptions::default(), palette)
+    }
+
+    /// Exports the frame to an image using the given options.
+    /// Pixels referencing the transparent palette entry are exported as a fully transparent RGB(0,255,0).
+    /// Pixels referencing an entry past the end of the palette are exported according to `options.invalid_index`.
+    pub fn to_image_with(
+        &self,
+        options: &BamV1ExportOptions,
+        palette: &[Rgb],
+    ) -> image::ImageResult<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+        if options.invalid_index == InvalidPaletteIndex::Strict
+            && let Some(palette_index) = self
+                .pixel_palette_indexes
+                .iter()
+                .find(|palette_index| **palette_index as usize >= palette.len())
+        {
+            return Err(std::io::Error::other(format!(
+                "Palette index {} out of the palette of {} colors",
+                palette_index,
+                palette.len()
+            )))?;
+        }
+
+        let transparent_index = options.transparency.transparent_index(palette);
         Ok(ImageBuffer::from_fn(self.width, self.height, |x, y| {
             let idx = (y * self.width + x) as usize;
-            let p = &palette[self.pixel_palette_indexes[idx] as usize];
-            Rgba([p.r, p.g, p.b, p.alpha])
+            let palette_index = self.pixel_palette_indexes[idx] as usize;
+            if Some(palette_index) == transparent_index {
+                return Rgba([0, 255, 0, 0]);
+            }
+            match (palette.get(palette_index).copied(), options.invalid_index) {
+                (Some(p), _) | (None, InvalidPaletteIndex::Color(p)) => {
+                    Rgba([p.r, p.g, p.b, p.alpha])
+                }
+                (None, _) => Rgba([0, 255, 0, 0]),
+            }
         }))
     }
 }
@@ -213,6 +474,202 @@ mod tests {
         assert!(res.is_err());
     }
 
+    #[test]
+    fn test_parse_bam_v1_should_fail_if_rle_run_overflows_frame() {
+        // A single 2x2 RLE compressed frame whose only run decodes to 6 pixels
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"BAM V1  ");
+        bytes.extend_from_slice(&1u16.to_le_bytes()); // frames count
+        bytes.push(0); // cycles count
+        bytes.push(0); // RLE compressed color index
+        bytes.extend_from_slice(&24u32.to_le_bytes()); // frames offset
+        bytes.extend_from_slice(&36u32.to_le_bytes()); // palette offset
+        bytes.extend_from_slice(&40u32.to_le_bytes()); // lookup offset
+        // frame entry
+        bytes.extend_from_slice(&2u16.to_le_bytes()); // width
+        bytes.extend_from_slice(&2u16.to_le_bytes()); // height
+        bytes.extend_from_slice(&0u16.to_le_bytes()); // center x
+        bytes.extend_from_slice(&0u16.to_le_bytes()); // center y
+        bytes.extend_from_slice(&40u32.to_le_bytes()); // data offset, RLE compressed
+        // palette
+        bytes.extend_from_slice(&[0, 255, 0, 0]);
+        // frame data
+        bytes.extend_from_slice(&[0, 5]);
+
+        let data = DataSource::new(bytes);
+        let mut reader = data.reader().unwrap();
+        let err = BamV1Parser::import(&mut reader).unwrap_err();
+        assert!(err.to_string().contains("overflows the frame size"));
+    }
+
+    #[test]
+    fn test_rle_encode() {
+        assert_eq!(rle_encode(&[0, 0, 0, 0, 1, 2], 0), vec![0, 3, 1, 2]);
+        assert_eq!(rle_encode(&[1, 2, 3], 0), vec![1, 2, 3]);
+        assert_eq!(rle_encode(&[0; 300], 0), vec![0, 255, 0, 43]);
+        assert_eq!(rle_encode(&[0, 1], 0), vec![0, 0, 1]);
+    }
+
+    #[test]
+    fn test_export_bam_v1_roundtrip() {
+        for path in [
+            "BAM_V1/01/1chan03B_decompressed.BAM",
+            "BAM_V1/02/SPHEART_decompressed.BAM",
+        ] {
+            let data = DataSource::new(Path::new(&format!("{RESOURCES_DIR}/resources/{path}")));
+            let bam = BamV1Parser::import(&mut data.reader().unwrap()).unwrap();
+
+            let mut exported = Vec::new();
+            bam.export(&mut exported).unwrap();
+
+            let data = DataSource::new(exported);
+            let reimported = BamV1Parser::import(&mut data.reader().unwrap()).unwrap();
+
+            assert_eq!(bam, reimported);
+        }
+    }
+
+    #[test]
+    fn test_bam_v1_frame_to_image_invalid_palette_index() {
+        let palette = vec![
+            Rgb {
+                r: 0,
+                g: 255,
+                b: 0,
+                alpha: 255,
+            },
+            Rgb {
+                r: 10,
+                g: 20,
+                b: 30,
+                alpha: 255,
+            },
+        ];
+        // The index 5 is past the end of the palette
+        let frame = BamV1Frame {
+            width: 2,
+            height: 1,
+            center_x: 0,
+            center_y: 0,
+            compressed: false,
+            pixel_palette_indexes: vec![1, 5],
+        };
+
+        let image = frame.to_image(&palette).unwrap();
+        assert_eq!(image.get_pixel(0, 0), &Rgba([10, 20, 30, 255]));
+        assert_eq!(image.get_pixel(1, 0), &Rgba([0, 255, 0, 0]));
+
+        let fallback = Rgb {
+            r: 255,
+            g: 0,
+            b: 255,
+            alpha: 255,
+        };
+        let image = frame
+            .to_image_with(
+                &BamV1ExportOptions {
+                    invalid_index: InvalidPaletteIndex::Color(fallback),
+                    ..Default::default()
+                },
+                &palette,
+            )
+            .unwrap();
+        assert_eq!(image.get_pixel(0, 0), &Rgba([10, 20, 30, 255]));
+        assert_eq!(image.get_pixel(1, 0), &Rgba([255, 0, 255, 255]));
+
+        let strict = BamV1ExportOptions {
+            invalid_index: InvalidPaletteIndex::Strict,
+            ..Default::default()
+        };
+        assert!(frame.to_image_with(&strict, &palette).is_err());
+        assert!(frame.to_image_with(&strict, &[palette[1]; 6]).is_ok());
+    }
+
+    #[test]
+    fn test_bam_v1_cycle_to_frames() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/BAM_V1/02/SPHEART_decompressed.BAM"
+        )));
+        let bam = BamV1Parser::import(&mut data.reader().unwrap()).unwrap();
+        let frames_count = bam.cycles[0].frame_indices.len();
+        let bounds = bam.cycle_bounds(0).unwrap();
+
+        let frames = bam.cycle_to_frames(0, 10).unwrap();
+
+        assert_eq!(frames.len(), frames_count);
+        assert!(
+            frames
+                .iter()
+                .all(|frame| frame.buffer().dimensions() == (bounds.width, bounds.height))
+        );
+        let total_delay_ms: u32 = frames
+            .iter()
+            .map(|frame| {
+                let (numer, denom) = frame.delay().numer_denom_ms();
+                numer / denom
+            })
+            .sum();
+        assert_eq!(total_delay_ms, frames_count as u32 * 100);
+
+        assert!(bam.cycle_to_frames(bam.cycles.len(), 10).is_err());
+        assert!(bam.cycle_to_frames(0, 0).is_err());
+    }
+
+    #[test]
+    fn test_flip_bam_v1_frame() {
+        let frame = BamV1Frame {
+            width: 3,
+            height: 2,
+            center_x: 1,
+            center_y: -4,
+            compressed: true,
+            pixel_palette_indexes: vec![1, 2, 3, 4, 5, 6],
+        };
+
+        let horizontal = frame.flipped_horizontal();
+        assert_eq!(horizontal.pixel_palette_indexes, vec![3, 2, 1, 6, 5, 4]);
+        assert_eq!((horizontal.center_x, horizontal.center_y), (2, -4));
+
+        let vertical = frame.flipped_vertical();
+        assert_eq!(vertical.pixel_palette_indexes, vec![4, 5, 6, 1, 2, 3]);
+        assert_eq!((vertical.center_x, vertical.center_y), (1, 6));
+
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/BAM_V1/02/SPHEART_decompressed.BAM"
+        )));
+        let bam = BamV1Parser::import(&mut data.reader().unwrap()).unwrap();
+        for frame in &bam.frames {
+            assert_eq!(&frame.flipped_horizontal().flipped_horizontal(), frame);
+            assert_eq!(&frame.flipped_vertical().flipped_vertical(), frame);
+        }
+    }
+
+    #[test]
+    fn test_export_bam_v1_preserves_compression_flags() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/BAM_V1/02/SPHEART_decompressed.BAM"
+        )));
+        let mut bam = BamV1Parser::import(&mut data.reader().unwrap()).unwrap();
+        assert!(bam.frames.iter().all(|frame| frame.compressed));
+
+        bam.frames[1].compressed = false;
+
+        let mut exported = Vec::new();
+        bam.export(&mut exported).unwrap();
+
+        let data = DataSource::new(exported);
+        let reimported = BamV1Parser::import(&mut data.reader().unwrap()).unwrap();
+
+        let flags = reimported
+            .frames
+            .iter()
+            .map(|frame| frame.compressed)
+            .collect::<Vec<_>>();
+        assert!(!flags[1]);
+        assert!(flags.iter().enumerate().all(|(i, flag)| *flag == (i != 1)));
+        assert_eq!(bam, reimported);
+    }
+
     #[test]
     fn test_parse_bam_v1_01() {
         let data = DataSource::new(Path::new(&format!(
@@ -255,6 +712,69 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_bam_v1_frame_to_image_transparency() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/BAM_V1/01/1chan03B_decompressed.BAM"
+        )));
+
+        let mut reader = data.reader().unwrap();
+        let bam = BamV1Parser::import(&mut reader).unwrap();
+        let frame = &bam.frames[0];
+
+        let auto_index = Transparency::Auto.transparent_index(&bam.palette).unwrap();
+
+        // Auto is the default
+        let auto = frame
+            .to_image_with(&BamV1ExportOptions::default(), &bam.palette)
+            .unwrap();
+        assert_eq!(auto, frame.to_image(&bam.palette).unwrap());
+
+        // None keeps the raw palette
+        let none = frame
+            .to_image_with(
+                &BamV1ExportOptions {
+                    transparency: Transparency::None,
+                    ..Default::default()
+                },
+                &bam.palette,
+            )
+            .unwrap();
+
+        // Index keys only the selected entry
+        let keyed_index = (auto_index as u8).wrapping_add(1);
+        let index = frame
+            .to_image_with(
+                &BamV1ExportOptions {
+                    transparency: Transparency::Index(keyed_index),
+                    ..Default::default()
+                },
+                &bam.palette,
+            )
+            .unwrap();
+
+        for (i, palette_index) in frame.pixel_palette_indexes.iter().enumerate() {
+            let x = i as u32 % frame.width;
+            let y = i as u32 / frame.width;
+            let p = &bam.palette[*palette_index as usize];
+            let raw = Rgba([p.r, p.g, p.b, p.alpha]);
+
+            assert_eq!(none.get_pixel(x, y), &raw);
+
+            if *palette_index as usize == auto_index {
+                assert_eq!(auto.get_pixel(x, y), &Rgba([0, 255, 0, 0]));
+            } else {
+                assert_eq!(auto.get_pixel(x, y), &raw);
+            }
+
+            if *palette_index == keyed_index {
+                assert_eq!(index.get_pixel(x, y), &Rgba([0, 255, 0, 0]));
+            } else {
+                assert_eq!(index.get_pixel(x, y), &raw);
+            }
+        }
+    }
+
     #[test]
     fn test_parse_bam_v1_02() {
         let data = DataSource::new(Path::new(&format!(
@@ -280,9 +800,9 @@ mod tests {
 
         for (i, frame) in bam.frames.iter().enumerate() {
             assert!(frame.center_x > 0);
-            assert!(frame.center_x < frame.width);
+            assert!(frame.center_x < frame.width as i32);
             assert!(frame.center_y > 0);
-            assert!(frame.center_y < frame.height);
+            assert!(frame.center_y < frame.height as i32);
             assert_eq!(
                 frame.pixel_palette_indexes.len(),
                 (frame.width * frame.height) as usize
@@ -3,9 +3,13 @@ use std::io::{BufRead, Seek};
 use image::{ImageBuffer, Rgba};
 
 use crate::{
-    datasource::{DataSource, Importer, Reader},
+    datasource::Reader,
     fs::{CaseInsensitiveFS, CaseInsensitivePath},
-    resource::{bam::Type, pvr::PvrzImporter},
+    resource::{
+        bam::{BamBounds, Type},
+        key::ResourceType,
+        pvr::{PvrzBlock, PvrzCache, PvrzPageImporter, blit_block},
+    },
 };
 
 /// A BAM V2 file importer
@@ -39,8 +43,8 @@ impl BamV2Parser {
             for _ in 0..frames_count {
                 let width = reader.read_u16()? as u32;
                 let height = reader.read_u16()? as u32;
-                let center_x = reader.read_u16()? as u32;
-                let center_y = reader.read_u16()? as u32;
+                let center_x = reader.read_i16()? as i32;
+                let center_y = reader.read_i16()? as i32;
                 let data_blocks_start_index = reader.read_u16()? as usize;
                 let data_blocks_count = reader.read_u16()? as usize;
 
@@ -134,8 +138,10 @@ pub struct BamV2Cycle {
 pub struct BamV2Frame {
     pub width: u32,
     pub height: u32,
-    pub center_x: u32,
-    pub center_y: u32,
+    /// The position of the anchor of the frame relative to its top left corner.
+    /// The anchor can be outside the frame, so the coordinates can be negative.
+    pub center_x: i32,
+    pub center_y: i32,
     /// Count of data_block entries in this cycle
     pub data_blocks_count: usize,
     /// Start index of data_block entries in this cycle
@@ -157,17 +163,60 @@ pub struct BamV2DataBlock {
 impl BamV2DataBlock {
     /// Returns the MOSxxxx.PVRZ files name associated with this data block
     pub fn pvrz_name(&self) -> String {
-        format!("MOS{:04}.PVRZ", self.pvrz_page)
+        self.pvrz_name_with("MOS")
+    }
+
+    /// Returns the name of the PVRZ file associated with this data block, starting with `prefix`.
+    /// The page number is zero-padded to four digits, longer page numbers are kept whole.
+    pub fn pvrz_name_with(&self, prefix: &str) -> String {
+        format!("{}{:04}.PVRZ", prefix, self.pvrz_page)
+    }
+
+    /// Returns the block of the PVRZ file copied into the frame
+    pub fn pvrz_block(&self) -> PvrzBlock {
+        PvrzBlock {
+            source_x: self.source_x_coordinate,
+            source_y: self.source_y_coordinate,
+            target_x: self.target_x_coordinate,
+            target_y: self.target_y_coordinate,
+            width: self.width,
+            height: self.height,
+        }
     }
 }
 
 impl BamV2 {
+    /// Returns the bounding box of the frames of a cycle aligned on their centers.
+    /// Returns `None` if the cycle does not exist or it has no frames.
+    pub fn cycle_bounds(&self, cycle_index: usize) -> Option<BamBounds> {
+        let cycle = self.cycles.get(cycle_index)?;
+        BamBounds::of_frames(
+            self.frames
+                .iter()
+                .skip(cycle.frame_start_index)
+                .take(cycle.frames_count)
+                .map(|frame| (frame.width, frame.height, frame.center_x, frame.center_y)),
+        )
+    }
+
     /// Exports the frame to an image file.
     /// The image type is determined by the file extension.
     pub fn frame_to_image(
         &self,
         frame_index: usize,
         fs: &CaseInsensitiveFS,
+    ) -> image::ImageResult<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+        self.frame_to_image_cached(frame_index, fs, &mut PvrzCache::new())
+    }
+
+    /// Same as `frame_to_image`, reusing the PVRZ files already decoded in the cache
+    /// and adding the ones decoded for this frame.
+    /// A cached PVRZ file is not read again unless its size or modification time changed.
+    pub fn frame_to_image_cached(
+        &self,
+        frame_index: usize,
+        fs: &CaseInsensitiveFS,
+        cache: &mut PvrzCache,
     ) -> image::ImageResult<ImageBuffer<Rgba<u8>, Vec<u8>>> {
         let frame = if let Some(frame) = self.frames.get(frame_index) {
             frame
@@ -178,8 +227,16 @@ impl BamV2 {
             )))?;
         };
 
-        let data_blocks = &self.data_blocks[frame.data_blocks_start_index
-            ..frame.data_blocks_start_index + frame.data_blocks_count];
+        let data_blocks = self
+            .data_blocks
+            .get(
+                frame.data_blocks_start_index
+                    ..frame.data_blocks_start_index + frame.data_blocks_count,
+            )
+            .ok_or(std::io::Error::other(format!(
+                "Data blocks of frame {} not found.",
+                frame_index
+            )))?;
 
         let mut target_image = image::ImageBuffer::new(frame.width, frame.height);
         let target_image_buffer = target_image.as_mut();
@@ -192,30 +249,26 @@ impl BamV2 {
                     block.pvrz_name()
                 )))?;
 
-            let datasource = DataSource::new(pvrz_path);
-
-            // TODO: Suboptimal: PVRZ images should be cached
-            let source_header = PvrzImporter::import(&datasource)?;
-            let source_image = PvrzImporter::to_image(&source_header, &datasource)?;
-            let source_image_buffer = source_image.as_raw();
-
-            for row in 0..block.height {
-                let block_source_row = block.source_y_coordinate + row;
-                let block_destination_row = block.target_y_coordinate + row;
-
-                let source_start = ((block_source_row * source_header.width
-                    + block.source_x_coordinate)
-                    * 4) as usize;
-                let source_end = source_start + (block.width * 4) as usize;
-
-                let target_start = ((block_destination_row * frame.width
-                    + block.target_x_coordinate)
-                    * 4) as usize;
-                let target_end = target_start + (block.width * 4) as usize;
-
-                target_image_buffer[target_start..target_end]
-                    .copy_from_slice(&source_image_buffer[source_start..source_end]);
-            }
+            let page = cache.get_or_import_file::<PvrzPageImporter>(
+                &block.pvrz_name(),
+                ResourceType::Pvrz,
+                &pvrz_path,
+            )?;
+            let (source_header, source_image) = page.as_ref();
+
+            blit_block(
+                target_image_buffer,
+                frame.width,
+                source_image.as_raw(),
+                source_header.width,
+                &block.pvrz_block(),
+            )
+            .map_err(|err| {
+                std::io::Error::new(
+                    err.kind(),
+                    format!("Frame {}, {}: {}", frame_index, block.pvrz_name(), err),
+                )
+            })?;
         }
 
         Ok(target_image)
@@ -247,6 +300,14 @@ mod tests {
 
         data_block.pvrz_page = 1234;
         assert_eq!(data_block.pvrz_name(), "MOS1234.PVRZ");
+
+        data_block.pvrz_page = 42;
+        assert_eq!(data_block.pvrz_name_with("TIS"), "TIS0042.PVRZ");
+        assert_eq!(data_block.pvrz_name_with("MOS"), data_block.pvrz_name());
+
+        data_block.pvrz_page = 12345;
+        assert_eq!(data_block.pvrz_name(), "MOS12345.PVRZ");
+        assert_eq!(data_block.pvrz_name_with("TIS"), "TIS12345.PVRZ");
     }
 
     #[test]
@@ -311,4 +372,42 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_frame_to_image_cached() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/BAM_V2/1CHELM03.BAM"
+        )));
+        let bam = BamV2Parser::import(&mut data.reader().unwrap()).unwrap();
+        let fs = CaseInsensitiveFS::new(format!("{RESOURCES_DIR}/resources/BAM_V2/")).unwrap();
+
+        // All the blocks of the frame are stored in MOS0000.PVRZ
+        let mut cache = PvrzCache::new();
+        let image = bam.frame_to_image_cached(0, &fs, &mut cache).unwrap();
+        assert_eq!(cache.len(), 1);
+        assert_eq!(image, bam.frame_to_image(0, &fs).unwrap());
+
+        let cached = bam.frame_to_image_cached(0, &fs, &mut cache).unwrap();
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cached, image);
+    }
+
+    #[test]
+    fn test_frame_to_image_fails_if_block_out_of_bounds() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/BAM_V2/1CHELM03.BAM"
+        )));
+        let mut bam = BamV2Parser::import(&mut data.reader().unwrap()).unwrap();
+        let fs = CaseInsensitiveFS::new(format!("{RESOURCES_DIR}/resources/BAM_V2/")).unwrap();
+
+        // The block ends after the right border of the 128 pixels wide frame
+        bam.data_blocks[0].target_x_coordinate = 100;
+        assert!(bam.frame_to_image(0, &fs).is_err());
+
+        // The block starts after the end of the PVRZ image
+        bam.data_blocks[0].target_x_coordinate = 0;
+        bam.data_blocks[0].source_y_coordinate = u32::MAX - 10;
+        let err = bam.frame_to_image(0, &fs).unwrap_err();
+        assert!(err.to_string().contains("MOS0000.PVRZ"));
+    }
 }
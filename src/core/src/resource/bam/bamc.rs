@@ -1,8 +1,10 @@
-use std::io::BufRead;
+use std::io::{BufRead, Write};
+
+use flate2::{Compression, write::ZlibEncoder};
 
 use crate::{
     datasource::Reader,
-    resource::bam::{Bam, BamImporter, Type},
+    resource::bam::{Bam, BamImporter, BamV1, Type},
 };
 
 /// A BAMC file importer
@@ -20,6 +22,11 @@ impl BamcParser {
             )));
         };
 
+        Self::import_after_signature(reader)
+    }
+
+    /// Imports a BAMC file whose signature was already read
+    pub fn import_after_signature<R: BufRead>(reader: &mut Reader<R>) -> std::io::Result<Bam> {
         let _uncompressed_size = reader.read_u32()?;
 
         let mut uncompressed_reader = reader.as_zip_reader().decode_all()?;
@@ -28,6 +35,23 @@ impl BamcParser {
     }
 }
 
+impl BamV1 {
+    /// Exports the BAM as a compressed BAMC file.
+    /// The BAM V1 body is zlib compressed and prefixed by the BAMC signature and its uncompressed size.
+    pub fn export_compressed(&self, w: &mut impl Write) -> std::io::Result<()> {
+        let mut uncompressed = Vec::new();
+        self.export(&mut uncompressed)?;
+
+        w.write_all(Type::BamC.signature().as_bytes())?;
+        w.write_all(&(uncompressed.len() as u32).to_le_bytes())?;
+
+        let mut encoder = ZlibEncoder::new(w, Compression::default());
+        encoder.write_all(&uncompressed)?;
+        encoder.finish()?;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::path::Path;
@@ -94,4 +118,41 @@ mod tests {
 
         assert_eq!(Bam::V1(bam_from_decompressed), bam_from_compressed);
     }
+
+    #[test]
+    fn test_export_bamc_roundtrip() {
+        for (decompressed, compressed) in [
+            (
+                "BAM_V1/01/1chan03B_decompressed.BAM",
+                "BAM_V1/01/1chan03B_compressed.BAM",
+            ),
+            (
+                "BAM_V1/02/SPHEART_decompressed.BAM",
+                "BAM_V1/02/SPHEART_compressed.BAM",
+            ),
+        ] {
+            let bam = {
+                let data = DataSource::new(Path::new(&format!(
+                    "{RESOURCES_DIR}/resources/{decompressed}"
+                )));
+                BamV1Parser::import(&mut data.reader().unwrap()).unwrap()
+            };
+
+            let bam_from_compressed = {
+                let data = DataSource::new(Path::new(&format!(
+                    "{RESOURCES_DIR}/resources/{compressed}"
+                )));
+                BamcParser::import(&mut data.reader().unwrap()).unwrap()
+            };
+
+            let mut exported = Vec::new();
+            bam.export_compressed(&mut exported).unwrap();
+
+            let data = DataSource::new(exported);
+            let reimported = BamcParser::import(&mut data.reader().unwrap()).unwrap();
+
+            assert_eq!(reimported, bam_from_compressed);
+            assert_eq!(reimported, Bam::V1(bam));
+        }
+    }
 }
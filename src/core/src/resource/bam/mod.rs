@@ -1,11 +1,13 @@
-use std::io::{BufRead, Read, Seek};
+use std::io::{BufRead, Cursor, Read, Seek};
 
 use crate::{
     datasource::{Importer, Reader},
     resource::bam::{bam_v1::BamV1Parser, bam_v2::BamV2Parser, bamc::BamcParser},
 };
 
-pub use bam_v1::BamV1;
+pub use bam_v1::{
+    BamV1, BamV1Cycle, BamV1ExportOptions, BamV1Frame, InvalidPaletteIndex, Transparency,
+};
 pub use bam_v2::BamV2;
 
 mod bam_v1;
@@ -25,6 +27,26 @@ impl Importer for BamImporter {
 }
 
 impl BamImporter {
+    /// Imports a BAM file from a reader that cannot seek back, e.g. a network stream.
+    /// The signature is read once and the file is parsed forward: a BAMC file is decompressed
+    /// as it is read, the other files are buffered in memory as their offsets need random access.
+    pub fn from_stream<R: BufRead>(reader: &mut Reader<R>) -> std::io::Result<Bam> {
+        let signature = reader.read_exact::<8>()?;
+
+        match detect_from_signature(&signature) {
+            Some(Type::BamC) => BamcParser::import_after_signature(reader),
+            Some(Type::BamV1 | Type::BamV2) => {
+                let mut data = signature.to_vec();
+                reader.data.read_to_end(&mut data)?;
+                Self::from_reader(&mut Reader {
+                    data: Cursor::new(data),
+                    charset: reader.charset,
+                })
+            }
+            None => Err(unsupported_signature(&signature)),
+        }
+    }
+
     /// Imports a BAM file
     fn from_reader<R: BufRead + Seek>(reader: &mut Reader<R>) -> std::io::Result<Bam> {
         let position = reader.position()?;
@@ -67,32 +89,83 @@ impl Type {
     }
 }
 
+/// A BAM file.
+/// Compressed BAMC files always wrap a BAM V1, so they are returned as `Bam::V1` once decompressed.
 #[derive(Debug, PartialEq, Eq)]
 pub enum Bam {
     V1(BamV1),
     V2(BamV2),
 }
 
+/// The bounding box of a set of frames aligned on their centers.
+/// The coordinates of the top left corner are relative to the common center, so the frames do not jitter when played in sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BamBounds {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl BamBounds {
+    /// Computes the bounds of frames given as `(width, height, center_x, center_y)`.
+    /// A negative center places the anchor outside the frame, on its left or above it.
+    /// Returns `None` if there are no frames.
+    pub fn of_frames(frames: impl IntoIterator<Item = (u32, u32, i32, i32)>) -> Option<BamBounds> {
+        let (left, top, right, bottom) = frames.into_iter().fold(None, |bounds, frame| {
+            let (width, height, center_x, center_y) = frame;
+            let left = -(center_x as i64);
+            let top = -(center_y as i64);
+            let right = left + width as i64;
+            let bottom = top + height as i64;
+            Some(match bounds {
+                None => (left, top, right, bottom),
+                Some((l, t, r, b)) => (left.min(l), top.min(t), right.max(r), bottom.max(b)),
+            })
+        })?;
+        Some(BamBounds {
+            x: left as i32,
+            y: top as i32,
+            width: (right - left) as u32,
+            height: (bottom - top) as u32,
+        })
+    }
+
+    /// Returns the position of the top left corner of a frame inside the bounds
+    pub fn frame_position(&self, center_x: i32, center_y: i32) -> (u32, u32) {
+        (
+            (-(center_x as i64) - self.x as i64) as u32,
+            (-(center_y as i64) - self.y as i64) as u32,
+        )
+    }
+}
+
 /// Detects the type of a BAM file
 fn detect_bam_type<R: Read>(reader: &mut Reader<R>) -> std::io::Result<Type> {
-    let value = reader.read_string(8)?;
+    let signature = reader.read_exact::<8>()?;
+    detect_from_signature(&signature).ok_or_else(|| unsupported_signature(&signature))
+}
 
-    match value.as_str() {
-        BAM_V1_SIGNATURE => Ok(Type::BamV1),
-        BAM_V2_SIGNATURE => Ok(Type::BamV2),
-        BAMC_SIGNATURE => Ok(Type::BamC),
-        val => Err(std::io::Error::other(format!(
-            "Unsupported BAM file: {}",
-            val
-        ))),
-    }
+/// Detects the type of a BAM file from its 8 bytes signature.
+/// Returns `None` if the signature is not a BAM one.
+pub fn detect_from_signature(signature: &[u8; 8]) -> Option<Type> {
+    [Type::BamV1, Type::BamV2, Type::BamC]
+        .into_iter()
+        .find(|r#type| r#type.signature().as_bytes() == signature)
+}
+
+fn unsupported_signature(signature: &[u8; 8]) -> std::io::Error {
+    std::io::Error::other(format!(
+        "Unsupported BAM file: {}",
+        String::from_utf8_lossy(signature)
+    ))
 }
 
 #[cfg(test)]
 mod tests {
     use std::path::Path;
 
-    use crate::{datasource::DataSource, test_utils::RESOURCES_DIR};
+    use crate::{datasource::DataSource, resource::common::Rgb, test_utils::RESOURCES_DIR};
 
     use super::*;
 
@@ -131,4 +204,199 @@ mod tests {
             Type::BamC
         );
     }
+
+    #[test]
+    fn test_detect_from_signature() {
+        assert_eq!(detect_from_signature(b"BAM V1  "), Some(Type::BamV1));
+        assert_eq!(detect_from_signature(b"BAM V2  "), Some(Type::BamV2));
+        assert_eq!(detect_from_signature(b"BAMCV1  "), Some(Type::BamC));
+        assert_eq!(detect_from_signature(b"BAM V1\0\0"), None);
+        assert_eq!(detect_from_signature(b"MOS V1  "), None);
+    }
+
+    #[test]
+    fn test_import_bam_from_stream() {
+        for (name, expected) in [
+            ("BAM_V1/01/1chan03B_decompressed.BAM", Type::BamV1),
+            ("BAM_V1/01/1chan03B_compressed.BAM", Type::BamV1),
+            ("BAM_V2/1CHELM03.BAM", Type::BamV2),
+        ] {
+            let path = format!("{RESOURCES_DIR}/resources/{name}");
+            let bytes = std::fs::read(&path).unwrap();
+            // A byte slice can be read but not seeked
+            let mut reader = Reader {
+                data: bytes.as_slice(),
+                charset: encoding_rs::WINDOWS_1252,
+            };
+
+            let bam = BamImporter::from_stream(&mut reader).unwrap();
+
+            assert_eq!(
+                bam,
+                BamImporter::import(&DataSource::new(bytes.clone())).unwrap()
+            );
+            let r#type = match &bam {
+                Bam::V1(bam) => &bam.r#type,
+                Bam::V2(bam) => &bam.r#type,
+            };
+            assert_eq!(r#type, &expected, "{name}");
+        }
+
+        let mut reader = Reader {
+            data: b"MOS V1  ".as_slice(),
+            charset: encoding_rs::WINDOWS_1252,
+        };
+        assert!(BamImporter::from_stream(&mut reader).is_err());
+    }
+
+    #[test]
+    fn test_import_bam_v1() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/BAM_V1/01/1chan03B_decompressed.BAM"
+        )));
+
+        match BamImporter::import(&data).unwrap() {
+            Bam::V1(bam) => {
+                assert_eq!(bam.r#type, Type::BamV1);
+                assert_eq!(bam.frames.len(), 1);
+            }
+            bam => panic!("Expected a BAM V1, got {bam:?}"),
+        }
+    }
+
+    #[test]
+    fn test_import_bam_v2() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/BAM_V2/1CHELM03.BAM"
+        )));
+
+        match BamImporter::import(&data).unwrap() {
+            Bam::V2(bam) => {
+                assert_eq!(bam.r#type, Type::BamV2);
+                assert_eq!(bam.frames.len(), 1);
+                assert_eq!(bam.cycles.len(), 1);
+                assert_eq!(bam.data_blocks.len(), 8);
+            }
+            bam => panic!("Expected a BAM V2, got {bam:?}"),
+        }
+    }
+
+    #[test]
+    fn test_bam_bounds_of_frames() {
+        assert_eq!(BamBounds::of_frames([]), None);
+
+        let bounds = BamBounds::of_frames([(50, 60, 25, 30), (20, 80, 0, 70)]).unwrap();
+        assert_eq!(
+            bounds,
+            BamBounds {
+                x: -25,
+                y: -70,
+                width: 50,
+                height: 100
+            }
+        );
+        assert_eq!(bounds.frame_position(25, 30), (0, 40));
+        assert_eq!(bounds.frame_position(0, 70), (25, 0));
+    }
+
+    #[test]
+    fn test_negative_center_places_frame_after_anchor() {
+        // The first frame is anchored 5 pixels on its left and 3 pixels above it
+        let bam = BamV1 {
+            r#type: Type::BamV1,
+            frames: vec![
+                BamV1Frame {
+                    width: 10,
+                    height: 10,
+                    center_x: -5,
+                    center_y: -3,
+                    compressed: false,
+                    pixel_palette_indexes: vec![1; 100],
+                },
+                BamV1Frame {
+                    width: 4,
+                    height: 4,
+                    center_x: 2,
+                    center_y: 2,
+                    compressed: false,
+                    pixel_palette_indexes: vec![1; 16],
+                },
+            ],
+            palette: vec![
+                Rgb {
+                    r: 0,
+                    g: 255,
+                    b: 0,
+                    alpha: 255,
+                },
+                Rgb {
+                    r: 255,
+                    g: 0,
+                    b: 0,
+                    alpha: 255,
+                },
+            ],
+            cycles: vec![BamV1Cycle {
+                frame_indices: vec![0, 1],
+            }],
+            rle_compressed_color_index: 0,
+        };
+
+        // The negative centers survive an export
+        let mut exported = Vec::new();
+        bam.export(&mut exported).unwrap();
+        let Bam::V1(bam) = BamImporter::import(&DataSource::new(exported)).unwrap() else {
+            panic!("Expected a BAM V1");
+        };
+        assert_eq!((bam.frames[0].center_x, bam.frames[0].center_y), (-5, -3));
+
+        let bounds = bam.cycle_bounds(0).unwrap();
+        assert_eq!(
+            bounds,
+            BamBounds {
+                x: -2,
+                y: -2,
+                width: 17,
+                height: 15
+            }
+        );
+        // The anchor is at (2, 2) in the bounds: the first frame starts 5 pixels right and 3 below it
+        assert_eq!(bounds.frame_position(-5, -3), (7, 5));
+        assert_eq!(bounds.frame_position(2, 2), (0, 0));
+    }
+
+    #[test]
+    fn test_cycle_bounds_contain_all_frames() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/BAM_V1/02/SPHEART_compressed.BAM"
+        )));
+
+        let Bam::V1(bam) = BamImporter::import(&data).unwrap() else {
+            panic!("Expected a BAM V1");
+        };
+        for (index, cycle) in bam.cycles.iter().enumerate() {
+            let bounds = bam.cycle_bounds(index).unwrap();
+            for frame in cycle.frame_indices.iter().map(|i| &bam.frames[*i]) {
+                let (x, y) = bounds.frame_position(frame.center_x, frame.center_y);
+                assert!(x + frame.width <= bounds.width);
+                assert!(y + frame.height <= bounds.height);
+            }
+        }
+        assert_eq!(bam.cycle_bounds(bam.cycles.len()), None);
+    }
+
+    #[test]
+    fn test_import_bamc() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/BAM_V1/02/SPHEART_compressed.BAM"
+        )));
+
+        match BamImporter::import(&data).unwrap() {
+            Bam::V1(bam) => {
+                assert_eq!(bam.r#type, Type::BamV1);
+                assert_eq!(bam.frames.len(), 15);
+            }
+            bam => panic!("Expected a BAM V1, got {bam:?}"),
+        }
+    }
 }
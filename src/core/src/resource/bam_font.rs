@@ -0,0 +1,175 @@
+use encoding_rs::WINDOWS_1252;
+use image::{ImageBuffer, Rgba};
+
+use crate::{
+    datasource::{DataSource, Importer},
+    fs::{CaseInsensitivePath, FileSystem},
+    resource::bam::{Bam, BamImporter, BamV1, BamV1Frame},
+};
+
+/// A bitmap font read from a BAM file.
+/// The glyphs are stored in the BAM file with the same name of the font: the glyph of the character
+/// with WINDOWS-1252 code `n` is the first frame of the cycle `n - 1`.
+/// The width of a glyph is the width of its frame.
+/// The fonts described by a `ResourceType::Fnt` resource are read with `FntFont`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct BamFont {
+    pub bam: BamV1,
+}
+
+impl BamFont {
+    /// Creates a font from the BAM file containing its glyphs
+    pub fn new(bam: BamV1) -> Self {
+        BamFont { bam }
+    }
+
+    /// Opens a font, reading its BAM file from the file system.
+    /// The BAM file is looked up in the root directory first, then in the predefined folders.
+    pub fn open(name: &str, fs: &impl FileSystem) -> std::io::Result<BamFont> {
+        let path = CaseInsensitivePath::new(&format!("{name}.BAM"));
+        let source = fs
+            .open_opt(&path)
+            .or_else(|| fs.search_path_opt(&path).map(DataSource::new))
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("Font BAM file not found: {}", path.as_str()),
+                )
+            })?;
+
+        match BamImporter::import(&source)? {
+            Bam::V1(bam) => Ok(BamFont::new(bam)),
+            Bam::V2(_) => Err(std::io::Error::other(format!(
+                "Unsupported BAM V2 font: {}",
+                name
+            ))),
+        }
+    }
+
+    /// Returns the frame of the glyph of a character.
+    /// Returns `None` if the character is not mapped by WINDOWS-1252, or the font has no glyph for it.
+    pub fn glyph_frame(&self, ch: char) -> Option<&BamV1Frame> {
+        let cycle = glyph_cycle(ch)?;
+        let frame_index = *self.bam.cycles.get(cycle)?.frame_indices.first()?;
+        self.bam.frames.get(frame_index)
+    }
+
+    /// Returns the width of the glyph of a character
+    pub fn glyph_width(&self, ch: char) -> Option<u32> {
+        self.glyph_frame(ch).map(|frame| frame.width)
+    }
+
+    /// Renders the glyph of a character
+    pub fn glyph_image(&self, ch: char) -> Option<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+        self.glyph_frame(ch)?.to_image(&self.bam.palette).ok()
+    }
+}
+
+/// Returns the index of the BAM cycle of a character, that is its WINDOWS-1252 code minus 1
+pub(crate) fn glyph_cycle(ch: char) -> Option<usize> {
+    let mut buffer = [0u8; 4];
+    let (bytes, _, had_errors) = WINDOWS_1252.encode(ch.encode_utf8(&mut buffer));
+    match (had_errors, bytes.as_ref()) {
+        (false, [code]) if *code > 0 => Some(*code as usize - 1),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fs::MemoryFS,
+        resource::{
+            bam::{BamV1Cycle, Type},
+            common::Rgb,
+        },
+    };
+
+    /// Builds a font with the glyphs of 'A', a 2x3 frame, and 'é', a 3x2 frame
+    fn test_font_bam() -> BamV1 {
+        let mut cycles = (0..255)
+            .map(|_| BamV1Cycle {
+                frame_indices: vec![],
+            })
+            .collect::<Vec<_>>();
+        cycles[b'A' as usize - 1].frame_indices.push(0);
+        cycles[0xe9 - 1].frame_indices.push(1);
+
+        BamV1 {
+            r#type: Type::BamV1,
+            frames: vec![
+                BamV1Frame {
+                    width: 2,
+                    height: 3,
+                    center_x: 0,
+                    center_y: 0,
+                    compressed: false,
+                    pixel_palette_indexes: vec![1, 0, 0, 1, 1, 1],
+                },
+                BamV1Frame {
+                    width: 3,
+                    height: 2,
+                    center_x: 0,
+                    center_y: 0,
+                    compressed: false,
+                    pixel_palette_indexes: vec![0, 1, 0, 1, 0, 1],
+                },
+            ],
+            palette: vec![
+                Rgb {
+                    r: 0,
+                    g: 255,
+                    b: 0,
+                    alpha: 255,
+                },
+                Rgb {
+                    r: 255,
+                    g: 255,
+                    b: 255,
+                    alpha: 255,
+                },
+            ],
+            cycles,
+            rle_compressed_color_index: 0,
+        }
+    }
+
+    #[test]
+    fn test_glyph_cycle() {
+        assert_eq!(glyph_cycle('A'), Some(64));
+        assert_eq!(glyph_cycle('é'), Some(0xe8));
+        assert_eq!(glyph_cycle('€'), Some(0x7f));
+        assert_eq!(glyph_cycle('\0'), None);
+        assert_eq!(glyph_cycle('漢'), None);
+    }
+
+    #[test]
+    fn test_render_glyphs() {
+        let mut bam = Vec::new();
+        test_font_bam().export(&mut bam).unwrap();
+        let mut fs = MemoryFS::new();
+        fs.insert("normal.bam", bam);
+
+        let font = BamFont::open("NORMAL", &fs).unwrap();
+
+        let a = font.glyph_image('A').unwrap();
+        assert_eq!(font.glyph_width('A'), Some(2));
+        assert_eq!(a.dimensions(), (2, 3));
+        assert_eq!(a.get_pixel(0, 0), &Rgba([255, 255, 255, 255]));
+        assert_eq!(a.get_pixel(1, 0), &Rgba([0, 255, 0, 0]));
+        assert_eq!(a.get_pixel(1, 2), &Rgba([255, 255, 255, 255]));
+
+        let e = font.glyph_image('é').unwrap();
+        assert_eq!(font.glyph_width('é'), Some(3));
+        assert_eq!(e.dimensions(), (3, 2));
+        assert_eq!(e.get_pixel(1, 0), &Rgba([255, 255, 255, 255]));
+        assert_eq!(e.get_pixel(1, 1), &Rgba([0, 255, 0, 0]));
+
+        // Characters without glyphs
+        assert!(font.glyph_image('B').is_none());
+        assert!(font.glyph_width('漢').is_none());
+
+        assert!(BamFont::open("MISSING", &fs).is_err());
+    }
+}
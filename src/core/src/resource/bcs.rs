@@ -0,0 +1,717 @@
+use std::{iter::Peekable, str::Chars};
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    datasource::{DataSource, Importer},
+    resource::ids::IdsTables,
+};
+
+/// A BCS file importer
+pub struct BcsImporter;
+
+impl Importer for BcsImporter {
+    type T = Bcs;
+
+    fn import(source: &DataSource) -> std::io::Result<Bcs> {
+        let mut reader = source.reader()?;
+        let text = reader.read_string(u64::MAX)?;
+        let mut parser = Parser::new(tokenize(&text)?);
+
+        let mut blocks = Vec::new();
+        parser.expect("SC")?;
+        while parser.next_is("CR") {
+            blocks.push(parser.block()?);
+        }
+        parser.expect("SC")?;
+
+        Ok(Bcs { blocks })
+    }
+}
+
+/// A compiled script.
+/// Opcodes and parameters are kept raw, as they are stored in the file.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bcs {
+    pub blocks: Vec<ScriptBlock>,
+}
+
+/// A condition-response block.
+/// When all the triggers are true, one of the responses is picked randomly according to its weight.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScriptBlock {
+    pub triggers: Vec<Trigger>,
+    pub responses: Vec<Response>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Trigger {
+    /// The trigger id in TRIGGER.IDS
+    pub opcode: u32,
+    /// The integer parameters, the second one holds the flags
+    pub integers: Vec<i32>,
+    /// The point parameter, only present in the EE games
+    pub point: Option<Vec<i32>>,
+    pub strings: Vec<String>,
+    pub object: ScriptObject,
+}
+
+impl Trigger {
+    /// Returns whether the trigger result is negated
+    pub fn negated(&self) -> bool {
+        self.integers.get(1).is_some_and(|flags| flags & 1 != 0)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Response {
+    pub weight: u32,
+    pub actions: Vec<Action>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Action {
+    /// The action id in ACTION.IDS
+    pub opcode: u32,
+    /// The action subject followed by the two object parameters
+    pub objects: Vec<ScriptObject>,
+    /// The integer parameters, the second and third ones are the coordinates of the point parameter
+    pub integers: Vec<i32>,
+    pub strings: Vec<String>,
+}
+
+/// An object specifier, identifying a creature by name or by its IDS values
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScriptObject {
+    /// The EA, GENERAL, RACE, CLASS, SPECIFIC, GENDER and ALIGN values,
+    /// followed by the OBJECT.IDS identifiers
+    pub integers: Vec<i32>,
+    /// The area rectangle, only present in the EE games
+    pub area: Option<Vec<i32>>,
+    pub name: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum Token {
+    /// A two letters block delimiter, e.g. `SC` or `TR`
+    Keyword(String),
+    Integer(i32),
+    String(String),
+    /// A list of integers enclosed in square brackets
+    Point(Vec<i32>),
+}
+
+/// Splits the script source into tokens.
+/// Block delimiters can directly follow a number, e.g. `100AC`, so whitespaces are not required.
+fn tokenize(text: &str) -> std::io::Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '"' => {
+                chars.next();
+                let value = take_while(&mut chars, |c| c != '"');
+                if chars.next() != Some('"') {
+                    return Err(std::io::Error::other("Unterminated string in BCS file"));
+                }
+                tokens.push(Token::String(value));
+            }
+            '[' => {
+                chars.next();
+                let value = take_while(&mut chars, |c| c != ']');
+                if chars.next() != Some(']') {
+                    return Err(std::io::Error::other("Unterminated point in BCS file"));
+                }
+                let values = value
+                    .split(['.', ','])
+                    .map(parse_integer)
+                    .collect::<std::io::Result<_>>()?;
+                tokens.push(Token::Point(values));
+            }
+            c if c == '-' || c.is_ascii_digit() => {
+                chars.next();
+                let digits = take_while(&mut chars, |c| c.is_ascii_digit());
+                tokens.push(Token::Integer(parse_integer(&format!("{c}{digits}"))?));
+            }
+            c if c.is_ascii_uppercase() => {
+                let keyword = take_while(&mut chars, |c| c.is_ascii_uppercase());
+                tokens.push(Token::Keyword(keyword));
+            }
+            c => {
+                return Err(std::io::Error::other(format!(
+                    "Unexpected character in BCS file: {}",
+                    c
+                )));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn take_while(chars: &mut Peekable<Chars>, predicate: impl Fn(char) -> bool) -> String {
+    let mut value = String::new();
+    while let Some(&c) = chars.peek() {
+        if !predicate(c) {
+            break;
+        }
+        value.push(c);
+        chars.next();
+    }
+    value
+}
+
+fn parse_integer(value: &str) -> std::io::Result<i32> {
+    // Some values, like flags, are stored as unsigned numbers that overflow an i32
+    value
+        .trim()
+        .parse::<i32>()
+        .or_else(|_| value.trim().parse::<u32>().map(|value| value as i32))
+        .map_err(|_| std::io::Error::other(format!("Invalid number in BCS file: {}", value)))
+}
+
+/// A recursive descent parser over the BCS tokens
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn new(tokens: Vec<Token>) -> Self {
+        Self {
+            tokens,
+            position: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> std::io::Result<Token> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or_else(|| std::io::Error::other("Unexpected end of BCS file"))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn next_is(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Keyword(value)) if value == keyword)
+    }
+
+    fn expect(&mut self, keyword: &str) -> std::io::Result<()> {
+        match self.next()? {
+            Token::Keyword(value) if value == keyword => Ok(()),
+            token => Err(std::io::Error::other(format!(
+                "Expected {} in BCS file, found {:?}",
+                keyword, token
+            ))),
+        }
+    }
+
+    fn integer(&mut self) -> std::io::Result<i32> {
+        match self.next()? {
+            Token::Integer(value) => Ok(value),
+            token => Err(std::io::Error::other(format!(
+                "Expected a number in BCS file, found {:?}",
+                token
+            ))),
+        }
+    }
+
+    /// Reads the parameters up to the next keyword
+    fn parameters(&mut self) -> (Vec<i32>, Vec<String>, Option<Vec<i32>>) {
+        let mut integers = Vec::new();
+        let mut strings = Vec::new();
+        let mut point = None;
+        while let Some(token) = self.peek() {
+            match token {
+                Token::Integer(value) => integers.push(*value),
+                Token::String(value) => strings.push(value.clone()),
+                Token::Point(value) => point = Some(value.clone()),
+                Token::Keyword(_) => break,
+            }
+            self.position += 1;
+        }
+        (integers, strings, point)
+    }
+
+    fn block(&mut self) -> std::io::Result<ScriptBlock> {
+        self.expect("CR")?;
+
+        let mut triggers = Vec::new();
+        self.expect("CO")?;
+        while self.next_is("TR") {
+            triggers.push(self.trigger()?);
+        }
+        self.expect("CO")?;
+
+        let mut responses = Vec::new();
+        self.expect("RS")?;
+        while self.next_is("RE") {
+            responses.push(self.response()?);
+        }
+        self.expect("RS")?;
+
+        self.expect("CR")?;
+        Ok(ScriptBlock {
+            triggers,
+            responses,
+        })
+    }
+
+    fn trigger(&mut self) -> std::io::Result<Trigger> {
+        self.expect("TR")?;
+        let opcode = self.integer()? as u32;
+        let (integers, strings, point) = self.parameters();
+        let object = self.object()?;
+        self.expect("TR")?;
+        Ok(Trigger {
+            opcode,
+            integers,
+            point,
+            strings,
+            object,
+        })
+    }
+
+    fn response(&mut self) -> std::io::Result<Response> {
+        self.expect("RE")?;
+        let weight = self.integer()? as u32;
+        let mut actions = Vec::new();
+        while self.next_is("AC") {
+            actions.push(self.action()?);
+        }
+        self.expect("RE")?;
+        Ok(Response { weight, actions })
+    }
+
+    fn action(&mut self) -> std::io::Result<Action> {
+        self.expect("AC")?;
+        let opcode = self.integer()? as u32;
+        let mut objects = Vec::with_capacity(3);
+        while self.next_is("OB") {
+            objects.push(self.object()?);
+        }
+        let (integers, strings, _) = self.parameters();
+        self.expect("AC")?;
+        Ok(Action {
+            opcode,
+            objects,
+            integers,
+            strings,
+        })
+    }
+
+    fn object(&mut self) -> std::io::Result<ScriptObject> {
+        self.expect("OB")?;
+        let (integers, strings, area) = self.parameters();
+        self.expect("OB")?;
+        Ok(ScriptObject {
+            integers,
+            area,
+            name: strings.into_iter().next().unwrap_or_default(),
+        })
+    }
+}
+
+/// The IDS files of the object qualifiers, in the order they are stored in a script object
+const OBJECT_QUALIFIERS: [&str; 7] = [
+    "EA", "GENERAL", "RACE", "CLASS", "SPECIFIC", "GENDER", "ALIGN",
+];
+
+/// Decompiles a script to its source form.
+/// Trigger and action names are taken from the TRIGGER and ACTION IDS files, integer parameters
+/// are replaced by the symbols of the IDS file declared in the signature.
+/// Missing signatures and symbols are written as raw numbers.
+pub fn decompile(bcs: &Bcs, ids: &IdsTables) -> String {
+    let mut source = String::new();
+    for block in &bcs.blocks {
+        source.push_str("IF\n");
+        for trigger in &block.triggers {
+            source.push_str(&format!("  {}\n", decompile_trigger(trigger, ids)));
+        }
+        source.push_str("THEN\n");
+        for response in &block.responses {
+            source.push_str(&format!("  RESPONSE #{}\n", response.weight));
+            for action in &response.actions {
+                source.push_str(&format!("    {}\n", decompile_action(action, ids)));
+            }
+        }
+        source.push_str("END\n\n");
+    }
+    source
+}
+
+fn decompile_trigger(trigger: &Trigger, ids: &IdsTables) -> String {
+    let negation = if trigger.negated() { "!" } else { "" };
+    let signature = ids
+        .get("TRIGGER")
+        .and_then(|table| table.symbol(trigger.opcode as i64));
+
+    // The second integer holds the flags and is not a parameter
+    let integers = trigger
+        .integers
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| *index != 1)
+        .map(|(_, value)| *value)
+        .collect::<Vec<_>>();
+    let point = trigger.point.clone().unwrap_or_default();
+
+    let call = match signature {
+        Some(signature) => decompile_call(
+            &Signature::parse(signature),
+            &integers,
+            &trigger.strings,
+            &[&trigger.object],
+            &point,
+            ids,
+        ),
+        None => raw_call(
+            &format!("0x{:04X}", trigger.opcode),
+            &integers,
+            &trigger.strings,
+            &[&trigger.object],
+            ids,
+        ),
+    };
+    format!("{negation}{call}")
+}
+
+fn decompile_action(action: &Action, ids: &IdsTables) -> String {
+    let signature = ids
+        .get("ACTION")
+        .and_then(|table| table.symbol(action.opcode as i64));
+
+    // The second and third integers are the coordinates of the point parameter
+    let integers = action
+        .integers
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| *index != 1 && *index != 2)
+        .map(|(_, value)| *value)
+        .collect::<Vec<_>>();
+    let point = action.integers.get(1..3).unwrap_or_default();
+    let objects = action.objects.iter().skip(1).collect::<Vec<_>>();
+
+    let call = match signature {
+        Some(signature) => decompile_call(
+            &Signature::parse(signature),
+            &integers,
+            &action.strings,
+            &objects,
+            point,
+            ids,
+        ),
+        None => raw_call(
+            &action.opcode.to_string(),
+            &integers,
+            &action.strings,
+            &objects,
+            ids,
+        ),
+    };
+
+    // An action with a subject is executed by another creature
+    match action.objects.first() {
+        Some(subject) if !subject.is_empty() => {
+            format!(
+                "ActionOverride({},{})",
+                decompile_object(subject, ids),
+                call
+            )
+        }
+        _ => call,
+    }
+}
+
+/// Writes a call following its IDS signature
+fn decompile_call(
+    signature: &Signature,
+    integers: &[i32],
+    strings: &[String],
+    objects: &[&ScriptObject],
+    point: &[i32],
+    ids: &IdsTables,
+) -> String {
+    let strings = split_scoped_strings(signature, strings);
+    let mut integers = integers.iter();
+    let mut strings = strings.iter();
+    let mut objects = objects.iter();
+
+    let arguments = signature
+        .parameters
+        .iter()
+        .map(|parameter| match parameter.kind {
+            'I' => {
+                let value = integers.next().copied().unwrap_or_default();
+                parameter
+                    .ids
+                    .and_then(|name| ids.get(name))
+                    .and_then(|table| table.symbol(value as i64))
+                    .map(str::to_owned)
+                    .unwrap_or_else(|| value.to_string())
+            }
+            'S' => format!("\"{}\"", strings.next().map(String::as_str).unwrap_or("")),
+            'O' => objects
+                .next()
+                .map(|object| decompile_object(object, ids))
+                .unwrap_or_default(),
+            'P' => format!("[{}]", point.iter().join(".")),
+            _ => String::new(),
+        })
+        .join(",");
+
+    format!("{}({})", signature.name, arguments)
+}
+
+/// Writes a call without signature with all its raw parameters
+fn raw_call(
+    name: &str,
+    integers: &[i32],
+    strings: &[String],
+    objects: &[&ScriptObject],
+    ids: &IdsTables,
+) -> String {
+    let arguments = integers
+        .iter()
+        .map(i32::to_string)
+        .chain(strings.iter().map(|value| format!("\"{value}\"")))
+        .chain(objects.iter().map(|object| decompile_object(object, ids)))
+        .join(",");
+    format!("{name}({arguments})")
+}
+
+/// Variables names and their scope are stored together in the first string, e.g. `GLOBALMyVariable`,
+/// when the signature expects them as two different parameters.
+fn split_scoped_strings(signature: &Signature, strings: &[String]) -> Vec<String> {
+    let scoped = signature
+        .parameters
+        .iter()
+        .filter(|parameter| parameter.kind == 'S')
+        .nth(1)
+        .is_some_and(|parameter| {
+            let label = parameter.label.to_lowercase();
+            label.contains("area") || label.contains("scope")
+        });
+
+    match strings {
+        [first, second, ..] if scoped && second.is_empty() && first.len() > 6 => {
+            let (scope, name) = first.split_at(6);
+            vec![name.to_owned(), scope.to_owned()]
+        }
+        _ => strings.to_vec(),
+    }
+}
+
+fn decompile_object(object: &ScriptObject, ids: &IdsTables) -> String {
+    if !object.name.is_empty() {
+        return format!("\"{}\"", object.name);
+    }
+
+    let qualifiers_count = OBJECT_QUALIFIERS.len().min(object.integers.len());
+    let (qualifiers, identifiers) = object.integers.split_at(qualifiers_count);
+
+    let symbol = |table: &str, value: i32| {
+        ids.get(table)
+            .and_then(|table| table.symbol(value as i64))
+            .map(str::to_owned)
+            .unwrap_or_else(|| value.to_string())
+    };
+
+    let mut specifier = qualifiers
+        .iter()
+        .rposition(|value| *value != 0)
+        .map(|last| {
+            let mut values = qualifiers[..=last]
+                .iter()
+                .zip(OBJECT_QUALIFIERS)
+                .map(|(value, table)| symbol(table, *value));
+            format!("[{}]", values.join("."))
+        });
+
+    // Identifiers are function-like, e.g. LastAttackerOf(Myself), the first one is the outermost
+    for identifier in identifiers.iter().rev().filter(|value| **value != 0) {
+        let name = symbol("OBJECT", *identifier);
+        specifier = Some(match specifier {
+            Some(inner) => format!("{name}({inner})"),
+            None => name,
+        });
+    }
+
+    specifier.unwrap_or_else(|| format!("[{}]", symbol("EA", 0)))
+}
+
+impl ScriptObject {
+    /// Returns whether the object does not identify anything
+    pub fn is_empty(&self) -> bool {
+        self.name.is_empty() && self.integers.iter().all(|value| *value == 0)
+    }
+}
+
+/// A trigger or action signature, e.g. `Global(S:Name*,S:Area*,I:Value*)`
+struct Signature<'a> {
+    name: &'a str,
+    parameters: Vec<Parameter<'a>>,
+}
+
+/// A signature parameter, e.g. `I:State*Boolean`
+struct Parameter<'a> {
+    /// The parameter type: I(nteger), S(tring), O(bject), P(oint) or A(ction)
+    kind: char,
+    label: &'a str,
+    /// The IDS file holding the symbols of an integer parameter
+    ids: Option<&'a str>,
+}
+
+impl<'a> Signature<'a> {
+    fn parse(signature: &'a str) -> Self {
+        let (name, parameters) = signature.split_once('(').unwrap_or((signature, ""));
+        let parameters = parameters
+            .trim_end_matches(')')
+            .split(',')
+            .filter(|parameter| !parameter.trim().is_empty())
+            .map(|parameter| {
+                let (kind, rest) = parameter.split_once(':').unwrap_or((parameter, ""));
+                let (label, ids) = rest.split_once('*').unwrap_or((rest, ""));
+                Parameter {
+                    kind: kind.trim().chars().next().unwrap_or_default(),
+                    label,
+                    ids: (!ids.is_empty()).then_some(ids),
+                }
+            })
+            .collect();
+        Signature {
+            name: name.trim(),
+            parameters,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        resource::ids::IdsImporter,
+        test_utils::{BG2_RESOURCES_DIR, RESOURCES_DIR},
+    };
+    use insta::assert_snapshot;
+    use std::path::Path;
+
+    #[test]
+    fn test_parse_bcs() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/BCS/MINSC.BCS"
+        )));
+
+        let bcs = BcsImporter::import(&data).unwrap();
+
+        assert_eq!(bcs.blocks.len(), 2);
+
+        let block = &bcs.blocks[0];
+        assert_eq!(block.triggers.len(), 2);
+        assert_eq!(block.responses.len(), 2);
+        assert_eq!(block.responses[0].weight, 100);
+        assert_eq!(block.responses[0].actions.len(), 2);
+        assert_eq!(block.responses[1].actions.len(), 1);
+
+        let trigger = &block.triggers[1];
+        assert_eq!(trigger.opcode, 0x400F);
+        assert_eq!(trigger.integers, vec![1, 0, 0, 0]);
+        assert_eq!(trigger.strings, vec!["MinscTalk", "GLOBAL"]);
+        assert!(!trigger.negated());
+
+        let action = &block.responses[0].actions[0];
+        assert_eq!(action.opcode, 30);
+        assert_eq!(action.objects.len(), 3);
+        assert_eq!(action.integers, vec![1, 0, 0, 0, 0]);
+        assert_eq!(action.strings, vec!["GLOBALMinscTalk", ""]);
+
+        let block = &bcs.blocks[1];
+        assert_eq!(block.triggers.len(), 1);
+        assert!(block.triggers[0].negated());
+        assert_eq!(block.responses.len(), 1);
+        assert_eq!(block.responses[0].actions[0].objects[0].name, "Boo");
+    }
+
+    #[test]
+    fn test_parse_bcs_bg2() {
+        let data = DataSource::new(Path::new(&format!(
+            "{BG2_RESOURCES_DIR}/override/aerie.bcs"
+        )));
+
+        let bcs = BcsImporter::import(&data).unwrap();
+
+        assert_eq!(bcs.blocks.len(), 36);
+
+        let block = &bcs.blocks[0];
+        assert_eq!(block.triggers.len(), 2);
+        assert_eq!(block.triggers[0].opcode, 16399);
+        assert_eq!(
+            block.triggers[0].strings,
+            vec!["LOCALSAerieHasProperPortrait", ""]
+        );
+        assert_eq!(block.responses.len(), 1);
+        assert_eq!(block.responses[0].actions.len(), 3);
+        assert_eq!(block.responses[0].actions[0].integers[0], 3745);
+    }
+
+    #[test]
+    fn test_decompile() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/BCS/MINSC.BCS"
+        )));
+        let bcs = BcsImporter::import(&data).unwrap();
+
+        let mut ids = IdsTables::new();
+        for name in ["TRIGGER", "ACTION", "EA", "BOOLEAN"] {
+            let data = DataSource::new(Path::new(&format!(
+                "{RESOURCES_DIR}/resources/IDS/{name}.IDS"
+            )));
+            ids.insert(name, IdsImporter::import(&data).unwrap());
+        }
+
+        assert_snapshot!(decompile(&bcs, &ids));
+    }
+
+    #[test]
+    fn test_decompile_without_ids() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/BCS/MINSC.BCS"
+        )));
+        let bcs = BcsImporter::import(&data).unwrap();
+
+        let source = decompile(&bcs, &IdsTables::new());
+
+        // Without signatures the calls are written with their opcode and all their raw parameters
+        assert!(source.starts_with(
+            "IF\n  0x4074(0,0,0,\"\",\"\",[2])\n  0x400F(1,0,0,\"MinscTalk\",\"GLOBAL\",[0])\nTHEN\n"
+        ));
+        assert!(source.contains("ActionOverride(\"Boo\",3(0,0,0,\"\",\"\",[0],[0]))"));
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("RE\n100AC\n-1 \"a b\" [1.2.-3.4]OB").unwrap(),
+            vec![
+                Token::Keyword("RE".to_owned()),
+                Token::Integer(100),
+                Token::Keyword("AC".to_owned()),
+                Token::Integer(-1),
+                Token::String("a b".to_owned()),
+                Token::Point(vec![1, 2, -3, 4]),
+                Token::Keyword("OB".to_owned()),
+            ]
+        );
+    }
+}
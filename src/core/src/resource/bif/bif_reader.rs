@@ -3,8 +3,8 @@ use std::io::BufRead;
 use crate::{
     datasource::Reader,
     resource::bif::{
-        BIF_V1_0_SIGNATURE, BIFFV1_SIGNATURE, Bif, Type, parse_bif_embedded_file,
-        parse_bif_embedded_tileset,
+        BIF_V1_0_SIGNATURE, BIFFV1_SIGNATURE, Bif, MAX_DECOMPRESSION_PREALLOCATION, Type,
+        parse_bif_embedded_file, parse_bif_embedded_tileset,
     },
 };
 
@@ -14,6 +14,16 @@ pub struct BifParser;
 impl BifParser {
     /// Imports a BIFC V1 file
     pub fn import<R: BufRead>(reader: &mut Reader<R>) -> std::io::Result<Bif> {
+        let mut bif = Bif {
+            r#type: Type::Bif,
+            resources: vec![],
+        };
+        Self::read(reader, &mut bif)?;
+        Ok(bif)
+    }
+
+    /// Reads the entries of a BIF V1.0 file into `bif`, which keeps the entries read before an error
+    pub fn read<R: BufRead>(reader: &mut Reader<R>, bif: &mut Bif) -> std::io::Result<()> {
         let signature = reader.read_string(8)?;
 
         if !signature.eq(BIF_V1_0_SIGNATURE) {
@@ -56,10 +66,7 @@ impl BifParser {
 
         zip.skip(remaining_bytes)?;
 
-        let mut bif = Bif {
-            r#type: Type::Bif,
-            resources: Vec::with_capacity(files_number + tilesets_number),
-        };
+        bif.resources.reserve(files_number + tilesets_number);
 
         // reading file entries
         for _ in 0..files_number {
@@ -71,7 +78,70 @@ impl BifParser {
             bif.resources.push(parse_bif_embedded_tileset(&mut zip)?);
         }
 
-        Ok(bif)
+        Ok(())
+    }
+
+    /// Decompresses a BIFC V1 file and returns its BIFF V1 content
+    pub fn decompress<R: BufRead>(reader: &mut Reader<R>) -> std::io::Result<Vec<u8>> {
+        let signature = reader.read_string(8)?;
+
+        if !signature.eq(BIF_V1_0_SIGNATURE) {
+            return Err(std::io::Error::other(format!(
+                "Wrong file type: {}",
+                signature
+            )));
+        }
+
+        let name_length = reader.read_u32()? as u64;
+        reader.skip(name_length)?;
+
+        let uncompressed_data_length = reader.read_u32()? as u64;
+        let _compressed_data_length = reader.read_u32()? as u64;
+
+        let mut data = Vec::with_capacity(
+            (uncompressed_data_length as usize).min(MAX_DECOMPRESSION_PREALLOCATION),
+        );
+        reader
+            .as_zip_reader()
+            .read_to_end(&mut data, uncompressed_data_length)?;
+        Ok(data)
+    }
+
+    /// Extracts `size` bytes starting at `offset` of the BIFF V1 content of a BIFC V1 file.
+    /// The decompression stops after the end of the range.
+    pub fn extract<R: BufRead>(
+        reader: &mut Reader<R>,
+        offset: u64,
+        size: u64,
+    ) -> std::io::Result<Vec<u8>> {
+        let end = offset.checked_add(size).ok_or_else(|| {
+            std::io::Error::other(format!("Range of {} bytes at {} overflows", size, offset))
+        })?;
+        let signature = reader.read_string(8)?;
+
+        if !signature.eq(BIF_V1_0_SIGNATURE) {
+            return Err(std::io::Error::other(format!(
+                "Wrong file type: {}",
+                signature
+            )));
+        }
+
+        let name_length = reader.read_u32()? as u64;
+        reader.skip(name_length)?;
+
+        let _uncompressed_data_length = reader.read_u32()? as u64;
+        let _compressed_data_length = reader.read_u32()? as u64;
+
+        let mut zip = reader.as_zip_reader();
+        zip.skip(offset)?;
+        let data = zip.take_to_vec(size)?;
+        if (data.len() as u64) < size {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                format!("Range {}..{} out of the BIF content", offset, end),
+            ));
+        }
+        Ok(data)
     }
 }
 
@@ -113,7 +183,8 @@ mod tests {
                 locator: 0,
                 size: 3850,
                 offset: 120,
-                r#type: ResourceType::Wed
+                r#type: ResourceType::Wed,
+                unknown: 0,
             }
         );
         assert_eq!(
@@ -122,7 +193,8 @@ mod tests {
                 locator: 2,
                 size: 7480,
                 offset: 7288,
-                r#type: ResourceType::Bmp
+                r#type: ResourceType::Bmp,
+                unknown: 0,
             }
         );
         assert_eq!(
@@ -132,7 +204,8 @@ mod tests {
                 size: 5120,
                 offset: 43480,
                 count: 300,
-                r#type: ResourceType::Tis
+                r#type: ResourceType::Tis,
+                unknown: 0,
             }
         );
     }
@@ -1,8 +1,8 @@
 use crate::{
     datasource::Reader,
     resource::bif::{
-        BIFCV1_0_SIGNATURE, BIFFV1_SIGNATURE, Bif, Type, parse_bif_embedded_file,
-        parse_bif_embedded_tileset,
+        BIFCV1_0_SIGNATURE, BIFFV1_SIGNATURE, Bif, MAX_DECOMPRESSION_PREALLOCATION, Type,
+        parse_bif_embedded_file, parse_bif_embedded_tileset,
     },
 };
 use std::{
@@ -15,9 +15,17 @@ pub struct BifcParser;
 
 impl BifcParser {
     /// Imports a BIFC V1.0 file
-    pub fn import<'a: 'b, 'b, R: BufRead + Seek>(
-        reader: &'b mut Reader<R>,
-    ) -> std::io::Result<Bif> {
+    pub fn import<R: BufRead + Seek>(reader: &mut Reader<R>) -> std::io::Result<Bif> {
+        let mut bif = Bif {
+            r#type: Type::Bifc,
+            resources: vec![],
+        };
+        Self::read(reader, &mut bif)?;
+        Ok(bif)
+    }
+
+    /// Reads the entries of a BIFC V1.0 file into `bif`, which keeps the entries read before an error
+    pub fn read<R: BufRead + Seek>(reader: &mut Reader<R>, bif: &mut Bif) -> std::io::Result<()> {
         let signature = reader.read_string(8)?;
 
         if !signature.eq(BIFCV1_0_SIGNATURE) {
@@ -29,7 +37,7 @@ impl BifcParser {
 
         let _uncompressed_size = reader.read_u32()?;
 
-        let bif = {
+        {
             let mut zip = Reader {
                 charset: reader.charset,
                 data: BifcCompressedReader::new(reader),
@@ -59,10 +67,7 @@ impl BifcParser {
 
             zip.skip(remaining_bytes)?;
 
-            let mut bif = Bif {
-                r#type: Type::Bifc,
-                resources: Vec::with_capacity(files_number + tilesets_number),
-            };
+            bif.resources.reserve(files_number + tilesets_number);
 
             // reading file entries
             for _ in 0..files_number {
@@ -73,12 +78,106 @@ impl BifcParser {
             for _ in 0..tilesets_number {
                 bif.resources.push(parse_bif_embedded_tileset(&mut zip)?);
             }
+        }
 
-            bif
+        Ok(())
+    }
+
+    /// Decompresses a BIFC V1.0 file and returns its BIFF V1 content
+    pub fn decompress<R: BufRead>(reader: &mut Reader<R>) -> std::io::Result<Vec<u8>> {
+        let signature = reader.read_string(8)?;
+
+        if !signature.eq(BIFCV1_0_SIGNATURE) {
+            return Err(std::io::Error::other(format!(
+                "Wrong file type: {}",
+                signature
+            )));
         };
 
-        Ok(bif)
+        let uncompressed_size = reader.read_u32()? as usize;
+
+        let mut data = Vec::with_capacity(uncompressed_size.min(MAX_DECOMPRESSION_PREALLOCATION));
+        while data.len() < uncompressed_size {
+            let block_size = reader.read_u32()? as u64;
+            let compressed_size = reader.read_u32()? as u64;
+
+            let mut block = reader.take(compressed_size);
+            block.as_zip_reader().read_to_end(&mut data, block_size)?;
+            // Skips any trailing byte of the block not consumed by the zlib stream
+            block.skip(compressed_size)?;
+        }
+        Ok(data)
+    }
+
+    /// Extracts `size` bytes starting at `offset` of the BIFF V1 content of a BIFC V1.0 file.
+    /// The chunks preceding `offset` are skipped without decompressing them,
+    /// and the reading stops after the chunk containing `offset + size`.
+    pub fn extract<R: BufRead + Seek>(
+        reader: &mut Reader<R>,
+        offset: u64,
+        size: u64,
+    ) -> std::io::Result<Vec<u8>> {
+        extract_chunks(reader, offset, size).map(|(data, _)| data)
+    }
+}
+
+/// Extracts a range of the BIFF V1 content of a BIFC V1.0 file,
+/// returns the extracted bytes and the number of decompressed chunks
+fn extract_chunks<R: BufRead + Seek>(
+    reader: &mut Reader<R>,
+    offset: u64,
+    size: u64,
+) -> std::io::Result<(Vec<u8>, usize)> {
+    let signature = reader.read_string(8)?;
+
+    if !signature.eq(BIFCV1_0_SIGNATURE) {
+        return Err(std::io::Error::other(format!(
+            "Wrong file type: {}",
+            signature
+        )));
+    };
+
+    let uncompressed_size = reader.read_u32()? as u64;
+    let end = offset.checked_add(size).ok_or_else(|| {
+        std::io::Error::other(format!("Range of {} bytes at {} overflows", size, offset))
+    })?;
+    if end > uncompressed_size {
+        return Err(std::io::Error::other(format!(
+            "Range {}..{} out of the BIFC content of {} bytes",
+            offset, end, uncompressed_size
+        )));
     }
+
+    let mut data = Vec::with_capacity((size as usize).min(MAX_DECOMPRESSION_PREALLOCATION));
+    let mut decompressed_chunks = 0;
+    let mut chunk_start = 0;
+    while chunk_start < end {
+        let chunk_size = reader.read_u32()? as u64;
+        let compressed_size = reader.read_u32()? as u64;
+        if chunk_size == 0 {
+            return Err(std::io::Error::other("Empty BIFC chunk"));
+        }
+        let chunk_end = chunk_start + chunk_size;
+
+        if chunk_end <= offset {
+            let position = reader.position()?;
+            reader.set_position(position + compressed_size)?;
+        } else {
+            let mut chunk =
+                Vec::with_capacity((chunk_size as usize).min(MAX_DECOMPRESSION_PREALLOCATION));
+            let mut block = reader.take(compressed_size);
+            block.as_zip_reader().read_to_end(&mut chunk, chunk_size)?;
+            block.skip(compressed_size)?;
+            decompressed_chunks += 1;
+
+            let start = (offset.saturating_sub(chunk_start) as usize).min(chunk.len());
+            let stop = ((end.min(chunk_end) - chunk_start) as usize).min(chunk.len());
+            data.extend_from_slice(&chunk[start..stop]);
+        }
+        chunk_start = chunk_end;
+    }
+
+    Ok((data, decompressed_chunks))
 }
 
 struct BifcCompressedReader<'a, R: BufRead> {
@@ -151,6 +250,44 @@ mod tests {
     };
     use std::path::Path;
 
+    #[test]
+    fn test_extract_stops_at_the_chunk_of_the_resource() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}bg2/data/Data/AREA070C.bif"
+        )));
+        let content = BifcParser::decompress(&mut data.reader().unwrap()).unwrap();
+        let bif = BifcParser::import(&mut data.reader().unwrap()).unwrap();
+
+        let range = |resource: &BifEmbeddedResource| match resource {
+            BifEmbeddedResource::File { offset, size, .. } => (*offset, *size as u64),
+            BifEmbeddedResource::Tileset {
+                offset,
+                size,
+                count,
+                ..
+            } => (*offset, *size as u64 * *count as u64),
+        };
+
+        let (first_offset, first_size) = range(&bif.resources[0]);
+        let (first, first_chunks) =
+            extract_chunks(&mut data.reader().unwrap(), first_offset, first_size).unwrap();
+        assert_eq!(
+            first,
+            content[first_offset as usize..(first_offset + first_size) as usize]
+        );
+
+        let (last_offset, last_size) = range(&bif.resources[5]);
+        let (last, last_chunks) =
+            extract_chunks(&mut data.reader().unwrap(), last_offset, last_size).unwrap();
+        assert_eq!(
+            last,
+            content[last_offset as usize..(last_offset + last_size) as usize]
+        );
+
+        assert!(first_chunks < last_chunks);
+        assert!(BifcParser::extract(&mut data.reader().unwrap(), content.len() as u64, 1).is_err());
+    }
+
     #[test]
     fn test_detect_bifc_type() {
         let data = DataSource::new(Path::new(&format!(
@@ -173,7 +310,8 @@ mod tests {
                 locator: 1,
                 size: 3574,
                 offset: 4204,
-                r#type: ResourceType::Bmp
+                r#type: ResourceType::Bmp,
+                unknown: 0,
             }
         );
         assert_eq!(
@@ -182,7 +320,8 @@ mod tests {
                 locator: 4,
                 size: 98002,
                 offset: 19342,
-                r#type: ResourceType::Wav
+                r#type: ResourceType::Wav,
+                unknown: 0,
             }
         );
 
@@ -193,7 +332,8 @@ mod tests {
                 size: 5120,
                 count: 324,
                 offset: 117344,
-                r#type: ResourceType::Tis
+                r#type: ResourceType::Tis,
+                unknown: 0,
             }
         );
     }
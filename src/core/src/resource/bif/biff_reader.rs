@@ -13,6 +13,16 @@ pub struct BiffParser;
 impl BiffParser {
     /// Imports a BIFF V1 file
     pub fn import<R: Read + Seek>(reader: &mut Reader<R>) -> std::io::Result<Bif> {
+        let mut bif = Bif {
+            r#type: Type::Biff,
+            resources: vec![],
+        };
+        Self::read(reader, &mut bif)?;
+        Ok(bif)
+    }
+
+    /// Reads the entries of a BIFF V1 file into `bif`, which keeps the entries read before an error
+    pub fn read<R: Read + Seek>(reader: &mut Reader<R>, bif: &mut Bif) -> std::io::Result<()> {
         let signature = reader.read_string(8)?;
 
         if !signature.eq(BIFFV1_SIGNATURE) {
@@ -28,10 +38,7 @@ impl BiffParser {
 
         reader.set_position(files_offset)?;
 
-        let mut bif = Bif {
-            r#type: Type::Biff,
-            resources: Vec::with_capacity(files_number + tilesets_number),
-        };
+        bif.resources.reserve(files_number + tilesets_number);
 
         // reading file entries
         for _ in 0..files_number {
@@ -43,7 +50,7 @@ impl BiffParser {
             bif.resources.push(parse_bif_embedded_tileset(reader)?)
         }
 
-        Ok(bif)
+        Ok(())
     }
 }
 
@@ -82,7 +89,8 @@ mod tests {
                 locator: 1,
                 size: 4050,
                 offset: 7952,
-                r#type: ResourceType::Bcs
+                r#type: ResourceType::Bcs,
+                unknown: 0,
             }
         );
         assert_eq!(
@@ -91,7 +99,8 @@ mod tests {
                 locator: 3,
                 size: 285,
                 offset: 17222,
-                r#type: ResourceType::Bcs
+                r#type: ResourceType::Bcs,
+                unknown: 0,
             }
         );
     }
@@ -120,7 +129,8 @@ mod tests {
                 locator: 0,
                 size: 315816,
                 offset: 24,
-                r#type: ResourceType::Mos
+                r#type: ResourceType::Mos,
+                unknown: 0,
             }
         );
         assert_eq!(
@@ -130,8 +140,60 @@ mod tests {
                 size: 12,
                 offset: 461932,
                 count: 2507,
-                r#type: ResourceType::Tis
+                r#type: ResourceType::Tis,
+                unknown: 0,
             }
         );
     }
+
+    #[test]
+    fn test_embedded_resource_accessors() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}bg2_ee/data/area500c.bif"
+        )));
+
+        let bif = BiffParser::import(&mut data.reader().unwrap()).unwrap();
+
+        for resource in &bif.resources {
+            match resource {
+                BifEmbeddedResource::File {
+                    locator,
+                    size,
+                    offset,
+                    r#type,
+                    unknown,
+                } => {
+                    assert_eq!(resource.locator(), *locator);
+                    assert_eq!(resource.size(), *size);
+                    assert_eq!(resource.offset(), *offset);
+                    assert_eq!(resource.resource_type(), *r#type);
+                    assert_eq!(resource.unknown(), *unknown);
+                    assert!(!resource.is_tileset());
+                    assert_eq!(resource.data_size(), *size as u64);
+                }
+                BifEmbeddedResource::Tileset {
+                    locator,
+                    size,
+                    count,
+                    offset,
+                    r#type,
+                    unknown,
+                } => {
+                    assert_eq!(resource.locator(), *locator);
+                    assert_eq!(resource.size(), *size);
+                    assert_eq!(resource.offset(), *offset);
+                    assert_eq!(resource.resource_type(), *r#type);
+                    assert_eq!(resource.unknown(), *unknown);
+                    assert!(resource.is_tileset());
+                    assert_eq!(resource.data_size(), *size as u64 * *count as u64);
+                }
+            }
+        }
+
+        let tileset = &bif.resources[5];
+        assert_eq!(tileset.locator(), 16384);
+        assert_eq!(tileset.size(), 12);
+        assert_eq!(tileset.data_size(), 12 * 2507);
+        assert_eq!(tileset.resource_type(), ResourceType::Tis);
+    }
 }
@@ -5,7 +5,7 @@ mod biff_reader;
 use std::io::Read;
 
 use crate::{
-    datasource::{Importer, Reader},
+    datasource::{DataSource, ImportOptions, Importer, PartialResult, Reader},
     resource::{
         bif::{bif_reader::BifParser, bifc_reader::BifcParser, biff_reader::BiffParser},
         key::ResourceType,
@@ -18,7 +18,7 @@ pub struct BifImporter {}
 impl Importer for BifImporter {
     type T = Bif;
 
-    fn import(source: &crate::datasource::DataSource) -> std::io::Result<Self::T> {
+    fn import(source: &DataSource) -> std::io::Result<Self::T> {
         let reader = &mut source.reader()?;
         let position = reader.position()?;
 
@@ -39,10 +39,77 @@ impl Importer for BifImporter {
     }
 }
 
+impl BifImporter {
+    /// Imports a BIF file with the given options.
+    /// With a lenient truncation, the entries read before the end of a truncated file are returned.
+    pub fn import_with_options(
+        source: &DataSource,
+        options: &ImportOptions,
+    ) -> std::io::Result<PartialResult<Bif>> {
+        let reader = &mut source.reader()?;
+        let position = reader.position()?;
+
+        let r#type = detect_biff_type(reader)?;
+        reader.set_position(position)?;
+        let mut bif = Bif {
+            r#type,
+            resources: vec![],
+        };
+        let result = match bif.r#type {
+            Type::Biff => BiffParser::read(reader, &mut bif),
+            Type::Bif => BifParser::read(reader, &mut bif),
+            Type::Bifc => BifcParser::read(reader, &mut bif),
+        };
+        options.complete(bif, result)
+    }
+
+    /// Returns a data source over the uncompressed BIFF V1 content of a BIF file.
+    /// The offsets of the `BifEmbeddedResource`s are relative to this content.
+    /// The compressed files are fully decompressed in memory.
+    pub fn decompress(source: &DataSource) -> std::io::Result<DataSource> {
+        let reader = &mut source.reader()?;
+        let position = reader.position()?;
+
+        let data = match detect_biff_type(reader)? {
+            Type::Biff => return Ok(source.clone()),
+            Type::Bif => {
+                reader.set_position(position)?;
+                BifParser::decompress(reader)?
+            }
+            Type::Bifc => {
+                reader.set_position(position)?;
+                BifcParser::decompress(reader)?
+            }
+        };
+        Ok(DataSource::new(data).with_encoding(source.encoding()))
+    }
+
+    /// Returns a data source over `size` bytes starting at `offset` of the uncompressed BIFF V1 content of a BIF file,
+    /// e.g. the range of a `BifEmbeddedResource`.
+    /// Only the compressed data needed to reach the end of the range is decompressed.
+    pub fn extract(source: &DataSource, offset: u64, size: u64) -> std::io::Result<DataSource> {
+        let reader = &mut source.reader()?;
+        let position = reader.position()?;
+
+        let data = match detect_biff_type(reader)? {
+            Type::Biff => return Ok(source.embedded(offset, Some(size))),
+            Type::Bif => {
+                reader.set_position(position)?;
+                BifParser::extract(reader, offset, size)?
+            }
+            Type::Bifc => {
+                reader.set_position(position)?;
+                BifcParser::extract(reader, offset, size)?
+            }
+        };
+        Ok(DataSource::new(data).with_encoding(source.encoding()))
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum Type {
     Biff, // BIFF V1
-    Bif,  // BIFC V1   (compressed)
+    Bif,  // BIF V1.0  (compressed)
     Bifc, // BIFC V1.0 (compressed)
 }
 
@@ -50,6 +117,9 @@ pub const BIFFV1_SIGNATURE: &str = "BIFFV1  ";
 pub const BIF_V1_0_SIGNATURE: &str = "BIF V1.0";
 pub const BIFCV1_0_SIGNATURE: &str = "BIFCV1.0";
 
+/// The maximum number of bytes allocated before decompressing, the sizes come from the file headers
+const MAX_DECOMPRESSION_PREALLOCATION: usize = 1 << 20;
+
 impl Type {
     pub fn signature(&self) -> &'static str {
         match self {
@@ -60,12 +130,114 @@ impl Type {
     }
 }
 
+/// A BIF file, as read by the parser of its type.
+/// The file entries are listed first, followed by the tileset entries.
 #[derive(Debug, PartialEq, Eq)]
 pub struct Bif {
     pub r#type: Type,
     pub resources: Vec<BifEmbeddedResource>,
 }
 
+impl Bif {
+    /// Returns the resource referenced by the locator of a KEY resource entry.
+    /// The tileset index (bits 14-19) starts from 1, so a locator where it is 0 references a file by its index (bits 0-13).
+    pub fn resource_by_locator(&self, locator: u32) -> Option<&BifEmbeddedResource> {
+        let tileset_index = (locator >> 14) & 0x3f;
+        let file_index = locator & 0x3fff;
+        self.resources.iter().find(|resource| {
+            if resource.is_tileset() {
+                tileset_index != 0 && (resource.locator() >> 14) & 0x3f == tileset_index
+            } else {
+                tileset_index == 0 && resource.locator() & 0x3fff == file_index
+            }
+        })
+    }
+
+    /// Checks that the data of every resource lies within the uncompressed BIFF content of `source_len` bytes
+    /// and that no two resources share the same bytes.
+    /// All the problems found are reported, the resources are referenced by their index in `resources`.
+    pub fn validate(&self, source_len: u64) -> Result<(), Vec<BifValidationError>> {
+        let mut errors = Vec::new();
+
+        for (index, resource) in self.resources.iter().enumerate() {
+            let (offset, end) = resource.data_range();
+            if end > source_len {
+                errors.push(BifValidationError::OutOfBounds {
+                    index,
+                    offset,
+                    end,
+                    source_len,
+                });
+            }
+        }
+
+        let mut ranges = self
+            .resources
+            .iter()
+            .enumerate()
+            .map(|(index, resource)| (resource.data_range(), index))
+            .filter(|((offset, end), _)| end > offset)
+            .collect::<Vec<_>>();
+        ranges.sort();
+        // The resource ending last among the ones already visited
+        let mut last: Option<(u64, usize)> = None;
+        for ((offset, end), index) in ranges {
+            match last {
+                Some((last_end, last_index)) if offset < last_end => {
+                    errors.push(BifValidationError::Overlap {
+                        first: last_index.min(index),
+                        second: last_index.max(index),
+                    });
+                    if end > last_end {
+                        last = Some((end, index));
+                    }
+                }
+                _ => last = Some((end, index)),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// A problem found by [`Bif::validate`]
+#[derive(Debug, PartialEq, Eq)]
+pub enum BifValidationError {
+    /// The data of the resource ends after the end of the BIFF content
+    OutOfBounds {
+        index: usize,
+        offset: u64,
+        end: u64,
+        source_len: u64,
+    },
+    /// The data of two resources overlap
+    Overlap { first: usize, second: usize },
+}
+
+impl std::fmt::Display for BifValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BifValidationError::OutOfBounds {
+                index,
+                offset,
+                end,
+                source_len,
+            } => write!(
+                f,
+                "Resource {} spans bytes {}..{}, beyond the BIFF length {}",
+                index, offset, end, source_len
+            ),
+            BifValidationError::Overlap { first, second } => {
+                write!(f, "Resources {} and {} overlap", first, second)
+            }
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum BifEmbeddedResource {
     File {
@@ -73,6 +245,9 @@ pub enum BifEmbeddedResource {
         size: u32,
         offset: u64,
         r#type: ResourceType,
+        /// The trailing field of the entry, unused by the engine.
+        /// Some tools store the index of the BIF file in it.
+        unknown: u16,
     },
     Tileset {
         locator: u32,
@@ -80,9 +255,73 @@ pub enum BifEmbeddedResource {
         count: u32,
         offset: u64,
         r#type: ResourceType,
+        /// The trailing field of the entry, unused by the engine.
+        /// Some tools store the index of the BIF file in it.
+        unknown: u16,
     },
 }
 
+impl BifEmbeddedResource {
+    /// Returns the locator of the resource inside the BIF file
+    pub fn locator(&self) -> u32 {
+        match self {
+            BifEmbeddedResource::File { locator, .. }
+            | BifEmbeddedResource::Tileset { locator, .. } => *locator,
+        }
+    }
+
+    /// Returns the size of the file, or the size of a single tile for a tileset
+    pub fn size(&self) -> u32 {
+        match self {
+            BifEmbeddedResource::File { size, .. } | BifEmbeddedResource::Tileset { size, .. } => {
+                *size
+            }
+        }
+    }
+
+    /// Returns the offset of the resource data inside the BIF file
+    pub fn offset(&self) -> u64 {
+        match self {
+            BifEmbeddedResource::File { offset, .. }
+            | BifEmbeddedResource::Tileset { offset, .. } => *offset,
+        }
+    }
+
+    /// Returns the type of the resource
+    pub fn resource_type(&self) -> ResourceType {
+        match self {
+            BifEmbeddedResource::File { r#type, .. }
+            | BifEmbeddedResource::Tileset { r#type, .. } => *r#type,
+        }
+    }
+
+    /// Returns the trailing unknown field of the entry
+    pub fn unknown(&self) -> u16 {
+        match self {
+            BifEmbeddedResource::File { unknown, .. }
+            | BifEmbeddedResource::Tileset { unknown, .. } => *unknown,
+        }
+    }
+
+    /// Returns true if the resource is a tileset
+    pub fn is_tileset(&self) -> bool {
+        matches!(self, BifEmbeddedResource::Tileset { .. })
+    }
+
+    /// Returns the number of bytes of the resource data. A tileset spans `count` tiles of `size` bytes.
+    pub fn data_size(&self) -> u64 {
+        match self {
+            BifEmbeddedResource::File { size, .. } => *size as u64,
+            BifEmbeddedResource::Tileset { size, count, .. } => *size as u64 * *count as u64,
+        }
+    }
+
+    /// Returns the start and end offsets of the resource data
+    fn data_range(&self) -> (u64, u64) {
+        (self.offset(), self.offset() + self.data_size())
+    }
+}
+
 /// Detects the type of a BIFF file
 fn detect_biff_type<R: Read>(reader: &mut Reader<R>) -> std::io::Result<Type> {
     let value = reader.read_string(8)?;
@@ -105,13 +344,14 @@ fn parse_bif_embedded_file<R: Read>(
     let offset = reader.read_u32()? as u64;
     let size = reader.read_u32()?;
     let r#type = reader.read_u16()?;
-    reader.read_u16()?; // unknown data
+    let unknown = reader.read_u16()?;
 
     Ok(BifEmbeddedResource::File {
         locator,
         offset,
         size,
         r#type: ResourceType::from(r#type),
+        unknown,
     })
 }
 
@@ -123,7 +363,7 @@ fn parse_bif_embedded_tileset<R: Read>(
     let count = reader.read_u32()?;
     let size = reader.read_u32()?;
     let r#type = reader.read_u16()?;
-    reader.read_u16()?; // unknown data
+    let unknown = reader.read_u16()?;
 
     Ok(BifEmbeddedResource::Tileset {
         locator,
@@ -131,6 +371,7 @@ fn parse_bif_embedded_tileset<R: Read>(
         count,
         size,
         r#type: ResourceType::from(r#type),
+        unknown,
     })
 }
 
@@ -184,4 +425,216 @@ mod tests {
         let bif = BiffParser::import(&mut data.reader().unwrap()).unwrap();
         assert_eq!(bif.r#type, Type::Biff);
     }
+
+    #[test]
+    fn test_import_dispatches_on_type() {
+        for (path, expected_type, files, tilesets) in [
+            ("iwd/CD2/Data/AR3603.cbf", Type::Bif, 5, 1),
+            ("bg2/data/Data/AREA070C.bif", Type::Bifc, 5, 1),
+            ("pst/CS_0511.bif", Type::Biff, 4, 0),
+        ] {
+            let data = DataSource::new(Path::new(&format!("{RESOURCES_DIR}{path}")));
+
+            let bif = BifImporter::import(&data).unwrap();
+            assert_eq!(bif.r#type, expected_type, "{path}");
+            let tileset_count = bif
+                .resources
+                .iter()
+                .filter(|resource| resource.is_tileset())
+                .count();
+            assert_eq!(
+                (bif.resources.len() - tileset_count, tileset_count),
+                (files, tilesets),
+                "{path}"
+            );
+
+            // The same resources as the parser of the detected type
+            let mut reader = data.reader().unwrap();
+            let parsed = match expected_type {
+                Type::Bif => BifParser::import(&mut reader),
+                Type::Bifc => BifcParser::import(&mut reader),
+                Type::Biff => BiffParser::import(&mut reader),
+            }
+            .unwrap();
+            assert_eq!(bif, parsed, "{path}");
+        }
+    }
+
+    /// Builds a BIFF V1 file with the given `(offset, size)` file entries, followed by `data_len` bytes of data
+    fn biff_with_files(files: &[(u32, u32)], data_len: usize) -> Vec<u8> {
+        let mut bytes = BIFFV1_SIGNATURE.as_bytes().to_vec();
+        bytes.extend((files.len() as u32).to_le_bytes());
+        bytes.extend(0u32.to_le_bytes());
+        bytes.extend(20u32.to_le_bytes());
+        for (index, (offset, size)) in files.iter().enumerate() {
+            bytes.extend((index as u32).to_le_bytes());
+            bytes.extend(offset.to_le_bytes());
+            bytes.extend(size.to_le_bytes());
+            bytes.extend(ResourceType::TwoDA.to_u16().to_le_bytes());
+            bytes.extend(0u16.to_le_bytes());
+        }
+        bytes.resize(bytes.len() + data_len, 0);
+        bytes
+    }
+
+    #[test]
+    fn test_decompress() {
+        for path in [
+            "iwd/CD2/Data/AR3603.cbf",
+            "bg2/data/Data/AREA070C.bif",
+            "pst/CS_0511.bif",
+        ] {
+            let data = DataSource::new(Path::new(&format!("{RESOURCES_DIR}{path}")));
+            let bif = BifImporter::import(&data).unwrap();
+
+            // The uncompressed content is a BIFF V1 file listing the same resources
+            let decompressed = BifImporter::decompress(&data).unwrap();
+            let biff = BiffParser::import(&mut decompressed.reader().unwrap()).unwrap();
+            assert_eq!(biff.resources, bif.resources, "{path}");
+
+            for resource in &bif.resources {
+                let mut expected = Vec::new();
+                decompressed
+                    .embedded(resource.offset(), Some(resource.data_size()))
+                    .reader()
+                    .unwrap()
+                    .read_to_end(&mut expected, u64::MAX)
+                    .unwrap();
+                let mut extracted = Vec::new();
+                BifImporter::extract(&data, resource.offset(), resource.data_size())
+                    .unwrap()
+                    .reader()
+                    .unwrap()
+                    .read_to_end(&mut extracted, u64::MAX)
+                    .unwrap();
+                assert_eq!(extracted, expected, "{path}");
+            }
+
+            // A compressed range ending past u64::MAX is an error, not an overflow
+            if bif.r#type != Type::Biff {
+                assert!(BifImporter::extract(&data, u64::MAX, 2).is_err(), "{path}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_resource_by_locator() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}iwd/CD2/Data/AR3603.cbf"
+        )));
+        let bif = BifImporter::import(&data).unwrap();
+
+        for resource in &bif.resources {
+            // The BIF index in bits 20-31 is ignored
+            let locator = resource.locator() | (3 << 20);
+            assert_eq!(bif.resource_by_locator(locator), Some(resource));
+        }
+
+        let tileset = bif.resources.iter().find(|r| r.is_tileset()).unwrap();
+        assert_eq!(tileset.locator() >> 14 & 0x3f, 1);
+        assert_eq!(bif.resource_by_locator(1 << 14), Some(tileset));
+        assert_eq!(bif.resource_by_locator(2 << 14), None);
+        assert_eq!(bif.resource_by_locator(0x3fff), None);
+    }
+
+    #[test]
+    fn test_import_preserves_unknown_entry_field() {
+        let mut bytes = biff_with_files(&[(52, 10), (62, 20)], 30);
+        // The unknown field is the last u16 of each 16 bytes entry
+        bytes[20 + 16 + 14..20 + 16 + 16].copy_from_slice(&7u16.to_le_bytes());
+
+        let bif = BifImporter::import(&DataSource::new(bytes)).unwrap();
+        assert_eq!(bif.resources[0].unknown(), 0);
+        assert_eq!(bif.resources[1].unknown(), 7);
+
+        // The fixtures leave it unset
+        let data = DataSource::new(Path::new(&format!("{RESOURCES_DIR}pst/CS_0511.bif")));
+        let bif = BifImporter::import(&data).unwrap();
+        assert!(bif.resources.iter().all(|resource| resource.unknown() == 0));
+    }
+
+    #[test]
+    fn test_validate() {
+        // 20 bytes of header, 2 entries of 16 bytes, then 30 bytes of data
+        let bytes = biff_with_files(&[(52, 10), (62, 20)], 30);
+        let len = bytes.len() as u64;
+        let bif = BifImporter::import(&DataSource::new(bytes)).unwrap();
+        assert_eq!(bif.validate(len), Ok(()));
+
+        let bif = Bif {
+            r#type: Type::Biff,
+            resources: vec![
+                BifEmbeddedResource::File {
+                    locator: 0,
+                    size: 0,
+                    offset: 50,
+                    r#type: ResourceType::TwoDA,
+                    unknown: 0,
+                },
+                BifEmbeddedResource::Tileset {
+                    locator: 1 << 14,
+                    size: 10,
+                    count: 5,
+                    offset: 0,
+                    r#type: ResourceType::Tis,
+                    unknown: 0,
+                },
+            ],
+        };
+        assert_eq!(bif.validate(50), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_reports_all_problems() {
+        // The second entry overlaps the first one, the last entry overruns the data
+        let bytes = biff_with_files(&[(68, 10), (72, 10), (90, 20)], 30);
+        let len = bytes.len() as u64;
+        assert_eq!(len, 98);
+        let bif = BifImporter::import(&DataSource::new(bytes)).unwrap();
+
+        assert_eq!(
+            bif.validate(len),
+            Err(vec![
+                BifValidationError::OutOfBounds {
+                    index: 2,
+                    offset: 90,
+                    end: 110,
+                    source_len: 98,
+                },
+                BifValidationError::Overlap {
+                    first: 0,
+                    second: 1,
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_import_truncated_bif() {
+        // The third entry is cut in the middle
+        let mut bytes = biff_with_files(&[(68, 1), (69, 1), (70, 1)], 0);
+        bytes.truncate(20 + 16 * 2 + 8);
+
+        assert!(BifImporter::import(&DataSource::new(bytes.clone())).is_err());
+
+        let strict = BifImporter::import_with_options(
+            &DataSource::new(bytes.clone()),
+            &ImportOptions::new(),
+        );
+        assert!(strict.is_err());
+
+        let partial = BifImporter::import_with_options(
+            &DataSource::new(bytes),
+            &ImportOptions::new().with_lenient_truncation(true),
+        )
+        .unwrap();
+        assert!(!partial.is_complete());
+        assert_eq!(
+            partial.error.as_ref().unwrap().kind(),
+            std::io::ErrorKind::UnexpectedEof
+        );
+        assert_eq!(partial.value.r#type, Type::Biff);
+        assert_eq!(partial.value.resources.len(), 2);
+        assert_eq!(partial.value.resources[1].offset(), 69);
+    }
 }
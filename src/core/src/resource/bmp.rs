@@ -1,12 +1,128 @@
 use image::{ImageBuffer, Rgba};
 
-use crate::datasource::DataSource;
+use crate::{
+    datasource::{DataSource, Importer},
+    resource::common::Rgb,
+};
+
+/// The compression of the images whose pixels are split in channels by masks
+const BI_BITFIELDS: u32 = 3;
+const BI_ALPHABITFIELDS: u32 = 6;
 
 /// A BMP file importer
 pub struct BmpImporter;
 
+impl Importer for BmpImporter {
+    type T = BmpData;
+
+    /// Imports the BITMAPFILEHEADER, the BITMAPINFOHEADER and the color table of a BMP file.
+    /// The pixel data is not decoded, use `to_image` to get the image.
+    fn import(source: &DataSource) -> std::io::Result<BmpData> {
+        let mut reader = source.reader()?;
+
+        // BITMAPFILEHEADER
+        let signature = reader.read_string(2)?;
+        if signature != "BM" {
+            return Err(std::io::Error::other(format!(
+                "Wrong file type: {}",
+                signature
+            )));
+        }
+        let _file_size = reader.read_u32()?;
+        let _reserved = reader.read_u32()?;
+        let data_offset = reader.read_u32()?;
+
+        // BITMAPINFOHEADER, or one of its extensions (BITMAPV4HEADER, BITMAPV5HEADER)
+        let header_size = reader.read_u32()? as u64;
+        if header_size < 40 {
+            return Err(std::io::Error::other(format!(
+                "Unsupported BMP header size: {}",
+                header_size
+            )));
+        }
+        let width = reader.read_i32()?;
+        let height = reader.read_i32()?;
+        let _planes = reader.read_u16()?;
+        let bit_depth = reader.read_u16()?;
+        let compression = reader.read_u32()?;
+        let _image_size = reader.read_u32()?;
+        let _x_pixels_per_meter = reader.read_i32()?;
+        let _y_pixels_per_meter = reader.read_i32()?;
+        let colors_used = reader.read_u32()?;
+        let _colors_important = reader.read_u32()?;
+
+        // The color masks are part of the header since BITMAPV2INFOHEADER (52 bytes), and the
+        // alpha mask since BITMAPV3INFOHEADER (56 bytes). With a BITMAPINFOHEADER they follow it.
+        let masks = if compression == BI_BITFIELDS || compression == BI_ALPHABITFIELDS {
+            let red = reader.read_u32()?;
+            let green = reader.read_u32()?;
+            let blue = reader.read_u32()?;
+            let alpha = if header_size >= 56 || compression == BI_ALPHABITFIELDS {
+                reader.read_u32()?
+            } else {
+                0
+            };
+            Some(BmpMasks {
+                red,
+                green,
+                blue,
+                alpha,
+            })
+        } else {
+            None
+        };
+
+        // The color table follows the info header and is only used by paletted images
+        let palette = if bit_depth <= 8 {
+            let palette_entries = match colors_used {
+                0 => 1 << bit_depth,
+                colors => colors.min(1 << bit_depth),
+            };
+            reader.set_position(14 + header_size)?;
+            let mut palette = Vec::with_capacity(palette_entries as usize);
+            for _ in 0..palette_entries {
+                let b = reader.read_u8()?;
+                let g = reader.read_u8()?;
+                let r = reader.read_u8()?;
+                let _reserved = reader.read_u8()?;
+                palette.push(Rgb {
+                    r,
+                    g,
+                    b,
+                    alpha: 255,
+                });
+            }
+            palette
+        } else {
+            vec![]
+        };
+
+        Ok(BmpData {
+            width: width.unsigned_abs(),
+            height: height.unsigned_abs(),
+            top_down: height < 0,
+            bit_depth,
+            compression,
+            masks,
+            palette,
+            data_offset,
+        })
+    }
+}
+
 impl BmpImporter {
+    /// Decodes the image of a BMP file.
+    /// The 32 bits images with an alpha mask are decoded here, as the `image` crate ignores the
+    /// alpha mask of some header variants; the other ones are delegated to the `image` crate.
     pub fn to_image(source: &DataSource) -> image::ImageResult<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+        let bmp = Self::import(source)?;
+        if let Some(masks) = bmp
+            .masks
+            .filter(|masks| bmp.bit_depth == 32 && masks.alpha != 0)
+        {
+            return Self::to_image_with_alpha(source, &bmp, &masks);
+        }
+
         let reader = source.reader()?;
         Ok(
             image::ImageReader::with_format(reader.data, image::ImageFormat::Bmp)
@@ -16,6 +132,79 @@ impl BmpImporter {
     }
 }
 
+impl BmpImporter {
+    fn to_image_with_alpha(
+        source: &DataSource,
+        bmp: &BmpData,
+        masks: &BmpMasks,
+    ) -> image::ImageResult<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+        let mut reader = source.reader()?;
+        reader.set_position(bmp.data_offset as u64)?;
+
+        let mut image = ImageBuffer::new(bmp.width, bmp.height);
+        for row in 0..bmp.height {
+            let y = if bmp.top_down {
+                row
+            } else {
+                bmp.height - 1 - row
+            };
+            for x in 0..bmp.width {
+                let pixel = reader.read_u32()?;
+                image.put_pixel(
+                    x,
+                    y,
+                    Rgba([
+                        masked_channel(pixel, masks.red),
+                        masked_channel(pixel, masks.green),
+                        masked_channel(pixel, masks.blue),
+                        masked_channel(pixel, masks.alpha),
+                    ]),
+                );
+            }
+        }
+        Ok(image)
+    }
+}
+
+/// Extracts the channel selected by a mask from a pixel, scaled to 8 bits
+fn masked_channel(pixel: u32, mask: u32) -> u8 {
+    if mask == 0 {
+        return 0;
+    }
+    let max = mask >> mask.trailing_zeros();
+    let value = (pixel & mask) >> mask.trailing_zeros();
+    (value as u64 * 255 / max as u64) as u8
+}
+
+/// The header information of a BMP file
+#[derive(Debug, PartialEq, Eq)]
+pub struct BmpData {
+    pub width: u32,
+    pub height: u32,
+    /// Whether the rows are stored from top to bottom
+    pub top_down: bool,
+    /// The number of bits per pixel
+    pub bit_depth: u16,
+    /// The compression method (0 = BI_RGB, 1 = BI_RLE8, 2 = BI_RLE4, 3 = BI_BITFIELDS, 6 = BI_ALPHABITFIELDS)
+    pub compression: u32,
+    /// The masks of the color channels of BI_BITFIELDS and BI_ALPHABITFIELDS images
+    pub masks: Option<BmpMasks>,
+    /// The colors palette, empty if the image is not paletted
+    pub palette: Vec<Rgb>,
+    /// The offset of the pixel data
+    pub data_offset: u32,
+}
+
+/// The masks of the bits of each channel in a pixel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BmpMasks {
+    pub red: u32,
+    pub green: u32,
+    pub blue: u32,
+    /// The alpha mask, 0 if the image has no alpha channel
+    pub alpha: u32,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -56,4 +245,82 @@ mod tests {
 
         assert_images_are_equal(&image.into(), &original);
     }
+
+    #[test]
+    fn test_import_bmp_header() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/BMP/CCHAN05.BMP"
+        )));
+
+        let bmp = BmpImporter::import(&data).unwrap();
+
+        assert_eq!(bmp.width, 92);
+        assert_eq!(bmp.height, 135);
+        assert!(!bmp.top_down);
+        assert_eq!(bmp.bit_depth, 32);
+        assert_eq!(bmp.compression, 3);
+        assert_eq!(
+            bmp.masks,
+            Some(BmpMasks {
+                red: 0x00ff0000,
+                green: 0x0000ff00,
+                blue: 0x000000ff,
+                alpha: 0xff000000,
+            })
+        );
+        assert!(bmp.palette.is_empty());
+    }
+
+    #[test]
+    fn test_import_bmp_palette() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/BMP/PAL8.BMP"
+        )));
+
+        let bmp = BmpImporter::import(&data).unwrap();
+
+        assert_eq!(bmp.width, 5);
+        assert_eq!(bmp.height, 3);
+        assert_eq!(bmp.bit_depth, 8);
+        assert_eq!(bmp.compression, 0);
+        assert_eq!(bmp.masks, None);
+        assert_eq!(bmp.palette.len(), 16);
+
+        // Each pixel of the image is the palette color at index (x + y * width) % 16
+        let image = BmpImporter::to_image(&data).unwrap();
+        for (x, y, pixel) in image.enumerate_pixels() {
+            let color = &bmp.palette[((x + y * bmp.width) % 16) as usize];
+            assert_eq!(pixel, &Rgba([color.r, color.g, color.b, color.alpha]));
+        }
+    }
+
+    #[test]
+    fn test_parse_bmp_with_alpha() {
+        // A BITMAPINFOHEADER followed by the BI_ALPHABITFIELDS masks, in RGBA order
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/BMP/ALPHA32.BMP"
+        )));
+
+        let bmp = BmpImporter::import(&data).unwrap();
+        assert_eq!(bmp.compression, 6);
+        assert_eq!(bmp.masks.unwrap().alpha, 0x000000ff);
+
+        let image = BmpImporter::to_image(&data).unwrap();
+
+        assert_eq!(image.dimensions(), (3, 2));
+        assert_eq!(image.get_pixel(0, 0), &Rgba([255, 0, 0, 255]));
+        assert_eq!(image.get_pixel(1, 0), &Rgba([0, 255, 0, 128]));
+        assert_eq!(image.get_pixel(2, 0), &Rgba([0, 0, 255, 0]));
+        assert_eq!(image.get_pixel(0, 1), &Rgba([255, 255, 255, 64]));
+        assert_eq!(image.get_pixel(1, 1), &Rgba([0, 0, 0, 255]));
+        assert_eq!(image.get_pixel(2, 1), &Rgba([10, 20, 30, 200]));
+    }
+
+    #[test]
+    fn test_masked_channel() {
+        assert_eq!(masked_channel(0x12345678, 0x00ff0000), 0x34);
+        assert_eq!(masked_channel(0x7c00, 0x7c00), 255);
+        assert_eq!(masked_channel(0x4000, 0x7c00), 131);
+        assert_eq!(masked_channel(0xffffffff, 0), 0);
+    }
 }
@@ -0,0 +1,330 @@
+use std::io::Read;
+
+use serde::{Deserialize, Serialize};
+
+use crate::datasource::{DataSource, Importer, Reader};
+
+/// A CHU file importer
+pub struct ChuImporter;
+
+impl Importer for ChuImporter {
+    type T = Chu;
+
+    fn import(source: &DataSource) -> std::io::Result<Chu> {
+        let mut reader = source.reader()?;
+
+        let signature = reader.read_string(8)?;
+        if signature != "CHUIV1  " {
+            return Err(std::io::Error::other(format!(
+                "Wrong file type: {}",
+                signature
+            )));
+        }
+
+        let windows_count = reader.read_u32()? as usize;
+        let controls_offset = reader.read_u32()? as u64;
+        let windows_offset = reader.read_u32()? as u64;
+
+        // Read the windows
+        let mut entries = Vec::with_capacity(windows_count);
+        reader.set_position(windows_offset)?;
+        for _ in 0..windows_count {
+            let id = reader.read_u16()?;
+            reader.skip(2)?; // unknown
+            let x = reader.read_u16()?;
+            let y = reader.read_u16()?;
+            let width = reader.read_u16()?;
+            let height = reader.read_u16()?;
+            let has_background = reader.read_u16()? != 0;
+            let controls_count = reader.read_u16()? as usize;
+            let background = reader.read_string(8)?;
+            let first_control = reader.read_u16()? as u64;
+            let flags = reader.read_u16()?;
+            entries.push((
+                ChuWindow {
+                    id,
+                    x,
+                    y,
+                    width,
+                    height,
+                    has_background,
+                    background,
+                    flags,
+                    controls: Vec::with_capacity(controls_count),
+                },
+                first_control,
+            ));
+        }
+
+        let mut windows = Vec::with_capacity(windows_count);
+        for (mut window, first_control) in entries {
+            // Read the controls of the window, located through the control table
+            for index in first_control..first_control + window.controls.capacity() as u64 {
+                let offset = reader.read_u32_at(controls_offset + index * 8)? as u64;
+                reader.set_position(offset)?;
+                window.controls.push(ChuControl::read_entry(&mut reader)?);
+            }
+            windows.push(window);
+        }
+
+        Ok(Chu { windows })
+    }
+}
+
+/// A CHU file, describing the layout of the GUI windows
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Chu {
+    pub windows: Vec<ChuWindow>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChuWindow {
+    pub id: u16,
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+    /// Whether the window is drawn over the `background` MOS
+    pub has_background: bool,
+    pub background: String,
+    pub flags: u16,
+    pub controls: Vec<ChuControl>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChuControl {
+    pub id: u32,
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+    pub kind: ChuControlKind,
+}
+
+impl ChuControl {
+    /// Reads a control, the reader must be at the start of the control
+    fn read_entry<R: Read>(reader: &mut Reader<R>) -> std::io::Result<ChuControl> {
+        let id = reader.read_u32()?;
+        let x = reader.read_u16()?;
+        let y = reader.read_u16()?;
+        let width = reader.read_u16()?;
+        let height = reader.read_u16()?;
+        let control_type = reader.read_u8()?;
+        reader.skip(1)?; // unknown
+
+        let kind = match control_type {
+            0 => ChuControlKind::Button(ChuButton {
+                bam: reader.read_string(8)?,
+                cycle: reader.read_u8()?,
+                text_flags: reader.read_u8()?,
+                frame_unpressed: reader.read_u8()?,
+                anchor_x1: reader.read_u8()?,
+                frame_pressed: reader.read_u8()?,
+                anchor_x2: reader.read_u8()?,
+                frame_selected: reader.read_u8()?,
+                anchor_y1: reader.read_u8()?,
+                frame_disabled: reader.read_u8()?,
+                anchor_y2: reader.read_u8()?,
+            }),
+            2 => ChuControlKind::Slider(ChuSlider {
+                background: reader.read_string(8)?,
+                knob: reader.read_string(8)?,
+                cycle: reader.read_u16()?,
+                frame_ungrabbed: reader.read_u16()?,
+                frame_grabbed: reader.read_u16()?,
+                knob_x: reader.read_u16()?,
+                knob_y: reader.read_u16()?,
+                step_width: reader.read_u16()?,
+                step_count: reader.read_u16()?,
+            }),
+            3 => {
+                let backgrounds = [
+                    reader.read_string(8)?,
+                    reader.read_string(8)?,
+                    reader.read_string(8)?,
+                ];
+                let cursor = reader.read_string(8)?;
+                let cursor_cycle = reader.read_u16()?;
+                let cursor_frame = reader.read_u16()?;
+                let text_x = reader.read_u16()?;
+                let text_y = reader.read_u16()?;
+                let scroll_bar = reader.read_u32()?;
+                let font = reader.read_string(8)?;
+                reader.skip(2)?; // unknown
+                let text = reader.read_string(32)?;
+                let max_length = reader.read_u16()?;
+                let text_case = reader.read_u32()?;
+                ChuControlKind::TextEdit(ChuTextEdit {
+                    backgrounds,
+                    cursor,
+                    cursor_cycle,
+                    cursor_frame,
+                    text_x,
+                    text_y,
+                    scroll_bar,
+                    font,
+                    text,
+                    max_length,
+                    text_case,
+                })
+            }
+            5 => ChuControlKind::TextArea(ChuTextArea {
+                initials_font: reader.read_string(8)?,
+                font: reader.read_string(8)?,
+                colors: [reader.read_u32()?, reader.read_u32()?, reader.read_u32()?],
+                scroll_bar: reader.read_u32()?,
+            }),
+            6 => ChuControlKind::Label(ChuLabel {
+                text: reader.read_u32()?,
+                font: reader.read_string(8)?,
+                colors: [reader.read_u32()?, reader.read_u32()?],
+                text_flags: reader.read_u16()?,
+            }),
+            7 => ChuControlKind::ScrollBar(ChuScrollBar {
+                bam: reader.read_string(8)?,
+                cycle: reader.read_u16()?,
+                frame_up_unpressed: reader.read_u16()?,
+                frame_up_pressed: reader.read_u16()?,
+                frame_down_unpressed: reader.read_u16()?,
+                frame_down_pressed: reader.read_u16()?,
+                frame_trough: reader.read_u16()?,
+                frame_slider: reader.read_u16()?,
+                text_area: reader.read_u32()?,
+            }),
+            value => ChuControlKind::Unknown(value),
+        };
+
+        Ok(ChuControl {
+            id,
+            x,
+            y,
+            width,
+            height,
+            kind,
+        })
+    }
+}
+
+/// The type of a control, with its type specific fields
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChuControlKind {
+    Button(ChuButton),
+    Slider(ChuSlider),
+    TextEdit(ChuTextEdit),
+    TextArea(ChuTextArea),
+    Label(ChuLabel),
+    ScrollBar(ChuScrollBar),
+    Unknown(u8),
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChuButton {
+    pub bam: String,
+    pub cycle: u8,
+    pub text_flags: u8,
+    pub frame_unpressed: u8,
+    pub anchor_x1: u8,
+    pub frame_pressed: u8,
+    pub anchor_x2: u8,
+    pub frame_selected: u8,
+    pub anchor_y1: u8,
+    pub frame_disabled: u8,
+    pub anchor_y2: u8,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChuSlider {
+    /// The MOS file of the slider background
+    pub background: String,
+    /// The BAM file of the slider knob
+    pub knob: String,
+    pub cycle: u16,
+    pub frame_ungrabbed: u16,
+    pub frame_grabbed: u16,
+    pub knob_x: u16,
+    pub knob_y: u16,
+    /// The distance between two knob positions
+    pub step_width: u16,
+    pub step_count: u16,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChuTextEdit {
+    /// The MOS files of the text edit background
+    pub backgrounds: [String; 3],
+    /// The BAM file of the cursor
+    pub cursor: String,
+    pub cursor_cycle: u16,
+    pub cursor_frame: u16,
+    pub text_x: u16,
+    pub text_y: u16,
+    /// The id of the attached scroll bar control
+    pub scroll_bar: u32,
+    pub font: String,
+    /// The initial text
+    pub text: String,
+    pub max_length: u16,
+    pub text_case: u32,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChuTextArea {
+    pub initials_font: String,
+    pub font: String,
+    /// The RGBA colors of the text
+    pub colors: [u32; 3],
+    /// The id of the attached scroll bar control
+    pub scroll_bar: u32,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChuLabel {
+    /// The StrRef of the initial text
+    pub text: u32,
+    pub font: String,
+    /// The RGBA foreground and background colors
+    pub colors: [u32; 2],
+    pub text_flags: u16,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChuScrollBar {
+    pub bam: String,
+    pub cycle: u16,
+    pub frame_up_unpressed: u16,
+    pub frame_up_pressed: u16,
+    pub frame_down_unpressed: u16,
+    pub frame_down_pressed: u16,
+    pub frame_trough: u16,
+    pub frame_slider: u16,
+    /// The id of the attached text area control
+    pub text_area: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::RESOURCES_DIR;
+    use insta::assert_json_snapshot;
+    use std::path::Path;
+
+    #[test]
+    fn test_parse_chu() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/CHU/GUITEST.CHU"
+        )));
+
+        let chu = ChuImporter::import(&data).unwrap();
+
+        assert_eq!(chu.windows.len(), 2);
+        assert_eq!(chu.windows[0].controls.len(), 4);
+        assert_eq!(chu.windows[1].controls.len(), 3);
+        assert!(chu.windows[0].has_background);
+        assert!(matches!(
+            chu.windows[1].controls[2].kind,
+            ChuControlKind::Unknown(9)
+        ));
+
+        assert_json_snapshot!(chu);
+    }
+}
@@ -1,5 +1,9 @@
+use std::path::Path;
+
+use image::{ImageBuffer, ImageFormat, ImageResult, Rgba};
+
 /// Represents an RGB color with an alpha channel
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Rgb {
     pub r: u8,
     pub g: u8,
@@ -9,3 +13,105 @@ pub struct Rgb {
     /// 255 - opaque
     pub alpha: u8,
 }
+
+/// The background color used by `save_image` for the formats without alpha channel
+pub const DEFAULT_BACKGROUND: Rgb = Rgb {
+    r: 0,
+    g: 0,
+    b: 0,
+    alpha: 255,
+};
+
+/// Saves an image, the format is inferred from the extension of the path.
+/// The formats without alpha channel, like BMP or JPEG, get the image composited onto `DEFAULT_BACKGROUND`.
+pub fn save_image(image: &ImageBuffer<Rgba<u8>, Vec<u8>>, path: &Path) -> ImageResult<()> {
+    save_image_with_background(image, path, DEFAULT_BACKGROUND)
+}
+
+/// Saves an image, the format is inferred from the extension of the path.
+/// The formats without alpha channel, like BMP or JPEG, get the image composited onto `background`,
+/// whose alpha is ignored.
+pub fn save_image_with_background(
+    image: &ImageBuffer<Rgba<u8>, Vec<u8>>,
+    path: &Path,
+    background: Rgb,
+) -> ImageResult<()> {
+    let format = ImageFormat::from_path(path)?;
+    match format {
+        ImageFormat::Bmp | ImageFormat::Jpeg | ImageFormat::Pnm | ImageFormat::Hdr => {
+            flatten(image, background).save_with_format(path, format)
+        }
+        _ => image.save_with_format(path, format),
+    }
+}
+
+/// Composites an image onto an opaque background color
+fn flatten(
+    image: &ImageBuffer<Rgba<u8>, Vec<u8>>,
+    background: Rgb,
+) -> ImageBuffer<image::Rgb<u8>, Vec<u8>> {
+    let blend = |value: u8, background: u8, alpha: u8| {
+        let (value, background, alpha) = (value as u32, background as u32, alpha as u32);
+        ((value * alpha + background * (255 - alpha) + 127) / 255) as u8
+    };
+    ImageBuffer::from_fn(image.width(), image.height(), |x, y| {
+        let Rgba([r, g, b, alpha]) = *image.get_pixel(x, y);
+        image::Rgb([
+            blend(r, background.r, alpha),
+            blend(g, background.g, alpha),
+            blend(b, background.b, alpha),
+        ])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image_with_alpha() -> ImageBuffer<Rgba<u8>, Vec<u8>> {
+        ImageBuffer::from_fn(3, 1, |x, _| match x {
+            0 => Rgba([255, 0, 0, 255]),
+            1 => Rgba([0, 0, 255, 128]),
+            _ => Rgba([0, 255, 0, 0]),
+        })
+    }
+
+    #[test]
+    fn test_save_png_preserves_alpha() {
+        let path =
+            std::env::temp_dir().join(format!("infinitier_save_image_{}.png", std::process::id()));
+        let image = image_with_alpha();
+
+        save_image(&image, &path).unwrap();
+
+        let saved = image::open(&path).unwrap().to_rgba8();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(saved, image);
+    }
+
+    #[test]
+    fn test_save_bmp_flattens_alpha() {
+        let path =
+            std::env::temp_dir().join(format!("infinitier_save_image_{}.BMP", std::process::id()));
+        let background = Rgb {
+            r: 255,
+            g: 255,
+            b: 255,
+            alpha: 0,
+        };
+
+        save_image_with_background(&image_with_alpha(), &path, background).unwrap();
+
+        let saved = image::open(&path).unwrap().to_rgba8();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(saved.get_pixel(0, 0), &Rgba([255, 0, 0, 255]));
+        assert_eq!(saved.get_pixel(1, 0), &Rgba([127, 127, 255, 255]));
+        assert_eq!(saved.get_pixel(2, 0), &Rgba([255, 255, 255, 255]));
+    }
+
+    #[test]
+    fn test_save_image_fails_for_unknown_extension() {
+        let path = std::env::temp_dir().join("infinitier_save_image.unknown");
+        assert!(save_image(&image_with_alpha(), &path).is_err());
+    }
+}
@@ -0,0 +1,625 @@
+use serde::{Deserialize, Serialize};
+
+use crate::datasource::{DataSource, Importer};
+
+/// The number of soundset StrRefs stored in a V1.0 CRE file
+const SOUNDS_COUNT: usize = 100;
+
+/// The number of inventory slots stored in a V1.0 CRE file
+const ITEM_SLOTS_COUNT: usize = 38;
+
+/// A CRE file importer
+pub struct CreImporter;
+
+impl Importer for CreImporter {
+    type T = Cre;
+
+    fn import(source: &DataSource) -> std::io::Result<Cre> {
+        let mut reader = source.reader()?;
+
+        let signature = reader.read_string(4)?;
+        if signature != "CRE " {
+            return Err(std::io::Error::other(format!(
+                "Wrong file type: {}",
+                signature
+            )));
+        }
+
+        let version = CreVersion::parse(&reader.read_string(4)?)?;
+        if version != CreVersion::V1_0 {
+            return Err(std::io::Error::other(format!(
+                "Unsupported CRE version: {:?}",
+                version
+            )));
+        }
+
+        let long_name = reader.read_u32()?;
+        let short_name = reader.read_u32()?;
+        let flags = reader.read_u32()?;
+        let xp_value = reader.read_u32()?;
+        let xp = reader.read_u32()?;
+        let gold = reader.read_u32()?;
+        let status_flags = reader.read_u32()?;
+        let current_hp = reader.read_u16()?;
+        let max_hp = reader.read_u16()?;
+        let animation_id = reader.read_u32()?;
+        let mut colors = [0; 7];
+        for color in &mut colors {
+            *color = reader.read_u8()?;
+        }
+        let effects_version = reader.read_u8()?;
+        let small_portrait = reader.read_string(8)?;
+        let large_portrait = reader.read_string(8)?;
+        let reputation = reader.read_u8()?;
+        let hide_in_shadows = reader.read_u8()?;
+        let natural_ac = reader.read_i16()?;
+        let effective_ac = reader.read_i16()?;
+        reader.skip(8)?; // AC modifiers
+        let thac0 = reader.read_u8()?;
+        let attacks = reader.read_u8()?;
+        let saving_throws = CreSavingThrows {
+            death: reader.read_u8()?,
+            wands: reader.read_u8()?,
+            polymorph: reader.read_u8()?,
+            breath: reader.read_u8()?,
+            spells: reader.read_u8()?,
+        };
+
+        // Resistances, skills and proficiencies are not parsed yet
+        reader.set_position(0xa4)?;
+        let mut sounds = Vec::with_capacity(SOUNDS_COUNT);
+        for _ in 0..SOUNDS_COUNT {
+            sounds.push(reader.read_u32()?);
+        }
+
+        let levels = [reader.read_u8()?, reader.read_u8()?, reader.read_u8()?];
+        let sex = reader.read_u8()?;
+        let stats = CreStats {
+            strength: reader.read_u8()?,
+            strength_bonus: reader.read_u8()?,
+            intelligence: reader.read_u8()?,
+            wisdom: reader.read_u8()?,
+            dexterity: reader.read_u8()?,
+            constitution: reader.read_u8()?,
+            charisma: reader.read_u8()?,
+        };
+        let morale = reader.read_u8()?;
+        let morale_break = reader.read_u8()?;
+        let racial_enemy = reader.read_u8()?;
+        let morale_recovery_time = reader.read_u16()?;
+        let kit = reader.read_u32()?;
+        let scripts = CreScripts {
+            r#override: reader.read_string(8)?,
+            class: reader.read_string(8)?,
+            race: reader.read_string(8)?,
+            general: reader.read_string(8)?,
+            default: reader.read_string(8)?,
+        };
+        let ids = CreIds {
+            enemy_ally: reader.read_u8()?,
+            general: reader.read_u8()?,
+            race: reader.read_u8()?,
+            class: reader.read_u8()?,
+            specific: reader.read_u8()?,
+            gender: reader.read_u8()?,
+        };
+        reader.skip(5)?; // OBJECT.IDS references
+        let alignment = reader.read_u8()?;
+        let _global_actor_enumeration = reader.read_u16()?;
+        let _local_actor_enumeration = reader.read_u16()?;
+        let death_variable = reader.read_string(32)?;
+
+        let known_spells_offset = reader.read_u32()? as u64;
+        let known_spells_count = reader.read_u32()? as usize;
+        let memorization_info_offset = reader.read_u32()? as u64;
+        let memorization_info_count = reader.read_u32()? as usize;
+        let memorized_spells_offset = reader.read_u32()? as u64;
+        let memorized_spells_count = reader.read_u32()? as usize;
+        let item_slots_offset = reader.read_u32()? as u64;
+        let items_offset = reader.read_u32()? as u64;
+        let items_count = reader.read_u32()? as usize;
+        let effects_offset = reader.read_u32()? as u64;
+        let effects_count = reader.read_u32()?;
+        let dialog = reader.read_string(8)?;
+
+        let known_spells =
+            reader.read_table(known_spells_offset, known_spells_count, |reader| {
+                Ok(CreKnownSpell {
+                    resref: reader.read_string(8)?,
+                    level: reader.read_u16()?,
+                    spell_type: reader.read_u16()?,
+                })
+            })?;
+
+        let memorization_info = reader.read_table(
+            memorization_info_offset,
+            memorization_info_count,
+            |reader| {
+                Ok(CreMemorizationInfo {
+                    level: reader.read_u16()?,
+                    memorizable_count: reader.read_u16()?,
+                    memorizable_count_with_effects: reader.read_u16()?,
+                    spell_type: reader.read_u16()?,
+                    memorized_spells_index: reader.read_u32()?,
+                    memorized_spells_count: reader.read_u32()?,
+                })
+            },
+        )?;
+
+        let memorized_spells =
+            reader.read_table(memorized_spells_offset, memorized_spells_count, |reader| {
+                Ok(CreMemorizedSpell {
+                    resref: reader.read_string(8)?,
+                    memorized: reader.read_u32()? & 1 != 0,
+                })
+            })?;
+
+        let items = reader.read_table(items_offset, items_count, |reader| {
+            Ok(CreItem {
+                resref: reader.read_string(8)?,
+                expiration_time: reader.read_u16()?,
+                charges: [reader.read_u16()?, reader.read_u16()?, reader.read_u16()?],
+                flags: reader.read_u32()?,
+            })
+        })?;
+
+        // Read the item slots, each one is an index into the items table
+        let item_slots = reader.read_table(item_slots_offset, ITEM_SLOTS_COUNT, |reader| {
+            Ok(match reader.read_u16()? {
+                0xffff => None,
+                index => Some(index),
+            })
+        })?;
+        let selected_weapon = reader.read_u16()?;
+        let selected_weapon_ability = reader.read_u16()?;
+
+        Ok(Cre {
+            version,
+            long_name,
+            short_name,
+            flags,
+            xp_value,
+            xp,
+            gold,
+            status_flags,
+            current_hp,
+            max_hp,
+            animation_id,
+            colors,
+            effects_version,
+            small_portrait,
+            large_portrait,
+            reputation,
+            hide_in_shadows,
+            natural_ac,
+            effective_ac,
+            thac0,
+            attacks,
+            saving_throws,
+            sounds,
+            levels,
+            sex,
+            stats,
+            morale,
+            morale_break,
+            racial_enemy,
+            morale_recovery_time,
+            kit,
+            scripts,
+            ids,
+            alignment,
+            death_variable,
+            dialog,
+            known_spells_offset,
+            memorization_info_offset,
+            memorized_spells_offset,
+            item_slots_offset,
+            items_offset,
+            effects_offset,
+            effects_count,
+            known_spells,
+            memorization_info,
+            memorized_spells,
+            items,
+            item_slots,
+            selected_weapon,
+            selected_weapon_ability,
+        })
+    }
+}
+
+/// A CRE file.
+/// Names and soundset entries are StrRefs into the TLK file.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cre {
+    pub version: CreVersion,
+    pub long_name: u32,
+    /// The name displayed in the tooltip
+    pub short_name: u32,
+    pub flags: u32,
+    /// The experience gained for killing the creature
+    pub xp_value: u32,
+    pub xp: u32,
+    pub gold: u32,
+    pub status_flags: u32,
+    pub current_hp: u16,
+    pub max_hp: u16,
+    pub animation_id: u32,
+    /// Metal, minor, major, skin, leather, armor and hair color indexes
+    pub colors: [u8; 7],
+    /// 0 = V1 effects, 1 = V2 effects
+    pub effects_version: u8,
+    pub small_portrait: String,
+    pub large_portrait: String,
+    pub reputation: u8,
+    pub hide_in_shadows: u8,
+    pub natural_ac: i16,
+    pub effective_ac: i16,
+    pub thac0: u8,
+    pub attacks: u8,
+    pub saving_throws: CreSavingThrows,
+    /// The soundset StrRefs, one per sound slot
+    pub sounds: Vec<u32>,
+    /// The level in each of the creature classes
+    pub levels: [u8; 3],
+    pub sex: u8,
+    pub stats: CreStats,
+    pub morale: u8,
+    pub morale_break: u8,
+    pub racial_enemy: u8,
+    pub morale_recovery_time: u16,
+    pub kit: u32,
+    pub scripts: CreScripts,
+    pub ids: CreIds,
+    pub alignment: u8,
+    pub death_variable: String,
+    pub dialog: String,
+    pub known_spells_offset: u64,
+    pub memorization_info_offset: u64,
+    pub memorized_spells_offset: u64,
+    pub item_slots_offset: u64,
+    pub items_offset: u64,
+    pub effects_offset: u64,
+    pub effects_count: u32,
+    pub known_spells: Vec<CreKnownSpell>,
+    pub memorization_info: Vec<CreMemorizationInfo>,
+    pub memorized_spells: Vec<CreMemorizedSpell>,
+    pub items: Vec<CreItem>,
+    /// The inventory slots, each one referencing an entry of `items`
+    pub item_slots: Vec<Option<u16>>,
+    pub selected_weapon: u16,
+    pub selected_weapon_ability: u16,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum CreVersion {
+    /// BG, BG2, IWD
+    V1_0,
+    /// PST
+    V1_2,
+    /// IWD2
+    V2_2,
+    /// IWD
+    V9_0,
+}
+
+impl CreVersion {
+    fn parse(version: &str) -> std::io::Result<CreVersion> {
+        match version {
+            "V1.0" => Ok(CreVersion::V1_0),
+            "V1.2" => Ok(CreVersion::V1_2),
+            "V2.2" => Ok(CreVersion::V2_2),
+            "V9.0" => Ok(CreVersion::V9_0),
+            version => Err(std::io::Error::other(format!(
+                "Unknown CRE version: {}",
+                version
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CreSavingThrows {
+    pub death: u8,
+    pub wands: u8,
+    pub polymorph: u8,
+    pub breath: u8,
+    pub spells: u8,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CreStats {
+    pub strength: u8,
+    /// The exceptional strength percentage
+    pub strength_bonus: u8,
+    pub intelligence: u8,
+    pub wisdom: u8,
+    pub dexterity: u8,
+    pub constitution: u8,
+    pub charisma: u8,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CreScripts {
+    pub r#override: String,
+    pub class: String,
+    pub race: String,
+    pub general: String,
+    pub default: String,
+}
+
+/// The values of the creature in the EA, GENERAL, RACE, CLASS, SPECIFIC and GENDER IDS files
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CreIds {
+    pub enemy_ally: u8,
+    pub general: u8,
+    pub race: u8,
+    pub class: u8,
+    pub specific: u8,
+    pub gender: u8,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CreKnownSpell {
+    pub resref: String,
+    /// The spell level, starting from 0
+    pub level: u16,
+    /// 0 = priest, 1 = wizard, 2 = innate
+    pub spell_type: u16,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CreMemorizationInfo {
+    pub level: u16,
+    pub memorizable_count: u16,
+    pub memorizable_count_with_effects: u16,
+    pub spell_type: u16,
+    /// Index into `Cre::memorized_spells` of the first spell of this level
+    pub memorized_spells_index: u32,
+    pub memorized_spells_count: u32,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CreMemorizedSpell {
+    pub resref: String,
+    /// Whether the spell is still available for casting
+    pub memorized: bool,
+}
+
+impl Cre {
+    /// Returns the item stored in a slot
+    pub fn item_in(&self, slot: CreItemSlot) -> Option<&CreItem> {
+        let index = (*self.item_slots.get(slot.to_index())?)?;
+        self.items.get(index as usize)
+    }
+
+    /// Returns the occupied slots with their items, in slot order
+    pub fn slot_items(&self) -> Vec<(CreItemSlot, &CreItem)> {
+        self.item_slots
+            .iter()
+            .enumerate()
+            .filter_map(|(index, item_index)| {
+                let slot = CreItemSlot::from_index(index)?;
+                Some((slot, self.items.get((*item_index)? as usize)?))
+            })
+            .collect()
+    }
+
+    /// Returns the spells memorized in a level of the memorization table
+    pub fn memorized_spells_of(&self, info: &CreMemorizationInfo) -> &[CreMemorizedSpell] {
+        let start = info.memorized_spells_index as usize;
+        let end = start + info.memorized_spells_count as usize;
+        self.memorized_spells.get(start..end).unwrap_or_default()
+    }
+
+    /// Returns the spellbook, one entry per level of the memorization table
+    /// with the spells known and memorized at that level
+    pub fn spellbook(&self) -> Vec<CreSpellbookLevel<'_>> {
+        self.memorization_info
+            .iter()
+            .map(|info| CreSpellbookLevel {
+                spell_type: CreSpellType::from_u16(info.spell_type),
+                level: info.level,
+                memorizable_count: info.memorizable_count,
+                known: self
+                    .known_spells
+                    .iter()
+                    .filter(|spell| {
+                        spell.level == info.level && spell.spell_type == info.spell_type
+                    })
+                    .map(|spell| spell.resref.as_str())
+                    .collect(),
+                memorized: self.memorized_spells_of(info),
+            })
+            .collect()
+    }
+}
+
+/// A level of a CRE spellbook
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct CreSpellbookLevel<'a> {
+    pub spell_type: CreSpellType,
+    /// The spell level, starting from 0
+    pub level: u16,
+    pub memorizable_count: u16,
+    /// The resrefs of the known spells
+    pub known: Vec<&'a str>,
+    pub memorized: &'a [CreMemorizedSpell],
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum CreSpellType {
+    Priest,
+    Wizard,
+    Innate,
+    Unknown(u16),
+}
+
+impl CreSpellType {
+    pub fn from_u16(value: u16) -> CreSpellType {
+        match value {
+            0 => CreSpellType::Priest,
+            1 => CreSpellType::Wizard,
+            2 => CreSpellType::Innate,
+            value => CreSpellType::Unknown(value),
+        }
+    }
+}
+
+/// An inventory slot of a V1.0 CRE file
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum CreItemSlot {
+    Helmet,
+    Armor,
+    Shield,
+    Gloves,
+    LeftRing,
+    RightRing,
+    Amulet,
+    Belt,
+    Boots,
+    /// One of the 4 weapon slots, starting from 0
+    Weapon(u8),
+    /// One of the 4 quiver slots, starting from 0
+    Quiver(u8),
+    Cloak,
+    /// One of the 3 quick item slots, starting from 0
+    QuickItem(u8),
+    /// One of the 16 backpack slots, starting from 0
+    Inventory(u8),
+    /// The weapon created by a spell, e.g. a Flame Blade
+    MagicWeapon,
+}
+
+impl CreItemSlot {
+    /// Returns the slot at the given index of the slots table, `None` if the index is out of the table
+    pub fn from_index(index: usize) -> Option<CreItemSlot> {
+        Some(match index {
+            0 => CreItemSlot::Helmet,
+            1 => CreItemSlot::Armor,
+            2 => CreItemSlot::Shield,
+            3 => CreItemSlot::Gloves,
+            4 => CreItemSlot::LeftRing,
+            5 => CreItemSlot::RightRing,
+            6 => CreItemSlot::Amulet,
+            7 => CreItemSlot::Belt,
+            8 => CreItemSlot::Boots,
+            9..=12 => CreItemSlot::Weapon((index - 9) as u8),
+            13..=16 => CreItemSlot::Quiver((index - 13) as u8),
+            17 => CreItemSlot::Cloak,
+            18..=20 => CreItemSlot::QuickItem((index - 18) as u8),
+            21..=36 => CreItemSlot::Inventory((index - 21) as u8),
+            37 => CreItemSlot::MagicWeapon,
+            _ => return None,
+        })
+    }
+
+    /// Returns the index of the slot in the slots table
+    pub fn to_index(&self) -> usize {
+        match self {
+            CreItemSlot::Helmet => 0,
+            CreItemSlot::Armor => 1,
+            CreItemSlot::Shield => 2,
+            CreItemSlot::Gloves => 3,
+            CreItemSlot::LeftRing => 4,
+            CreItemSlot::RightRing => 5,
+            CreItemSlot::Amulet => 6,
+            CreItemSlot::Belt => 7,
+            CreItemSlot::Boots => 8,
+            CreItemSlot::Weapon(index) => 9 + *index as usize,
+            CreItemSlot::Quiver(index) => 13 + *index as usize,
+            CreItemSlot::Cloak => 17,
+            CreItemSlot::QuickItem(index) => 18 + *index as usize,
+            CreItemSlot::Inventory(index) => 21 + *index as usize,
+            CreItemSlot::MagicWeapon => 37,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CreItem {
+    pub resref: String,
+    pub expiration_time: u16,
+    /// The charges of each of the item abilities
+    pub charges: [u16; 3],
+    pub flags: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::RESOURCES_DIR;
+    use insta::assert_json_snapshot;
+    use std::path::Path;
+
+    #[test]
+    fn test_parse_cre() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/CRE/MINSC6.CRE"
+        )));
+
+        let cre = CreImporter::import(&data).unwrap();
+
+        assert_eq!(cre.version, CreVersion::V1_0);
+        assert_eq!(cre.long_name, 3001);
+        assert_eq!(cre.max_hp, 48);
+        assert_eq!(cre.stats.strength, 18);
+        assert_eq!(cre.stats.strength_bonus, 93);
+        assert_eq!(cre.sounds.len(), 100);
+
+        assert_eq!(cre.items.len(), 3);
+        assert_eq!(cre.item_slots.iter().flatten().count(), 3);
+        assert_eq!(cre.known_spells.len(), 2);
+        assert_eq!(cre.memorized_spells.len(), 2);
+
+        assert_json_snapshot!(cre);
+    }
+
+    #[test]
+    fn test_cre_item_slots() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/CRE/MINSC6.CRE"
+        )));
+        let cre = CreImporter::import(&data).unwrap();
+
+        assert_eq!(
+            cre.item_in(CreItemSlot::Weapon(0))
+                .map(|item| item.resref.as_str()),
+            Some("SW1H99")
+        );
+        assert_eq!(cre.item_in(CreItemSlot::Helmet), None);
+
+        let slot_items = cre.slot_items();
+        assert_eq!(slot_items.len(), 3);
+        assert_json_snapshot!(slot_items);
+    }
+
+    #[test]
+    fn test_cre_spellbook() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/CRE/MINSC6.CRE"
+        )));
+        let cre = CreImporter::import(&data).unwrap();
+
+        let spellbook = cre.spellbook();
+
+        assert_eq!(spellbook.len(), 2);
+        assert_eq!(spellbook[0].spell_type, CreSpellType::Wizard);
+        assert_eq!(spellbook[0].known, vec!["SPWI112"]);
+        assert_eq!(spellbook[0].memorizable_count, 1);
+        assert_eq!(spellbook[0].memorized.len(), 1);
+        assert_eq!(spellbook[1].spell_type, CreSpellType::Innate);
+        assert_eq!(spellbook[1].known, vec!["SPCL311"]);
+        assert_eq!(spellbook[1].memorized[0].resref, "SPCL311");
+
+        assert_json_snapshot!(spellbook);
+    }
+
+    #[test]
+    fn test_cre_item_slot_index_roundtrip() {
+        for index in 0..ITEM_SLOTS_COUNT {
+            assert_eq!(CreItemSlot::from_index(index).unwrap().to_index(), index);
+        }
+        assert_eq!(CreItemSlot::from_index(ITEM_SLOTS_COUNT), None);
+    }
+}
@@ -0,0 +1,396 @@
+use std::{
+    fmt::Write,
+    io::{Read, Seek},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    datasource::{DataSource, Importer, Reader},
+    strings::{Gender, StringResolver},
+};
+
+/// The value used by states and transitions that do not reference any entry
+const NONE: u32 = 0xffffffff;
+
+/// A DLG file importer
+pub struct DlgImporter;
+
+impl Importer for DlgImporter {
+    type T = Dlg;
+
+    fn import(source: &DataSource) -> std::io::Result<Dlg> {
+        let mut reader = source.reader()?;
+
+        let signature = reader.read_string(8)?;
+        if signature != "DLG V1.0" {
+            return Err(std::io::Error::other(format!(
+                "Wrong file type: {}",
+                signature
+            )));
+        }
+
+        let states_count = reader.read_u32()? as usize;
+        let states_offset = reader.read_u32()? as u64;
+        let transitions_count = reader.read_u32()? as usize;
+        let transitions_offset = reader.read_u32()? as u64;
+        let state_triggers_offset = reader.read_u32()? as u64;
+        let state_triggers_count = reader.read_u32()? as usize;
+        let transition_triggers_offset = reader.read_u32()? as u64;
+        let transition_triggers_count = reader.read_u32()? as usize;
+        let actions_offset = reader.read_u32()? as u64;
+        let actions_count = reader.read_u32()? as usize;
+        // The flags were added by BG2, older files have the states table right after the counts
+        let flags = if states_offset >= 0x34 {
+            reader.read_u32()?
+        } else {
+            0
+        };
+
+        // Read the states
+        let states = reader.read_table(states_offset, states_count, |reader| {
+            Ok(DlgState {
+                text: reader.read_u32()?,
+                transition_index: reader.read_u32()?,
+                transition_count: reader.read_u32()?,
+                trigger_index: match reader.read_u32()? {
+                    NONE => None,
+                    index => Some(index),
+                },
+            })
+        })?;
+
+        // Read the transitions
+        let transitions = reader.read_table(transitions_offset, transitions_count, |reader| {
+            let flags = DlgTransitionFlag::from_bits_retain(reader.read_u32()?);
+            let text = reader.read_u32()?;
+            let journal_text = reader.read_u32()?;
+            let trigger_index = reader.read_u32()?;
+            let action_index = reader.read_u32()?;
+            let next_dialog = reader.read_string(8)?;
+            let next_state = reader.read_u32()?;
+
+            let terminates = flags.contains(DlgTransitionFlag::Terminates);
+            Ok(DlgTransition {
+                text: flags.contains(DlgTransitionFlag::HasText).then_some(text),
+                journal_text: flags
+                    .contains(DlgTransitionFlag::HasJournal)
+                    .then_some(journal_text),
+                trigger_index: flags
+                    .contains(DlgTransitionFlag::HasTrigger)
+                    .then_some(trigger_index),
+                action_index: flags
+                    .contains(DlgTransitionFlag::HasAction)
+                    .then_some(action_index),
+                next_dialog: (!terminates).then_some(next_dialog),
+                next_state: (!terminates).then_some(next_state),
+                flags,
+            })
+        })?;
+
+        let state_triggers =
+            read_strings(&mut reader, state_triggers_offset, state_triggers_count)?;
+        let transition_triggers = read_strings(
+            &mut reader,
+            transition_triggers_offset,
+            transition_triggers_count,
+        )?;
+        let actions = read_strings(&mut reader, actions_offset, actions_count)?;
+
+        Ok(Dlg {
+            flags,
+            states,
+            transitions,
+            state_triggers,
+            transition_triggers,
+            actions,
+        })
+    }
+}
+
+/// Reads a table of (offset, length) entries and the strings they point to
+fn read_strings<R: Read + Seek>(
+    reader: &mut Reader<R>,
+    offset: u64,
+    count: usize,
+) -> std::io::Result<Vec<String>> {
+    let entries = reader.read_table(offset, count, |reader| {
+        Ok((reader.read_u32()? as u64, reader.read_u32()? as u64))
+    })?;
+
+    entries
+        .into_iter()
+        .map(|(offset, length)| reader.read_string_at(offset, length))
+        .collect()
+}
+
+impl Dlg {
+    /// Returns the transitions, i.e. the player replies, of a state.
+    /// Returns `None` if the state references transitions past the end of the transitions table.
+    pub fn state_transitions(&self, state: &DlgState) -> Option<&[DlgTransition]> {
+        let start = state.transition_index as usize;
+        self.transitions
+            .get(start..start.checked_add(state.transition_count as usize)?)
+    }
+
+    /// Returns the trigger that must be true for a state to be selected as the dialog start
+    pub fn state_trigger(&self, state: &DlgState) -> Option<&str> {
+        state
+            .trigger_index
+            .and_then(|index| self.state_triggers.get(index as usize))
+            .map(String::as_str)
+    }
+
+    /// Returns the trigger that must be true for a transition to be available
+    pub fn transition_trigger(&self, transition: &DlgTransition) -> Option<&str> {
+        transition
+            .trigger_index
+            .and_then(|index| self.transition_triggers.get(index as usize))
+            .map(String::as_str)
+    }
+
+    /// Returns the actions executed when a transition is taken
+    pub fn transition_action(&self, transition: &DlgTransition) -> Option<&str> {
+        transition
+            .action_index
+            .and_then(|index| self.actions.get(index as usize))
+            .map(String::as_str)
+    }
+
+    /// Exports the conversation as a Graphviz DOT graph.
+    ///
+    /// The states are the nodes, labeled by their text, and the transitions the edges, labeled by the player reply.
+    /// `name` is the resref of this dialog, used to tell the jumps to other dialogs apart:
+    /// they are drawn as dashed edges to placeholder nodes. The transitions ending the conversation lead to an `end` node.
+    /// Without a resolver, or if a StrRef is not found, the texts are replaced by their StrRef.
+    /// The transitions of a state referencing entries past the end of the transitions table are left out.
+    pub fn to_dot(&self, name: &str, resolver: Option<&StringResolver>) -> String {
+        let text = |strref: u32| {
+            resolver
+                .and_then(|resolver| resolver.resolve(strref, Gender::Male))
+                .unwrap_or_else(|| format!("StrRef {strref}"))
+        };
+
+        let mut dot = format!("digraph {} {{\n", dot_string(name));
+        for (index, state) in self.states.iter().enumerate() {
+            let label = format!("{index}: {}", text(state.text));
+            let _ = writeln!(dot, "    s{index} [label={}];", dot_string(&label));
+        }
+
+        let mut external_nodes = Vec::new();
+        let mut ends = false;
+        for (index, state) in self.states.iter().enumerate() {
+            for transition in self.state_transitions(state).unwrap_or_default() {
+                let label = dot_string(&transition.text.map(text).unwrap_or_default());
+                match (&transition.next_dialog, transition.next_state) {
+                    (Some(dialog), Some(next_state)) if dialog.eq_ignore_ascii_case(name) => {
+                        let _ = writeln!(dot, "    s{index} -> s{next_state} [label={label}];");
+                    }
+                    (Some(dialog), Some(next_state)) => {
+                        let node = format!("{}:{next_state}", dialog.to_uppercase());
+                        let _ = writeln!(
+                            dot,
+                            "    s{index} -> {} [label={label}, style=dashed];",
+                            dot_string(&node)
+                        );
+                        if !external_nodes.contains(&node) {
+                            external_nodes.push(node);
+                        }
+                    }
+                    _ => {
+                        let _ = writeln!(dot, "    s{index} -> end [label={label}];");
+                        ends = true;
+                    }
+                }
+            }
+        }
+
+        for node in &external_nodes {
+            let _ = writeln!(dot, "    {} [shape=box, style=dashed];", dot_string(node));
+        }
+        if ends {
+            dot.push_str("    end [shape=doublecircle];\n");
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Quotes a string as a DOT identifier
+fn dot_string(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n");
+    format!("\"{escaped}\"")
+}
+
+/// A DLG file.
+/// The conversation is a graph where each state is an NPC line and each transition a player reply
+/// leading to another state, possibly of another dialog.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Dlg {
+    /// How the dialog reacts when the speaker is attacked (BG2 only)
+    pub flags: u32,
+    pub states: Vec<DlgState>,
+    pub transitions: Vec<DlgTransition>,
+    /// The raw source of the state triggers
+    pub state_triggers: Vec<String>,
+    /// The raw source of the transition triggers
+    pub transition_triggers: Vec<String>,
+    /// The raw source of the transition actions
+    pub actions: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DlgState {
+    /// The StrRef of the NPC line
+    pub text: u32,
+    /// Index into `Dlg::transitions` of the first transition of this state
+    pub transition_index: u32,
+    pub transition_count: u32,
+    /// Index into `Dlg::state_triggers`
+    pub trigger_index: Option<u32>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DlgTransition {
+    pub flags: DlgTransitionFlag,
+    /// The StrRef of the player reply
+    pub text: Option<u32>,
+    pub journal_text: Option<u32>,
+    /// Index into `Dlg::transition_triggers`
+    pub trigger_index: Option<u32>,
+    /// Index into `Dlg::actions`
+    pub action_index: Option<u32>,
+    /// The dialog of the next state, `None` if the transition ends the conversation
+    pub next_dialog: Option<String>,
+    pub next_state: Option<u32>,
+}
+
+bitflags::bitflags! {
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    pub struct DlgTransitionFlag: u32 {
+        const HasText = 1 << 0;
+        const HasTrigger = 1 << 1;
+        const HasAction = 1 << 2;
+        const Terminates = 1 << 3;
+        const HasJournal = 1 << 4;
+        const Interrupt = 1 << 5;
+        const AddUnsolvedQuest = 1 << 6;
+        const AddJournalNote = 1 << 7;
+        const AddSolvedQuest = 1 << 8;
+        const ImmediateExecution = 1 << 9;
+        const ClearActions = 1 << 10;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{resource::tlk::TlkImporter, test_utils::RESOURCES_DIR};
+    use insta::assert_json_snapshot;
+    use std::path::Path;
+
+    #[test]
+    fn test_parse_dlg() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/DLG/MINSCD.DLG"
+        )));
+
+        let dlg = DlgImporter::import(&data).unwrap();
+
+        assert_eq!(dlg.states.len(), 3);
+        assert_eq!(dlg.transitions.len(), 4);
+
+        let start = &dlg.states[0];
+        assert_eq!(dlg.state_trigger(start), Some("NumTimesTalkedTo(0)\r\n"));
+
+        let replies = dlg.state_transitions(start).unwrap();
+        assert_eq!(replies.len(), 2);
+        assert_eq!(
+            dlg.transition_trigger(&replies[0]),
+            Some("PartyHasItem(\"MISC6Y\")\r\n")
+        );
+        assert_eq!(replies[0].next_state, Some(1));
+        assert_eq!(replies[1].next_state, None);
+        assert!(dlg.transition_action(&replies[1]).is_some());
+
+        // Walk the conversation down to its end
+        let next = &dlg.states[replies[0].next_state.unwrap() as usize];
+        let next =
+            &dlg.states[dlg.state_transitions(next).unwrap()[0].next_state.unwrap() as usize];
+        let last = &dlg.state_transitions(next).unwrap()[0];
+        assert_eq!(last.journal_text, Some(5201));
+        assert_eq!(dlg.transition_action(last), Some("GiveGoldForce(100)\r\n"));
+
+        assert_json_snapshot!(dlg);
+    }
+
+    #[test]
+    fn test_export_dlg_to_dot() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/DLG/MINSCD.DLG"
+        )));
+        let mut dlg = DlgImporter::import(&data).unwrap();
+        // The first state is in the talk table, the second one continues in another dialog
+        dlg.states[0].text = 1;
+        dlg.transitions[2].next_dialog = Some("JAHEIRAJ".to_string());
+        dlg.transitions[2].next_state = Some(5);
+
+        let tlk = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/TLK/dialog.tlk"
+        )));
+        let resolver = StringResolver::new(TlkImporter::import(&tlk).unwrap());
+        let dot = dlg.to_dot("minscd", Some(&resolver));
+
+        assert!(dot.starts_with("digraph \"minscd\" {\n"));
+        assert!(dot.contains("    s0 [label=\"0: Hello, sir.\"];\n"));
+        assert!(dot.contains("    s1 [label=\"1: StrRef 5002\"];\n"));
+        assert_eq!(dot.matches(" [label=\"").count(), 3 + 4);
+        assert_eq!(dot.matches(" -> ").count(), 4);
+        assert_eq!(dot.matches("style=dashed").count(), 2);
+        assert!(dot.contains("    s1 -> \"JAHEIRAJ:5\" ["));
+        assert!(dot.contains("    \"JAHEIRAJ:5\" [shape=box, style=dashed];\n"));
+        assert_eq!(dot.matches(" -> end ").count(), 2);
+        assert!(dot.ends_with("    end [shape=doublecircle];\n}\n"));
+    }
+
+    #[test]
+    fn test_import_dlg_with_huge_counts() {
+        let mut bytes = std::fs::read(format!("{RESOURCES_DIR}/resources/DLG/MINSCD.DLG")).unwrap();
+        // The states count is the first field after the signature
+        bytes[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(DlgImporter::import(&DataSource::new(bytes)).is_err());
+
+        // A header alone, with huge counts and offsets
+        let mut bytes = b"DLG V1.0".to_vec();
+        for _ in 0..5 {
+            bytes.extend(u32::MAX.to_le_bytes());
+            bytes.extend(0x34u32.to_le_bytes());
+        }
+        bytes.extend(0u32.to_le_bytes());
+        assert!(DlgImporter::import(&DataSource::new(bytes)).is_err());
+    }
+
+    #[test]
+    fn test_invalid_state_transitions() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/DLG/MINSCD.DLG"
+        )));
+        let mut dlg = DlgImporter::import(&data).unwrap();
+        // The second state points past the end of the transitions table
+        dlg.states[1].transition_index = 3;
+        dlg.states[1].transition_count = 2;
+        dlg.states[2].transition_index = u32::MAX;
+
+        assert!(dlg.state_transitions(&dlg.states[0]).is_some());
+        assert!(dlg.state_transitions(&dlg.states[1]).is_none());
+        assert!(dlg.state_transitions(&dlg.states[2]).is_none());
+
+        let dot = dlg.to_dot("minscd", None);
+        assert_eq!(dot.matches(" -> ").count(), 2);
+        assert!(!dot.contains("    s1 -> "));
+    }
+}
@@ -0,0 +1,106 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    datasource::{DataSource, Importer},
+    resource::effect::{EffectV1, EffectV2},
+};
+
+/// An EFF file importer
+pub struct EffImporter;
+
+impl Importer for EffImporter {
+    type T = Eff;
+
+    /// Imports an EFF file of any version, detected from its signature.
+    /// A V1 effect is widened to the V2 layout, see `From<&EffectV1> for EffectV2`.
+    fn import(source: &DataSource) -> std::io::Result<Eff> {
+        let mut reader = source.reader()?;
+
+        let signature = reader.read_string(8)?;
+        match signature.as_str() {
+            "EFF V1.0" => Ok(Eff {
+                version: EffVersion::V1,
+                effect: (&EffectV1::read_entry(&mut reader)?).into(),
+            }),
+            // The V2 header is followed by the effect, which starts with the same signature
+            "EFF V2.0" => Ok(Eff {
+                version: EffVersion::V2,
+                effect: EffectV2::read_entry(&mut reader)?,
+            }),
+            signature => Err(std::io::Error::other(format!(
+                "Unsupported EFF file: {}",
+                signature
+            ))),
+        }
+    }
+}
+
+/// An EFF file, holding a single standalone effect
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Eff {
+    /// The version of the file
+    pub version: EffVersion,
+    /// The effect, in the V2 layout whatever the version of the file
+    pub effect: EffectV2,
+}
+
+/// The version of an EFF file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EffVersion {
+    V1,
+    V2,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{resource::effect::EffectOpcode, test_utils::RESOURCES_DIR};
+    use insta::assert_json_snapshot;
+    use std::path::Path;
+
+    #[test]
+    fn test_parse_eff_v2() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/EFF/SPWI112D.EFF"
+        )));
+
+        let eff = EffImporter::import(&data).unwrap();
+
+        assert_eq!(eff.version, EffVersion::V2);
+        let effect = &eff.effect;
+        assert_eq!(effect.opcode, EffectOpcode::Damage);
+        assert_eq!(effect.dice_thrown, 1);
+        assert_eq!(effect.dice_sides, 4);
+        assert_eq!(effect.parent_resource, "SPWI112");
+
+        assert_json_snapshot!(eff);
+    }
+
+    #[test]
+    fn test_parse_eff_v1() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/EFF/SOUND1.EFF"
+        )));
+
+        let eff = EffImporter::import(&data).unwrap();
+
+        assert_eq!(eff.version, EffVersion::V1);
+        let effect = &eff.effect;
+        assert_eq!(effect.opcode, EffectOpcode::PlaySound);
+        assert_eq!(effect.resource, "EFF_M01");
+        // The fields missing from the V1 layout get their default values
+        assert_eq!(effect.parent_resource_slot, -1);
+        assert_eq!(effect.caster_x, -1);
+        assert_eq!(effect.parent_resource, "");
+
+        assert_json_snapshot!(eff);
+    }
+
+    #[test]
+    fn test_effect_opcode() {
+        assert_eq!(EffectOpcode::from(12), EffectOpcode::Damage);
+        assert_eq!(EffectOpcode::from(999), EffectOpcode::Unknown(999));
+        assert_eq!(EffectOpcode::Damage.to_u16(), 12);
+        assert_eq!(EffectOpcode::Unknown(999).to_u16(), 999);
+    }
+}
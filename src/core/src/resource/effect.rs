@@ -0,0 +1,493 @@
+use std::io::Read;
+
+use serde::{Deserialize, Serialize};
+
+use crate::datasource::Reader;
+
+/// A V1 effect, also known as feature block.
+/// It is embedded in ITM and SPL files.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EffectV1 {
+    pub opcode: EffectOpcode,
+    pub target: u8,
+    pub power: u8,
+    pub parameter1: u32,
+    pub parameter2: u32,
+    pub timing_mode: u8,
+    pub dispel_resistance: u8,
+    pub duration: u32,
+    /// Upper bound of the probability roll
+    pub probability1: u8,
+    /// Lower bound of the probability roll
+    pub probability2: u8,
+    pub resource: String,
+    pub dice_thrown: u32,
+    pub dice_sides: u32,
+    pub saving_throw_type: u32,
+    pub saving_throw_bonus: i32,
+    pub special: u32,
+}
+
+impl EffectV1 {
+    /// The size in bytes of an effect entry
+    pub const SIZE: u64 = 0x30;
+
+    /// Reads a V1 effect from the current position
+    pub(crate) fn read_entry<R: Read>(reader: &mut Reader<R>) -> std::io::Result<EffectV1> {
+        Ok(EffectV1 {
+            opcode: EffectOpcode::from(reader.read_u16()?),
+            target: reader.read_u8()?,
+            power: reader.read_u8()?,
+            parameter1: reader.read_u32()?,
+            parameter2: reader.read_u32()?,
+            timing_mode: reader.read_u8()?,
+            dispel_resistance: reader.read_u8()?,
+            duration: reader.read_u32()?,
+            probability1: reader.read_u8()?,
+            probability2: reader.read_u8()?,
+            resource: reader.read_string(8)?,
+            dice_thrown: reader.read_u32()?,
+            dice_sides: reader.read_u32()?,
+            saving_throw_type: reader.read_u32()?,
+            saving_throw_bonus: reader.read_i32()?,
+            special: reader.read_u32()?,
+        })
+    }
+}
+
+/// A V2 effect.
+/// It is embedded in CRE files and stored as a standalone EFF file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EffectV2 {
+    pub opcode: EffectOpcode,
+    pub target: u32,
+    pub power: u32,
+    pub parameter1: u32,
+    pub parameter2: u32,
+    pub timing_mode: u16,
+    pub duration: u32,
+    /// Upper bound of the probability roll
+    pub probability1: u16,
+    /// Lower bound of the probability roll
+    pub probability2: u16,
+    pub resource: String,
+    pub dice_thrown: u32,
+    pub dice_sides: u32,
+    pub saving_throw_type: u32,
+    pub saving_throw_bonus: i32,
+    pub special: u32,
+    pub primary_type: u32,
+    pub min_level: u32,
+    pub max_level: u32,
+    pub dispel_resistance: u32,
+    pub parameter3: u32,
+    pub parameter4: u32,
+    pub parameter5: u32,
+    pub time_applied: u32,
+    pub resource2: String,
+    pub resource3: String,
+    pub caster_x: i32,
+    pub caster_y: i32,
+    pub target_x: i32,
+    pub target_y: i32,
+    pub parent_resource_type: u32,
+    pub parent_resource: String,
+    pub parent_resource_flags: u32,
+    pub projectile: u32,
+    pub parent_resource_slot: i32,
+    pub variable_name: String,
+    pub caster_level: u32,
+    pub first_apply: u32,
+    pub secondary_type: u32,
+}
+
+impl EffectV2 {
+    /// The size in bytes of an effect entry, including its `EFF V2.0` signature
+    pub const SIZE: u64 = 0x108;
+
+    /// Reads a V2 effect, starting from its signature, from the current position
+    pub(crate) fn read_entry<R: Read>(reader: &mut Reader<R>) -> std::io::Result<EffectV2> {
+        let signature = reader.read_string(8)?;
+        if signature != "EFF V2.0" {
+            return Err(std::io::Error::other(format!(
+                "Wrong effect signature: {}",
+                signature
+            )));
+        }
+
+        let opcode = EffectOpcode::from(reader.read_u32()? as u16);
+        let target = reader.read_u32()?;
+        let power = reader.read_u32()?;
+        let parameter1 = reader.read_u32()?;
+        let parameter2 = reader.read_u32()?;
+        let timing_mode = reader.read_u16()?;
+        reader.skip(2)?; // unknown
+        let duration = reader.read_u32()?;
+        let probability1 = reader.read_u16()?;
+        let probability2 = reader.read_u16()?;
+        let resource = reader.read_string(8)?;
+        let dice_thrown = reader.read_u32()?;
+        let dice_sides = reader.read_u32()?;
+        let saving_throw_type = reader.read_u32()?;
+        let saving_throw_bonus = reader.read_i32()?;
+        let special = reader.read_u32()?;
+        let primary_type = reader.read_u32()?;
+        reader.skip(4)?; // unknown
+        let min_level = reader.read_u32()?;
+        let max_level = reader.read_u32()?;
+        let dispel_resistance = reader.read_u32()?;
+        let parameter3 = reader.read_u32()?;
+        let parameter4 = reader.read_u32()?;
+        let parameter5 = reader.read_u32()?;
+        let time_applied = reader.read_u32()?;
+        let resource2 = reader.read_string(8)?;
+        let resource3 = reader.read_string(8)?;
+        let caster_x = reader.read_i32()?;
+        let caster_y = reader.read_i32()?;
+        let target_x = reader.read_i32()?;
+        let target_y = reader.read_i32()?;
+        let parent_resource_type = reader.read_u32()?;
+        let parent_resource = reader.read_string(8)?;
+        let parent_resource_flags = reader.read_u32()?;
+        let projectile = reader.read_u32()?;
+        let parent_resource_slot = reader.read_i32()?;
+        let variable_name = reader.read_string(32)?;
+        let caster_level = reader.read_u32()?;
+        let first_apply = reader.read_u32()?;
+        let secondary_type = reader.read_u32()?;
+        reader.skip(60)?; // unused
+
+        Ok(EffectV2 {
+            opcode,
+            target,
+            power,
+            parameter1,
+            parameter2,
+            timing_mode,
+            duration,
+            probability1,
+            probability2,
+            resource,
+            dice_thrown,
+            dice_sides,
+            saving_throw_type,
+            saving_throw_bonus,
+            special,
+            primary_type,
+            min_level,
+            max_level,
+            dispel_resistance,
+            parameter3,
+            parameter4,
+            parameter5,
+            time_applied,
+            resource2,
+            resource3,
+            caster_x,
+            caster_y,
+            target_x,
+            target_y,
+            parent_resource_type,
+            parent_resource,
+            parent_resource_flags,
+            projectile,
+            parent_resource_slot,
+            variable_name,
+            caster_level,
+            first_apply,
+            secondary_type,
+        })
+    }
+}
+
+impl From<&EffectV1> for EffectV2 {
+    /// Widens a V1 effect, e.g. an ITM or SPL feature block, to the V2 layout.
+    ///
+    /// The fields shared by the two layouts keep their value, the byte sized `target`, `power`,
+    /// `timing_mode`, `dispel_resistance` and probabilities are widened. The fields missing from
+    /// the V1 layout are left empty, except the coordinates and the parent resource slot which
+    /// are set to -1, the value the engine uses when they are not known.
+    fn from(effect: &EffectV1) -> Self {
+        EffectV2 {
+            opcode: effect.opcode,
+            target: effect.target as u32,
+            power: effect.power as u32,
+            parameter1: effect.parameter1,
+            parameter2: effect.parameter2,
+            timing_mode: effect.timing_mode as u16,
+            duration: effect.duration,
+            probability1: effect.probability1 as u16,
+            probability2: effect.probability2 as u16,
+            resource: effect.resource.clone(),
+            dice_thrown: effect.dice_thrown,
+            dice_sides: effect.dice_sides,
+            saving_throw_type: effect.saving_throw_type,
+            saving_throw_bonus: effect.saving_throw_bonus,
+            special: effect.special,
+            primary_type: 0,
+            min_level: 0,
+            max_level: 0,
+            dispel_resistance: effect.dispel_resistance as u32,
+            parameter3: 0,
+            parameter4: 0,
+            parameter5: 0,
+            time_applied: 0,
+            resource2: String::new(),
+            resource3: String::new(),
+            caster_x: -1,
+            caster_y: -1,
+            target_x: -1,
+            target_y: -1,
+            parent_resource_type: 0,
+            parent_resource: String::new(),
+            parent_resource_flags: 0,
+            projectile: 0,
+            parent_resource_slot: -1,
+            variable_name: String::new(),
+            caster_level: 0,
+            first_apply: 0,
+            secondary_type: 0,
+        }
+    }
+}
+
+/// The opcode of an effect, which determines what the effect does
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EffectOpcode {
+    AcBonus,
+    AttacksPerRound,
+    CureSleep,
+    Berserk,
+    CureBerserk,
+    CharmCreature,
+    CharismaBonus,
+    SetColor,
+    SetColorGlowSolid,
+    SetColorGlowPulse,
+    ConstitutionBonus,
+    CurePoison,
+    Damage,
+    Kill,
+    Defrost,
+    DexterityBonus,
+    Haste,
+    CurrentHpBonus,
+    MaximumHpBonus,
+    IntelligenceBonus,
+    Invisibility,
+    LoreBonus,
+    LuckBonus,
+    ResetMorale,
+    Panic,
+    Poison,
+    RemoveCurse,
+    AcidResistanceBonus,
+    ColdResistanceBonus,
+    ElectricityResistanceBonus,
+    FireResistanceBonus,
+    MagicDamageResistanceBonus,
+    RaiseDead,
+    SaveVsDeathBonus,
+    SaveVsWandBonus,
+    SaveVsPolymorphBonus,
+    SaveVsBreathBonus,
+    SaveVsSpellBonus,
+    Silence,
+    Sleep,
+    Slow,
+    Sparkle,
+    BonusWizardSpells,
+    StoneToFlesh,
+    StrengthBonus,
+    Stun,
+    CureStun,
+    RemoveInvisibility,
+    Vocalize,
+    WisdomBonus,
+    Thac0Bonus,
+    Slay,
+    DispelEffects,
+    CastingFailure,
+    Infravision,
+    SummonCreature,
+    AttackDamageBonus,
+    Blindness,
+    Disease,
+    ProtectionFromOpcode,
+    RemoveItem,
+    Confusion,
+    DisplayString,
+    LightingEffects,
+    DisplayPortraitIcon,
+    CastSpell,
+    CastSpellAtPoint,
+    GiveInnateAbility,
+    RemoveSpell,
+    PlaySound,
+    UseEffFile,
+    ProtectionFromSpell,
+    ModifyProficiencies,
+    Unknown(u16),
+}
+
+impl EffectOpcode {
+    /// Returns the `EffectOpcode` enum variant based on the given value.
+    pub fn from(value: u16) -> Self {
+        match value {
+            0 => EffectOpcode::AcBonus,
+            1 => EffectOpcode::AttacksPerRound,
+            2 => EffectOpcode::CureSleep,
+            3 => EffectOpcode::Berserk,
+            4 => EffectOpcode::CureBerserk,
+            5 => EffectOpcode::CharmCreature,
+            6 => EffectOpcode::CharismaBonus,
+            7 => EffectOpcode::SetColor,
+            8 => EffectOpcode::SetColorGlowSolid,
+            9 => EffectOpcode::SetColorGlowPulse,
+            10 => EffectOpcode::ConstitutionBonus,
+            11 => EffectOpcode::CurePoison,
+            12 => EffectOpcode::Damage,
+            13 => EffectOpcode::Kill,
+            14 => EffectOpcode::Defrost,
+            15 => EffectOpcode::DexterityBonus,
+            16 => EffectOpcode::Haste,
+            17 => EffectOpcode::CurrentHpBonus,
+            18 => EffectOpcode::MaximumHpBonus,
+            19 => EffectOpcode::IntelligenceBonus,
+            20 => EffectOpcode::Invisibility,
+            21 => EffectOpcode::LoreBonus,
+            22 => EffectOpcode::LuckBonus,
+            23 => EffectOpcode::ResetMorale,
+            24 => EffectOpcode::Panic,
+            25 => EffectOpcode::Poison,
+            26 => EffectOpcode::RemoveCurse,
+            27 => EffectOpcode::AcidResistanceBonus,
+            28 => EffectOpcode::ColdResistanceBonus,
+            29 => EffectOpcode::ElectricityResistanceBonus,
+            30 => EffectOpcode::FireResistanceBonus,
+            31 => EffectOpcode::MagicDamageResistanceBonus,
+            32 => EffectOpcode::RaiseDead,
+            33 => EffectOpcode::SaveVsDeathBonus,
+            34 => EffectOpcode::SaveVsWandBonus,
+            35 => EffectOpcode::SaveVsPolymorphBonus,
+            36 => EffectOpcode::SaveVsBreathBonus,
+            37 => EffectOpcode::SaveVsSpellBonus,
+            38 => EffectOpcode::Silence,
+            39 => EffectOpcode::Sleep,
+            40 => EffectOpcode::Slow,
+            41 => EffectOpcode::Sparkle,
+            42 => EffectOpcode::BonusWizardSpells,
+            43 => EffectOpcode::StoneToFlesh,
+            44 => EffectOpcode::StrengthBonus,
+            45 => EffectOpcode::Stun,
+            46 => EffectOpcode::CureStun,
+            47 => EffectOpcode::RemoveInvisibility,
+            48 => EffectOpcode::Vocalize,
+            49 => EffectOpcode::WisdomBonus,
+            54 => EffectOpcode::Thac0Bonus,
+            55 => EffectOpcode::Slay,
+            58 => EffectOpcode::DispelEffects,
+            60 => EffectOpcode::CastingFailure,
+            63 => EffectOpcode::Infravision,
+            67 => EffectOpcode::SummonCreature,
+            73 => EffectOpcode::AttackDamageBonus,
+            74 => EffectOpcode::Blindness,
+            78 => EffectOpcode::Disease,
+            101 => EffectOpcode::ProtectionFromOpcode,
+            112 => EffectOpcode::RemoveItem,
+            128 => EffectOpcode::Confusion,
+            139 => EffectOpcode::DisplayString,
+            141 => EffectOpcode::LightingEffects,
+            142 => EffectOpcode::DisplayPortraitIcon,
+            146 => EffectOpcode::CastSpell,
+            148 => EffectOpcode::CastSpellAtPoint,
+            171 => EffectOpcode::GiveInnateAbility,
+            172 => EffectOpcode::RemoveSpell,
+            174 => EffectOpcode::PlaySound,
+            177 => EffectOpcode::UseEffFile,
+            206 => EffectOpcode::ProtectionFromSpell,
+            233 => EffectOpcode::ModifyProficiencies,
+            i => EffectOpcode::Unknown(i),
+        }
+    }
+
+    /// Returns the numeric value of the `EffectOpcode` enum variant.
+    pub fn to_u16(&self) -> u16 {
+        match self {
+            EffectOpcode::AcBonus => 0,
+            EffectOpcode::AttacksPerRound => 1,
+            EffectOpcode::CureSleep => 2,
+            EffectOpcode::Berserk => 3,
+            EffectOpcode::CureBerserk => 4,
+            EffectOpcode::CharmCreature => 5,
+            EffectOpcode::CharismaBonus => 6,
+            EffectOpcode::SetColor => 7,
+            EffectOpcode::SetColorGlowSolid => 8,
+            EffectOpcode::SetColorGlowPulse => 9,
+            EffectOpcode::ConstitutionBonus => 10,
+            EffectOpcode::CurePoison => 11,
+            EffectOpcode::Damage => 12,
+            EffectOpcode::Kill => 13,
+            EffectOpcode::Defrost => 14,
+            EffectOpcode::DexterityBonus => 15,
+            EffectOpcode::Haste => 16,
+            EffectOpcode::CurrentHpBonus => 17,
+            EffectOpcode::MaximumHpBonus => 18,
+            EffectOpcode::IntelligenceBonus => 19,
+            EffectOpcode::Invisibility => 20,
+            EffectOpcode::LoreBonus => 21,
+            EffectOpcode::LuckBonus => 22,
+            EffectOpcode::ResetMorale => 23,
+            EffectOpcode::Panic => 24,
+            EffectOpcode::Poison => 25,
+            EffectOpcode::RemoveCurse => 26,
+            EffectOpcode::AcidResistanceBonus => 27,
+            EffectOpcode::ColdResistanceBonus => 28,
+            EffectOpcode::ElectricityResistanceBonus => 29,
+            EffectOpcode::FireResistanceBonus => 30,
+            EffectOpcode::MagicDamageResistanceBonus => 31,
+            EffectOpcode::RaiseDead => 32,
+            EffectOpcode::SaveVsDeathBonus => 33,
+            EffectOpcode::SaveVsWandBonus => 34,
+            EffectOpcode::SaveVsPolymorphBonus => 35,
+            EffectOpcode::SaveVsBreathBonus => 36,
+            EffectOpcode::SaveVsSpellBonus => 37,
+            EffectOpcode::Silence => 38,
+            EffectOpcode::Sleep => 39,
+            EffectOpcode::Slow => 40,
+            EffectOpcode::Sparkle => 41,
+            EffectOpcode::BonusWizardSpells => 42,
+            EffectOpcode::StoneToFlesh => 43,
+            EffectOpcode::StrengthBonus => 44,
+            EffectOpcode::Stun => 45,
+            EffectOpcode::CureStun => 46,
+            EffectOpcode::RemoveInvisibility => 47,
+            EffectOpcode::Vocalize => 48,
+            EffectOpcode::WisdomBonus => 49,
+            EffectOpcode::Thac0Bonus => 54,
+            EffectOpcode::Slay => 55,
+            EffectOpcode::DispelEffects => 58,
+            EffectOpcode::CastingFailure => 60,
+            EffectOpcode::Infravision => 63,
+            EffectOpcode::SummonCreature => 67,
+            EffectOpcode::AttackDamageBonus => 73,
+            EffectOpcode::Blindness => 74,
+            EffectOpcode::Disease => 78,
+            EffectOpcode::ProtectionFromOpcode => 101,
+            EffectOpcode::RemoveItem => 112,
+            EffectOpcode::Confusion => 128,
+            EffectOpcode::DisplayString => 139,
+            EffectOpcode::LightingEffects => 141,
+            EffectOpcode::DisplayPortraitIcon => 142,
+            EffectOpcode::CastSpell => 146,
+            EffectOpcode::CastSpellAtPoint => 148,
+            EffectOpcode::GiveInnateAbility => 171,
+            EffectOpcode::RemoveSpell => 172,
+            EffectOpcode::PlaySound => 174,
+            EffectOpcode::UseEffFile => 177,
+            EffectOpcode::ProtectionFromSpell => 206,
+            EffectOpcode::ModifyProficiencies => 233,
+            EffectOpcode::Unknown(i) => *i,
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use image::{ImageBuffer, Rgba};
+
+use crate::{
+    datasource::{DataSource, Importer},
+    fs::{CaseInsensitivePath, FileSystem},
+    resource::bam_font::{BamFont, glyph_cycle},
+};
+
+pub const FNT_V1_SIGNATURE: &str = "FNT V1  ";
+
+/// A FNT file importer
+pub struct FntImporter;
+
+impl Importer for FntImporter {
+    type T = Fnt;
+
+    fn import(source: &DataSource) -> std::io::Result<Fnt> {
+        let mut reader = source.reader()?;
+
+        let signature = reader.read_string(8)?;
+        if signature != FNT_V1_SIGNATURE {
+            return Err(std::io::Error::other(format!(
+                "Wrong file type: {}",
+                signature
+            )));
+        }
+
+        let bam = reader.read_string(8)?;
+        let widths_count = reader.read_u32()? as u64;
+        let widths_offset = reader.read_u32()? as u64;
+
+        reader.set_position(widths_offset)?;
+        let widths = reader.take_to_vec(widths_count)?;
+        if widths.len() as u64 != widths_count {
+            return Err(std::io::Error::other(format!(
+                "Width table truncated: {} of {} entries",
+                widths.len(),
+                widths_count
+            )));
+        }
+
+        Ok(Fnt { bam, widths })
+    }
+}
+
+/// A FNT file, the description of a bitmap font whose glyphs are stored in a BAM file
+#[derive(Debug, PartialEq, Eq)]
+pub struct Fnt {
+    /// The resref of the BAM file of the glyphs
+    pub bam: String,
+    /// The advance widths of the glyphs, indexed like the BAM cycles: the entry `n - 1` is the width
+    /// of the character with WINDOWS-1252 code `n`
+    pub widths: Vec<u8>,
+}
+
+impl Fnt {
+    /// Returns the width of a character in the width table
+    pub fn glyph_width(&self, ch: char) -> Option<u32> {
+        self.widths.get(glyph_cycle(ch)?).map(|width| *width as u32)
+    }
+}
+
+/// A bitmap font read from a FNT file and the BAM file it references
+#[derive(Debug, PartialEq, Eq)]
+pub struct FntFont {
+    pub fnt: Fnt,
+    pub glyphs: BamFont,
+}
+
+impl FntFont {
+    /// Opens a font, reading its FNT file and then its BAM file from the file system.
+    /// Both files are looked up in the root directory first, then in the predefined folders.
+    pub fn open(name: &str, fs: &impl FileSystem) -> std::io::Result<FntFont> {
+        let path = CaseInsensitivePath::new(&format!("{name}.FNT"));
+        let source = fs
+            .open_opt(&path)
+            .or_else(|| fs.search_path_opt(&path).map(DataSource::new))
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("Font FNT file not found: {}", path.as_str()),
+                )
+            })?;
+
+        let fnt = FntImporter::import(&source)?;
+        let glyphs = BamFont::open(&fnt.bam, fs)?;
+        Ok(FntFont { fnt, glyphs })
+    }
+
+    /// Returns the width of the glyph of a character.
+    /// The width table of the FNT file takes precedence over the width of the BAM frame.
+    pub fn glyph_width(&self, ch: char) -> Option<u32> {
+        self.fnt
+            .glyph_width(ch)
+            .or_else(|| self.glyphs.glyph_width(ch))
+    }
+
+    /// Renders the glyph of a character
+    pub fn glyph_image(&self, ch: char) -> Option<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+        self.glyphs.glyph_image(ch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fs::CaseInsensitiveFS, test_utils::RESOURCES_DIR};
+    use std::path::Path;
+
+    #[test]
+    fn test_import_fnt() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/FNT/NORMAL.FNT"
+        )));
+
+        let fnt = FntImporter::import(&data).unwrap();
+
+        assert_eq!(fnt.bam, "GLYPHS");
+        assert_eq!(fnt.widths.len(), 255);
+        assert_eq!(fnt.glyph_width('A'), Some(3));
+        assert_eq!(fnt.glyph_width('é'), Some(4));
+        assert_eq!(fnt.glyph_width('B'), Some(0));
+        assert_eq!(fnt.glyph_width('漢'), None);
+
+        let mut truncated =
+            std::fs::read(format!("{RESOURCES_DIR}/resources/FNT/NORMAL.FNT")).unwrap();
+        truncated.truncate(100);
+        assert!(FntImporter::import(&DataSource::new(truncated)).is_err());
+    }
+
+    #[test]
+    fn test_render_fnt_glyphs() {
+        let fs = CaseInsensitiveFS::new(format!("{RESOURCES_DIR}/resources/FNT")).unwrap();
+
+        let font = FntFont::open("normal", &fs).unwrap();
+
+        let a = font.glyph_image('A').unwrap();
+        assert_eq!(a.dimensions(), (2, 3));
+        assert_eq!(a.get_pixel(0, 0), &Rgba([255, 255, 255, 255]));
+        assert_eq!(a.get_pixel(1, 0), &Rgba([0, 255, 0, 0]));
+        // The width table adds a pixel of spacing to the frame width
+        assert_eq!(font.glyph_width('A'), Some(3));
+
+        let e = font.glyph_image('é').unwrap();
+        assert_eq!(e.dimensions(), (3, 2));
+        assert_eq!(e.get_pixel(1, 0), &Rgba([255, 255, 255, 255]));
+        assert_eq!(font.glyph_width('é'), Some(4));
+
+        assert!(font.glyph_image('B').is_none());
+        assert!(FntFont::open("MISSING", &fs).is_err());
+    }
+}
@@ -0,0 +1,343 @@
+use std::io::Read;
+
+use serde::{Deserialize, Serialize};
+
+use crate::datasource::{DataSource, Importer, Reader};
+
+/// The size of a NPC entry in a V1.1/V2.0/V2.1 GAM file
+const NPC_SIZE: u64 = 0x160;
+
+/// A GAM file importer
+pub struct GamImporter;
+
+impl Importer for GamImporter {
+    type T = Gam;
+
+    fn import(source: &DataSource) -> std::io::Result<Gam> {
+        let mut reader = source.reader()?;
+
+        let signature = reader.read_string(4)?;
+        if signature != "GAME" {
+            return Err(std::io::Error::other(format!(
+                "Wrong file type: {}",
+                signature
+            )));
+        }
+
+        let version = GamVersion::parse(&reader.read_string(4)?)?;
+        let game_time = reader.read_u32()?;
+        let formation = reader.read_u16()?;
+        let mut formation_buttons = [0; 5];
+        for button in &mut formation_buttons {
+            *button = reader.read_u16()?;
+        }
+        let party_gold = reader.read_u32()?;
+        reader.skip(2)?; // unknown
+        let weather = reader.read_u16()?;
+        let party_offset = reader.read_u32()? as u64;
+        let party_count = reader.read_u32()? as usize;
+        reader.skip(8)?; // party inventory, unused
+        let npcs_offset = reader.read_u32()? as u64;
+        let npcs_count = reader.read_u32()? as usize;
+        let globals_offset = reader.read_u32()? as u64;
+        let globals_count = reader.read_u32()? as usize;
+        let main_area = reader.read_string(8)?;
+        reader.skip(4)?; // familiar extra offset
+        let journal_count = reader.read_u32()? as usize;
+        let journal_offset = reader.read_u32()? as u64;
+        let reputation = reader.read_u32()?;
+        let current_area = reader.read_string(8)?;
+        let gui_flags = reader.read_u32()?;
+
+        // The NPC entries are NPC_SIZE bytes long, only their first fields are read
+        let read_npc = |reader: &mut Reader<_>| {
+            let start = reader.position()?;
+            let npc = GamNpc::read_entry(reader)?;
+            reader.set_position(start + NPC_SIZE)?;
+            Ok(npc)
+        };
+
+        // Read the party members
+        let party = reader.read_table(party_offset, party_count, read_npc)?;
+
+        // Read the NPCs met by the party that are not in the party
+        let npcs = reader.read_table(npcs_offset, npcs_count, read_npc)?;
+
+        // Read the global variables
+        let globals = reader.read_table(globals_offset, globals_count, |reader| {
+            let name = reader.read_string(32)?;
+            reader.skip(8)?; // type, reference value and dword value, unused
+            let value = reader.read_i32()?;
+            reader.skip(40)?; // double value and script name, unused
+            Ok(GamVariable { name, value })
+        })?;
+
+        // Read the journal entries
+        let journal = reader.read_table(journal_offset, journal_count, |reader| {
+            Ok(GamJournalEntry {
+                text: reader.read_u32()?,
+                time: reader.read_u32()?,
+                chapter: reader.read_u8()?,
+                read_by: reader.read_u8()?,
+                section: reader.read_u8()?,
+                location: reader.read_u8()?,
+            })
+        })?;
+
+        Ok(Gam {
+            version,
+            game_time,
+            formation,
+            formation_buttons,
+            party_gold,
+            weather,
+            main_area,
+            reputation,
+            current_area,
+            gui_flags,
+            party,
+            npcs,
+            globals,
+            journal,
+        })
+    }
+}
+
+/// A GAM file, the state of a saved game
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Gam {
+    pub version: GamVersion,
+    /// The game time, in units of 1/15 of a second
+    pub game_time: u32,
+    pub formation: u16,
+    pub formation_buttons: [u16; 5],
+    pub party_gold: u32,
+    pub weather: u16,
+    pub main_area: String,
+    /// The party reputation, multiplied by 10
+    pub reputation: u32,
+    pub current_area: String,
+    pub gui_flags: u32,
+    /// The characters in the party, the first one is the protagonist
+    pub party: Vec<GamNpc>,
+    /// The characters met by the party that are currently not in the party
+    pub npcs: Vec<GamNpc>,
+    pub globals: Vec<GamVariable>,
+    pub journal: Vec<GamJournalEntry>,
+}
+
+impl Gam {
+    /// Returns the party members sorted by their position in the party
+    pub fn party_by_order(&self) -> Vec<&GamNpc> {
+        let mut party = self.party.iter().collect::<Vec<_>>();
+        party.sort_by_key(|npc| npc.party_order);
+        party
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum GamVersion {
+    /// BG, IWD
+    V1_1,
+    /// BG2
+    V2_0,
+    V2_1,
+}
+
+impl GamVersion {
+    fn parse(version: &str) -> std::io::Result<GamVersion> {
+        match version {
+            "V1.1" => Ok(GamVersion::V1_1),
+            "V2.0" => Ok(GamVersion::V2_0),
+            "V2.1" => Ok(GamVersion::V2_1),
+            version => Err(std::io::Error::other(format!(
+                "Unsupported GAM version: {}",
+                version
+            ))),
+        }
+    }
+}
+
+/// A character entry, either a party member or a NPC
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GamNpc {
+    /// 0 if not selected, 1 if selected, 0x8000 if dead
+    pub selection: u16,
+    /// The position in the party, 0xffff if not in the party
+    pub party_order: u16,
+    /// The offset of the embedded CRE file, 0 if the character references an external one
+    pub cre_offset: u64,
+    pub cre_size: u32,
+    /// The CRE file of the character, if not embedded
+    pub cre: String,
+    pub orientation: u32,
+    pub area: String,
+    pub x: u16,
+    pub y: u16,
+    pub view_x: u16,
+    pub view_y: u16,
+    pub modal_action: u16,
+    pub happiness: i16,
+    pub name: String,
+    pub talk_count: u32,
+    pub stats: GamCharacterStats,
+    pub voice_set: String,
+}
+
+impl GamNpc {
+    /// Reads a NPC entry, the reader must be at the start of the entry
+    fn read_entry<R: Read>(reader: &mut Reader<R>) -> std::io::Result<GamNpc> {
+        let selection = reader.read_u16()?;
+        let party_order = reader.read_u16()?;
+        let cre_offset = reader.read_u32()? as u64;
+        let cre_size = reader.read_u32()?;
+        let cre = reader.read_string(8)?;
+        let orientation = reader.read_u32()?;
+        let area = reader.read_string(8)?;
+        let x = reader.read_u16()?;
+        let y = reader.read_u16()?;
+        let view_x = reader.read_u16()?;
+        let view_y = reader.read_u16()?;
+        let modal_action = reader.read_u16()?;
+        let happiness = reader.read_i16()?;
+        reader.skip(96)?; // interaction counts, unused
+        reader.skip(52)?; // quick weapons, spells and items
+        let name = reader.read_string(32)?;
+        let talk_count = reader.read_u32()?;
+
+        let most_powerful_vanquished = reader.read_u32()?;
+        let most_powerful_vanquished_xp = reader.read_u32()?;
+        let time_in_party = reader.read_u32()?;
+        let join_time = reader.read_u32()?;
+        let in_party = reader.read_u8()? != 0;
+        reader.skip(3)?; // unused, first letter of the CRE resref
+        let chapter_kills_xp = reader.read_u32()?;
+        let chapter_kills = reader.read_u32()?;
+        let game_kills_xp = reader.read_u32()?;
+        let game_kills = reader.read_u32()?;
+        let mut favourite_spells = Vec::with_capacity(4);
+        for _ in 0..4 {
+            favourite_spells.push(reader.read_string(8)?);
+        }
+        let mut favourite_spells_count = [0; 4];
+        for count in &mut favourite_spells_count {
+            *count = reader.read_u16()?;
+        }
+        let mut favourite_weapons = Vec::with_capacity(4);
+        for _ in 0..4 {
+            favourite_weapons.push(reader.read_string(8)?);
+        }
+        let mut favourite_weapons_time = [0; 4];
+        for time in &mut favourite_weapons_time {
+            *time = reader.read_u16()?;
+        }
+        let voice_set = reader.read_string(8)?;
+
+        Ok(GamNpc {
+            selection,
+            party_order,
+            cre_offset,
+            cre_size,
+            cre,
+            orientation,
+            area,
+            x,
+            y,
+            view_x,
+            view_y,
+            modal_action,
+            happiness,
+            name,
+            talk_count,
+            stats: GamCharacterStats {
+                most_powerful_vanquished,
+                most_powerful_vanquished_xp,
+                time_in_party,
+                join_time,
+                in_party,
+                chapter_kills_xp,
+                chapter_kills,
+                game_kills_xp,
+                game_kills,
+                favourite_spells,
+                favourite_spells_count,
+                favourite_weapons,
+                favourite_weapons_time,
+            },
+            voice_set,
+        })
+    }
+}
+
+/// The statistics shown in the character record screen
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GamCharacterStats {
+    /// The StrRef of the name of the most powerful creature vanquished
+    pub most_powerful_vanquished: u32,
+    pub most_powerful_vanquished_xp: u32,
+    /// The time spent in the party, in units of 1/15 of a second
+    pub time_in_party: u32,
+    pub join_time: u32,
+    pub in_party: bool,
+    pub chapter_kills_xp: u32,
+    pub chapter_kills: u32,
+    pub game_kills_xp: u32,
+    pub game_kills: u32,
+    pub favourite_spells: Vec<String>,
+    pub favourite_spells_count: [u16; 4],
+    pub favourite_weapons: Vec<String>,
+    pub favourite_weapons_time: [u16; 4],
+}
+
+/// A global variable
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GamVariable {
+    pub name: String,
+    pub value: i32,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GamJournalEntry {
+    /// The StrRef of the entry text
+    pub text: u32,
+    /// The time the entry was added, in seconds
+    pub time: u32,
+    pub chapter: u8,
+    pub read_by: u8,
+    pub section: u8,
+    /// 0x1f if the text is stored in the TOH file, 0xff if it is stored in the TLK file
+    pub location: u8,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::RESOURCES_DIR;
+    use insta::assert_json_snapshot;
+    use std::path::Path;
+
+    #[test]
+    fn test_parse_gam() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/GAM/BALDUR.GAM"
+        )));
+
+        let gam = GamImporter::import(&data).unwrap();
+
+        assert_eq!(gam.version, GamVersion::V2_0);
+        assert_eq!(gam.party_gold, 1250);
+        assert_eq!(gam.party.len(), 3);
+        assert_eq!(gam.npcs.len(), 1);
+        assert_eq!(gam.globals.len(), 2);
+        assert_eq!(gam.journal.len(), 2);
+
+        let order = gam
+            .party_by_order()
+            .iter()
+            .map(|npc| npc.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(order, vec!["Hero", "Minsc", "Jaheira"]);
+
+        assert_json_snapshot!(gam);
+    }
+}
@@ -0,0 +1,123 @@
+use crate::{
+    datasource::{DataSource, Importer},
+    locator::ResourceLocator,
+    resource::key::ResourceType,
+};
+
+/// The directive including another GLSL resource, e.g. `#include "FPCOMMON"`
+const INCLUDE_DIRECTIVE: &str = "#include";
+
+/// A GLSL file importer.
+/// The shaders of the Enhanced Editions are plain text.
+pub struct GlslImporter;
+
+impl Importer for GlslImporter {
+    type T = String;
+
+    fn import(source: &DataSource) -> std::io::Result<String> {
+        let mut reader = source.reader()?.into_text_reader()?;
+        reader.read_string(u64::MAX)
+    }
+}
+
+impl GlslImporter {
+    /// Imports a GLSL resource replacing its `#include "RESREF"` lines with the resolved text of the included resources.
+    /// The included resources are opened with the locator, the `.glsl` extension of their name is optional.
+    /// Returns an error if a resource includes itself, directly or through other resources.
+    pub fn import_resolved(name: &str, locator: &ResourceLocator) -> std::io::Result<String> {
+        let mut text = String::new();
+        resolve(name, locator, &mut Vec::new(), &mut text)?;
+        Ok(text)
+    }
+}
+
+/// Appends the resolved text of a resource to `text`.
+/// `including` holds the uppercased names of the resources being resolved, to detect the cycles.
+fn resolve(
+    name: &str,
+    locator: &ResourceLocator,
+    including: &mut Vec<String>,
+    text: &mut String,
+) -> std::io::Result<()> {
+    let name = name.to_uppercase();
+    let name = name.strip_suffix(".GLSL").unwrap_or(&name).to_string();
+    if including.contains(&name) {
+        return Err(std::io::Error::other(format!(
+            "Include cycle: {} -> {}",
+            including.join(" -> "),
+            name
+        )));
+    }
+
+    let source = GlslImporter::import(&locator.open(&name, ResourceType::Glsl)?)?;
+    including.push(name);
+    for line in source.lines() {
+        match include_target(line) {
+            Some(include) => resolve(include, locator, including, text)?,
+            None => {
+                text.push_str(line);
+                text.push('\n');
+            }
+        }
+    }
+    including.pop();
+    Ok(())
+}
+
+/// Returns the resource included by a line, if it is an `#include "RESREF"` directive
+fn include_target(line: &str) -> Option<&str> {
+    line.trim()
+        .strip_prefix(INCLUDE_DIRECTIVE)?
+        .trim()
+        .strip_prefix('"')?
+        .strip_suffix('"')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fs::CaseInsensitiveFS, test_utils::BG2_EE_RESOURCES_DIR};
+
+    fn locator() -> ResourceLocator {
+        ResourceLocator::from_fs(CaseInsensitiveFS::new(BG2_EE_RESOURCES_DIR).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_include_target() {
+        assert_eq!(include_target(r#"#include "FPCOMMON""#), Some("FPCOMMON"));
+        assert_eq!(
+            include_target(r#"  #include  "fpcommon.glsl" "#),
+            Some("fpcommon.glsl")
+        );
+        assert_eq!(include_target("#include FPCOMMON"), None);
+        assert_eq!(include_target("// #include \"FPCOMMON\""), None);
+    }
+
+    #[test]
+    fn test_import_resolved() {
+        let locator = locator();
+
+        let common =
+            GlslImporter::import(&locator.open("FPCOMMON", ResourceType::Glsl).unwrap()).unwrap();
+        let sprite =
+            GlslImporter::import(&locator.open("FPSPRITE", ResourceType::Glsl).unwrap()).unwrap();
+        assert!(sprite.starts_with("#include \"FPCOMMON\"\n"));
+
+        let resolved = GlslImporter::import_resolved("fpsprite", &locator).unwrap();
+
+        // The include line is replaced by the included text
+        let (_, sprite_body) = sprite.split_once('\n').unwrap();
+        assert_eq!(resolved, format!("{common}{sprite_body}"));
+        assert!(!resolved.contains(INCLUDE_DIRECTIVE));
+    }
+
+    #[test]
+    fn test_import_resolved_detects_cycles() {
+        let locator = locator();
+
+        let err = GlslImporter::import_resolved("FPSELF", &locator).unwrap_err();
+        assert!(err.to_string().contains("FPSELF -> FPSELF"));
+
+        assert!(GlslImporter::import_resolved("MISSING", &locator).is_err());
+    }
+}
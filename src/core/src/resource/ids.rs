@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+
+use crate::datasource::{DataSource, Importer};
+
+/// An IDS file importer
+pub struct IdsImporter;
+
+impl Importer for IdsImporter {
+    type T = Ids;
+
+    fn import(source: &DataSource) -> std::io::Result<Ids> {
+        let mut reader = source.reader()?.into_text_reader()?;
+
+        let mut entries = Vec::new();
+        loop {
+            let (line, bytes) = reader.read_line()?;
+            if bytes == 0 {
+                break;
+            }
+            // The optional header ("IDS V1.0" or the entries count) and blank lines are skipped
+            let line = line.trim();
+            let Some((value, symbol)) = line.split_once(char::is_whitespace) else {
+                continue;
+            };
+            if let Some(value) = parse_value(value) {
+                entries.push(IdsEntry {
+                    value,
+                    symbol: symbol.trim().to_string(),
+                });
+            }
+        }
+
+        Ok(Ids { entries })
+    }
+}
+
+/// Parses an IDS value, written either in decimal or in hexadecimal notation
+fn parse_value(value: &str) -> Option<i64> {
+    match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => i64::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+/// An IDS file, mapping numeric values to symbols
+#[derive(Debug, PartialEq, Eq)]
+pub struct Ids {
+    pub entries: Vec<IdsEntry>,
+}
+
+impl Ids {
+    /// Returns the first symbol associated to a value
+    pub fn symbol(&self, value: i64) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|entry| entry.value == value)
+            .map(|entry| entry.symbol.as_str())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct IdsEntry {
+    pub value: i64,
+    pub symbol: String,
+}
+
+/// A bundle of IDS files, indexed by their case-insensitive name without extension, e.g. `TRIGGER`
+#[derive(Debug, Default)]
+pub struct IdsTables {
+    tables: HashMap<String, Ids>,
+}
+
+impl IdsTables {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an IDS file to the bundle, replacing any previous one with the same name
+    pub fn insert(&mut self, name: &str, ids: Ids) {
+        self.tables.insert(name.to_uppercase(), ids);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Ids> {
+        self.tables.get(&name.to_uppercase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::RESOURCES_DIR;
+    use std::path::Path;
+
+    #[test]
+    fn test_parse_ids() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/IDS/TRIGGER.IDS"
+        )));
+
+        let ids = IdsImporter::import(&data).unwrap();
+
+        assert_eq!(ids.entries.len(), 3);
+        assert_eq!(ids.symbol(0x400F), Some("Global(S:Name*,S:Area*,I:Value*)"));
+        assert_eq!(ids.symbol(1), None);
+    }
+}
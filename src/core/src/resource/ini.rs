@@ -0,0 +1,105 @@
+use crate::datasource::{DataSource, Importer};
+
+/// An INI file importer
+pub struct IniImporter;
+
+impl Importer for IniImporter {
+    type T = Ini;
+
+    fn import(source: &DataSource) -> std::io::Result<Ini> {
+        let mut reader = source.reader()?.into_text_reader()?;
+
+        let mut sections: Vec<(String, Vec<(String, String)>)> = Vec::new();
+        loop {
+            let (line, bytes) = reader.read_line()?;
+            if bytes == 0 {
+                break;
+            }
+            // Everything after a `//` is a comment
+            let line = line
+                .split_once("//")
+                .map_or(line.as_str(), |(line, _)| line)
+                .trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(name) = line
+                .strip_prefix('[')
+                .and_then(|name| name.strip_suffix(']'))
+            {
+                sections.push((name.trim().to_string(), Vec::new()));
+            } else if let Some((key, value)) = line.split_once('=') {
+                // Keys preceding the first section header belong to an unnamed section
+                if sections.is_empty() {
+                    sections.push((String::new(), Vec::new()));
+                }
+                if let Some((_, entries)) = sections.last_mut() {
+                    entries.push((key.trim().to_string(), value.trim().to_string()));
+                }
+            }
+        }
+
+        Ok(Ini { sections })
+    }
+}
+
+/// An INI file.
+/// The sections and their keys are kept in file order, duplicated keys are preserved.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Ini {
+    pub sections: Vec<(String, Vec<(String, String)>)>,
+}
+
+impl Ini {
+    /// Returns the entries of the first section with the given case-insensitive name
+    pub fn section(&self, name: &str) -> Option<&[(String, String)]> {
+        self.sections
+            .iter()
+            .find(|(section, _)| section.eq_ignore_ascii_case(name))
+            .map(|(_, entries)| entries.as_slice())
+    }
+
+    /// Returns the first value of a key in a section, both matched case-insensitively
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        self.section(section)?
+            .iter()
+            .find(|(entry, _)| entry.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::RESOURCES_DIR;
+    use std::path::Path;
+
+    #[test]
+    fn test_parse_ini() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/INI/AR1000.INI"
+        )));
+
+        let ini = IniImporter::import(&data).unwrap();
+
+        assert_eq!(ini.sections.len(), 3);
+        assert_eq!(ini.sections[0].0, "locals");
+        assert_eq!(ini.sections[0].1.len(), 1);
+        assert_eq!(ini.sections[1].1.len(), 4);
+        assert_eq!(ini.sections[2].1.len(), 5);
+
+        assert_eq!(ini.get("spawn_main", "enter"), Some("goblin_group"));
+        assert_eq!(ini.get("GOBLIN_GROUP", "cre_file"), Some("goblin01"));
+        assert_eq!(ini.get("goblin_group", "missing"), None);
+
+        // Duplicated keys are all preserved
+        let points = ini
+            .section("goblin_group")
+            .unwrap()
+            .iter()
+            .filter(|(key, _)| key == "spawn_point")
+            .count();
+        assert_eq!(points, 2);
+    }
+}
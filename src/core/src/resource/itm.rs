@@ -0,0 +1,393 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    datasource::{DataSource, Importer},
+    resource::{
+        ability::{Ability, AbilityTarget, AttackType, TargetType, feature_blocks_range},
+        effect::EffectV1,
+    },
+};
+
+/// An ITM file importer
+pub struct ItmImporter;
+
+impl Importer for ItmImporter {
+    type T = Itm;
+
+    fn import(source: &DataSource) -> std::io::Result<Itm> {
+        let mut reader = source.reader()?;
+
+        let signature = reader.read_string(4)?;
+        if signature != "ITM " {
+            return Err(std::io::Error::other(format!(
+                "Wrong file type: {}",
+                signature
+            )));
+        }
+
+        let version = match reader.read_string(4)?.as_str() {
+            "V1  " => ItmVersion::V1,
+            "V1.1" => ItmVersion::V1_1,
+            version => {
+                return Err(std::io::Error::other(format!(
+                    "Unsupported ITM version: {}",
+                    version
+                )));
+            }
+        };
+
+        let unidentified_name = reader.read_u32()?;
+        let identified_name = reader.read_u32()?;
+        let replacement_item = reader.read_string(8)?;
+        let flags = ItmFlags::from_bits_retain(reader.read_u32()?);
+        let item_type = reader.read_u16()?;
+        let usability = reader.read_u32()?;
+        let animation = reader.read_string(2)?;
+        let min_level = reader.read_u16()?;
+        let min_strength = reader.read_u16()?;
+        let min_strength_bonus = reader.read_u8()?;
+        let kit_usability_1 = reader.read_u8()?;
+        let min_intelligence = reader.read_u8()?;
+        let kit_usability_2 = reader.read_u8()?;
+        let min_dexterity = reader.read_u8()?;
+        let kit_usability_3 = reader.read_u8()?;
+        let min_wisdom = reader.read_u8()?;
+        let kit_usability_4 = reader.read_u8()?;
+        let min_constitution = reader.read_u8()?;
+        let weapon_proficiency = reader.read_u8()?;
+        let min_charisma = reader.read_u16()?;
+        let price = reader.read_u32()?;
+        let max_stack = reader.read_u16()?;
+        let inventory_icon = reader.read_string(8)?;
+        let lore = reader.read_u16()?;
+        let ground_icon = reader.read_string(8)?;
+        let weight = reader.read_u32()?;
+        let unidentified_description = reader.read_u32()?;
+        let identified_description = reader.read_u32()?;
+        let description_icon = reader.read_string(8)?;
+        let enchantment = reader.read_u32()?;
+        let abilities_offset = reader.read_u32()? as u64;
+        let abilities_count = reader.read_u16()? as usize;
+        let feature_blocks_offset = reader.read_u32()? as u64;
+        let equipping_feature_blocks_index = reader.read_u16()?;
+        let equipping_feature_blocks_count = reader.read_u16()?;
+
+        // PST items carry some additional fields at the end of the header
+        let pst_header = match version {
+            ItmVersion::V1 => None,
+            ItmVersion::V1_1 => Some(ItmPstHeader {
+                dialog: reader.read_string(8)?,
+                talking_item_name: reader.read_u32()?,
+                weapon_color: reader.read_u16()?,
+            }),
+        };
+
+        // Read the extended headers
+        let abilities = reader.read_table(abilities_offset, abilities_count, |reader| {
+            Ok(ItmAbility {
+                attack_type: reader.read_u8()?,
+                identify_required: reader.read_u8()? != 0,
+                location: reader.read_u8()?,
+                alternative_dice_sides: reader.read_u8()?,
+                use_icon: reader.read_string(8)?,
+                target_type: reader.read_u8()?,
+                target_count: reader.read_u8()?,
+                range: reader.read_u16()?,
+                launcher_required: reader.read_u8()?,
+                alternative_dice_thrown: reader.read_u8()?,
+                speed_factor: reader.read_u8()?,
+                alternative_damage_bonus: reader.read_u8()?,
+                thac0_bonus: reader.read_i16()?,
+                dice_sides: reader.read_u8()?,
+                primary_type: reader.read_u8()?,
+                dice_thrown: reader.read_u8()?,
+                secondary_type: reader.read_u8()?,
+                damage_bonus: reader.read_i16()?,
+                damage_type: reader.read_u16()?,
+                feature_blocks_count: reader.read_u16()?,
+                feature_blocks_index: reader.read_u16()?,
+                max_charges: reader.read_u16()?,
+                charge_depletion: reader.read_u16()?,
+                flags: reader.read_u32()?,
+                projectile_animation: reader.read_u16()?,
+                melee_animation: [reader.read_u16()?, reader.read_u16()?, reader.read_u16()?],
+                is_arrow: reader.read_u16()? != 0,
+                is_bolt: reader.read_u16()? != 0,
+                is_bullet: reader.read_u16()? != 0,
+            })
+        })?;
+
+        // The feature blocks table is shared by the equipping effects and by all the abilities.
+        // Its size is not stored, so it is deduced from the blocks referenced by them.
+        let feature_blocks_count = abilities
+            .iter()
+            .map(|ability| {
+                ability.feature_blocks_index as usize + ability.feature_blocks_count as usize
+            })
+            .chain(std::iter::once(
+                equipping_feature_blocks_index as usize + equipping_feature_blocks_count as usize,
+            ))
+            .max()
+            .unwrap_or_default();
+
+        let feature_blocks = reader.read_table(
+            feature_blocks_offset,
+            feature_blocks_count,
+            EffectV1::read_entry,
+        )?;
+
+        Ok(Itm {
+            version,
+            unidentified_name,
+            identified_name,
+            replacement_item,
+            flags,
+            item_type,
+            usability,
+            kit_usability: [
+                kit_usability_1,
+                kit_usability_2,
+                kit_usability_3,
+                kit_usability_4,
+            ],
+            animation,
+            min_level,
+            min_strength,
+            min_strength_bonus,
+            min_intelligence,
+            min_dexterity,
+            min_wisdom,
+            min_constitution,
+            min_charisma,
+            weapon_proficiency,
+            price,
+            max_stack,
+            inventory_icon,
+            lore,
+            ground_icon,
+            weight,
+            unidentified_description,
+            identified_description,
+            description_icon,
+            enchantment,
+            abilities_offset,
+            feature_blocks_offset,
+            equipping_feature_blocks_index,
+            equipping_feature_blocks_count,
+            pst_header,
+            abilities,
+            feature_blocks,
+        })
+    }
+}
+
+impl Itm {
+    /// Returns the feature blocks applied when the item is equipped
+    pub fn equipping_feature_blocks(&self) -> &[EffectV1] {
+        let start = self.equipping_feature_blocks_index as usize;
+        let end = start + self.equipping_feature_blocks_count as usize;
+        &self.feature_blocks[start..end]
+    }
+
+    /// Returns the feature blocks of an ability
+    pub fn ability_feature_blocks(&self, ability: &ItmAbility) -> &[EffectV1] {
+        &self.feature_blocks
+            [feature_blocks_range(ability.feature_blocks_index, ability.feature_blocks_count)]
+    }
+
+    /// Returns the abilities with their feature blocks
+    pub fn ability_list(&self) -> Vec<Ability<'_>> {
+        self.abilities
+            .iter()
+            .map(|ability| {
+                let effect_range = feature_blocks_range(
+                    ability.feature_blocks_index,
+                    ability.feature_blocks_count,
+                );
+                Ability {
+                    attack_type: AttackType::from_u8(ability.attack_type),
+                    target: AbilityTarget {
+                        target_type: TargetType::from_u8(ability.target_type),
+                        count: ability.target_count,
+                    },
+                    range: ability.range,
+                    charges: ability.max_charges,
+                    effects: &self.feature_blocks[effect_range.clone()],
+                    effect_range,
+                }
+            })
+            .collect()
+    }
+}
+
+/// An ITM file.
+/// Names and descriptions are StrRefs into the TLK file.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Itm {
+    pub version: ItmVersion,
+    pub unidentified_name: u32,
+    pub identified_name: u32,
+    /// The item this one is replaced with when it is drained of charges
+    pub replacement_item: String,
+    pub flags: ItmFlags,
+    pub item_type: u16,
+    /// Bitmask of the classes, races and alignments that cannot use the item
+    pub usability: u32,
+    /// Bitmasks of the kits that cannot use the item
+    pub kit_usability: [u8; 4],
+    pub animation: String,
+    pub min_level: u16,
+    pub min_strength: u16,
+    pub min_strength_bonus: u8,
+    pub min_intelligence: u8,
+    pub min_dexterity: u8,
+    pub min_wisdom: u8,
+    pub min_constitution: u8,
+    pub min_charisma: u16,
+    pub weapon_proficiency: u8,
+    pub price: u32,
+    pub max_stack: u16,
+    pub inventory_icon: String,
+    pub lore: u16,
+    pub ground_icon: String,
+    pub weight: u32,
+    pub unidentified_description: u32,
+    pub identified_description: u32,
+    pub description_icon: String,
+    pub enchantment: u32,
+    pub abilities_offset: u64,
+    pub feature_blocks_offset: u64,
+    /// Index into `feature_blocks` of the first equipping effect
+    pub equipping_feature_blocks_index: u16,
+    pub equipping_feature_blocks_count: u16,
+    /// The PST specific fields, only present in V1.1 files
+    pub pst_header: Option<ItmPstHeader>,
+    pub abilities: Vec<ItmAbility>,
+    /// All the feature blocks of the file, equipping and abilities ones
+    pub feature_blocks: Vec<EffectV1>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ItmVersion {
+    /// BG, BG2, IWD
+    V1,
+    /// PST
+    V1_1,
+}
+
+bitflags::bitflags! {
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    pub struct ItmFlags: u32 {
+        const Critical = 1 << 0;
+        const TwoHanded = 1 << 1;
+        const Movable = 1 << 2;
+        const Displayable = 1 << 3;
+        const Cursed = 1 << 4;
+        const NotCopyable = 1 << 5;
+        const Magical = 1 << 6;
+        const LeftHanded = 1 << 7;
+        const Silver = 1 << 8;
+        const ColdIron = 1 << 9;
+        const Stolen = 1 << 10;
+        const Conversable = 1 << 11;
+    }
+}
+
+/// The additional header fields of PST items
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItmPstHeader {
+    pub dialog: String,
+    pub talking_item_name: u32,
+    pub weapon_color: u16,
+}
+
+/// An ITM extended header, describing one of the ways the item can be used
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItmAbility {
+    /// 0 = none, 1 = melee, 2 = ranged, 3 = magical, 4 = launcher
+    pub attack_type: u8,
+    pub identify_required: bool,
+    pub location: u8,
+    pub alternative_dice_sides: u8,
+    pub use_icon: String,
+    pub target_type: u8,
+    pub target_count: u8,
+    pub range: u16,
+    pub launcher_required: u8,
+    pub alternative_dice_thrown: u8,
+    pub speed_factor: u8,
+    pub alternative_damage_bonus: u8,
+    pub thac0_bonus: i16,
+    pub dice_sides: u8,
+    pub primary_type: u8,
+    pub dice_thrown: u8,
+    pub secondary_type: u8,
+    pub damage_bonus: i16,
+    pub damage_type: u16,
+    pub feature_blocks_count: u16,
+    /// Index into `Itm::feature_blocks` of the first effect of this ability
+    pub feature_blocks_index: u16,
+    pub max_charges: u16,
+    pub charge_depletion: u16,
+    pub flags: u32,
+    pub projectile_animation: u16,
+    pub melee_animation: [u16; 3],
+    pub is_arrow: bool,
+    pub is_bolt: bool,
+    pub is_bullet: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::RESOURCES_DIR;
+    use insta::assert_json_snapshot;
+    use std::path::Path;
+
+    #[test]
+    fn test_parse_itm() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/ITM/SW1H99.ITM"
+        )));
+
+        let itm = ItmImporter::import(&data).unwrap();
+
+        assert_eq!(itm.version, ItmVersion::V1);
+        assert_eq!(itm.unidentified_name, 1001);
+        assert_eq!(itm.identified_name, 1002);
+        assert_eq!(itm.item_type, 20);
+        assert_eq!(itm.price, 1500);
+        assert_eq!(itm.weight, 4);
+        assert!(itm.flags.contains(ItmFlags::Movable | ItmFlags::Magical));
+
+        assert_eq!(itm.abilities.len(), 2);
+        assert_eq!(itm.feature_blocks.len(), 5);
+        assert_eq!(itm.ability_feature_blocks(&itm.abilities[0]).len(), 2);
+        assert_eq!(itm.ability_feature_blocks(&itm.abilities[1]).len(), 1);
+        assert_eq!(itm.equipping_feature_blocks().len(), 2);
+
+        assert_json_snapshot!(itm);
+    }
+
+    #[test]
+    fn test_itm_ability_list() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/ITM/SW1H99.ITM"
+        )));
+        let itm = ItmImporter::import(&data).unwrap();
+
+        let abilities = itm.ability_list();
+
+        assert_eq!(abilities.len(), 2);
+        assert_eq!(
+            abilities
+                .iter()
+                .map(|ability| ability.effects.len())
+                .collect::<Vec<_>>(),
+            vec![2, 1]
+        );
+        assert_eq!(abilities[0].attack_type, AttackType::Melee);
+
+        assert_json_snapshot!(abilities);
+    }
+}
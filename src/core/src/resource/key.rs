@@ -1,10 +1,14 @@
-use std::io::{self, BufRead, Seek};
+use std::{
+    io::{self, BufRead, Seek},
+    path::Path,
+};
 
 use serde::{Deserialize, Serialize};
 
 use crate::{
     datasource::{DataSource, Importer, Reader},
     fs::CaseInsensitivePath,
+    resource::{bam::BAMC_SIGNATURE, mos::MOSC_SIGNATURE},
 };
 
 /// A KEY file importer
@@ -137,6 +141,8 @@ pub struct ResourceEntry {
     pub bif_entries_index: u64,
     /// Index of this resource into the bif.entries vector
     pub index_into_bif_file: u64,
+    /// The raw locator of the resource inside its BIF file
+    pub locator: u32,
 }
 
 impl BifEntry {
@@ -204,12 +210,81 @@ impl ResourceEntry {
             r#type: ResourceType::from(resource_type),
             bif_entries_index,
             index_into_bif_file: index_inside_bif_file,
+            locator,
         })
     }
+
+    /// Returns the index of the BIF file containing the resource, stored in bits 20-31 of the locator
+    pub fn bif_index(&self) -> usize {
+        (self.locator >> 20) as usize
+    }
+
+    /// Returns the index of the file inside its BIF file, stored in bits 0-13 of the locator.
+    /// It is 0 for the tilesets.
+    pub fn file_index(&self) -> usize {
+        (self.locator & 0x3fff) as usize
+    }
+
+    /// Returns the index of the tileset inside its BIF file, stored in bits 14-19 of the locator.
+    /// The tileset indices start from 1, so it is 0 for the files that are not tilesets.
+    pub fn tileset_index(&self) -> usize {
+        ((self.locator >> 14) & 0x3f) as usize
+    }
 }
 
+/// All the known resource types
+const ALL_RESOURCE_TYPES: [ResourceType; 47] = [
+    ResourceType::Bmp,
+    ResourceType::Mve,
+    ResourceType::Wav,
+    ResourceType::Wfx,
+    ResourceType::Plt,
+    ResourceType::Tga,
+    ResourceType::Bam,
+    ResourceType::Wed,
+    ResourceType::Chu,
+    ResourceType::Tis,
+    ResourceType::Mos,
+    ResourceType::Itm,
+    ResourceType::Spl,
+    ResourceType::Bcs,
+    ResourceType::Ids,
+    ResourceType::Cre,
+    ResourceType::Are,
+    ResourceType::Dlg,
+    ResourceType::TwoDA,
+    ResourceType::Gam,
+    ResourceType::Sto,
+    ResourceType::Wmp,
+    ResourceType::Eff,
+    ResourceType::Bs,
+    ResourceType::Chr,
+    ResourceType::Vvc,
+    ResourceType::Vef,
+    ResourceType::Pro,
+    ResourceType::Bio,
+    ResourceType::Wbm,
+    ResourceType::Fnt,
+    ResourceType::Gui,
+    ResourceType::Sql,
+    ResourceType::Pvrz,
+    ResourceType::Glsl,
+    ResourceType::Tot,
+    ResourceType::Toh,
+    ResourceType::Menu,
+    ResourceType::Lua,
+    ResourceType::Ttf,
+    ResourceType::Png,
+    ResourceType::Bah,
+    ResourceType::Ini,
+    ResourceType::Src,
+    ResourceType::Maze,
+    ResourceType::Mus,
+    ResourceType::Acm,
+];
+
 /// A Resource file type
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ResourceType {
     Bmp,
     Mve,
@@ -262,6 +337,11 @@ pub enum ResourceType {
 }
 
 impl ResourceType {
+    /// Returns all the known resource types, excluding `ResourceType::Unknown`
+    pub fn all() -> &'static [ResourceType] {
+        &ALL_RESOURCE_TYPES
+    }
+
     /// Returns the `ResourceType` enum variant based on the given hexadecimal value.
     pub fn from(bit: u16) -> Self {
         match bit {
@@ -370,6 +450,15 @@ impl ResourceType {
         }
     }
 
+    /// Returns the extension of the loose files of the resource type, or `None` if it is unknown.
+    /// Loose 2DA files use their own extension rather than the one of the KEY file.
+    pub fn file_extension(&self) -> Option<&str> {
+        match self {
+            ResourceType::TwoDA => Some("2da"),
+            _ => self.get_extension(),
+        }
+    }
+
     /// Returns the extension of the `ResourceType` enum variant as a string, or `None` if it is unknown.
     pub fn get_extension(&self) -> Option<&str> {
         match self {
@@ -423,13 +512,201 @@ impl ResourceType {
             ResourceType::Unknown(_) => None,
         }
     }
+
+    /// Returns the `ResourceType` enum variant of a case-insensitive file extension,
+    /// or `ResourceType::Unknown(0)` if the extension is not recognized.
+    /// This is the inverse of `get_extension`; "2da", the extension of loose 2DA files, is also accepted.
+    pub fn from_extension(ext: &str) -> Self {
+        match ext.to_ascii_lowercase().as_str() {
+            "bmp" => ResourceType::Bmp,
+            "mve" => ResourceType::Mve,
+            "wav" => ResourceType::Wav,
+            "wfx" => ResourceType::Wfx,
+            "plt" => ResourceType::Plt,
+            "tga" => ResourceType::Tga,
+            "bam" => ResourceType::Bam,
+            "wed" => ResourceType::Wed,
+            "chu" => ResourceType::Chu,
+            "tis" => ResourceType::Tis,
+            "mos" => ResourceType::Mos,
+            "itm" => ResourceType::Itm,
+            "spl" => ResourceType::Spl,
+            "bcs" => ResourceType::Bcs,
+            "ids" => ResourceType::Ids,
+            "cre" => ResourceType::Cre,
+            "are" => ResourceType::Are,
+            "dlg" => ResourceType::Dlg,
+            "two" | "2da" => ResourceType::TwoDA,
+            "gam" => ResourceType::Gam,
+            "sto" => ResourceType::Sto,
+            "wmp" => ResourceType::Wmp,
+            "eff" => ResourceType::Eff,
+            "bs" => ResourceType::Bs,
+            "chr" => ResourceType::Chr,
+            "vvc" => ResourceType::Vvc,
+            "vef" => ResourceType::Vef,
+            "pro" => ResourceType::Pro,
+            "bio" => ResourceType::Bio,
+            "wbm" => ResourceType::Wbm,
+            "fnt" => ResourceType::Fnt,
+            "gui" => ResourceType::Gui,
+            "sql" => ResourceType::Sql,
+            "pvrz" => ResourceType::Pvrz,
+            "glsl" => ResourceType::Glsl,
+            "tot" => ResourceType::Tot,
+            "toh" => ResourceType::Toh,
+            "menu" => ResourceType::Menu,
+            "lua" => ResourceType::Lua,
+            "ttf" => ResourceType::Ttf,
+            "png" => ResourceType::Png,
+            "bah" => ResourceType::Bah,
+            "ini" => ResourceType::Ini,
+            "src" => ResourceType::Src,
+            "maze" => ResourceType::Maze,
+            "mus" => ResourceType::Mus,
+            "acm" => ResourceType::Acm,
+            _ => ResourceType::Unknown(0),
+        }
+    }
+
+    /// Returns the `ResourceType` enum variant of the extension of a path
+    pub fn from_path(path: &Path) -> Self {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .map(ResourceType::from_extension)
+            .unwrap_or(ResourceType::Unknown(0))
+    }
+
+    /// Returns the kind of content of the resource type
+    pub fn category(&self) -> ResourceCategory {
+        match self {
+            ResourceType::Bmp
+            | ResourceType::Bam
+            | ResourceType::Mos
+            | ResourceType::Tis
+            | ResourceType::Pvrz
+            | ResourceType::Plt
+            | ResourceType::Png
+            | ResourceType::Tga
+            | ResourceType::Fnt => ResourceCategory::Image,
+            ResourceType::TwoDA
+            | ResourceType::Ids
+            | ResourceType::Bcs
+            | ResourceType::Bs
+            | ResourceType::Gui
+            | ResourceType::Ini
+            | ResourceType::Lua
+            | ResourceType::Glsl
+            | ResourceType::Src
+            | ResourceType::Menu
+            | ResourceType::Sql
+            | ResourceType::Bio => ResourceCategory::Text,
+            ResourceType::Wav
+            | ResourceType::Wfx
+            | ResourceType::Acm
+            | ResourceType::Mus
+            | ResourceType::Mve => ResourceCategory::Audio,
+            ResourceType::Itm
+            | ResourceType::Spl
+            | ResourceType::Cre
+            | ResourceType::Are
+            | ResourceType::Wed
+            | ResourceType::Chu
+            | ResourceType::Dlg
+            | ResourceType::Gam
+            | ResourceType::Sto
+            | ResourceType::Wmp
+            | ResourceType::Eff
+            | ResourceType::Chr
+            | ResourceType::Vvc
+            | ResourceType::Vef
+            | ResourceType::Pro
+            | ResourceType::Tot
+            | ResourceType::Toh
+            | ResourceType::Bah
+            | ResourceType::Maze => ResourceCategory::Data,
+            ResourceType::Wbm | ResourceType::Ttf | ResourceType::Unknown(_) => {
+                ResourceCategory::Other
+            }
+        }
+    }
+
+    /// Returns whether the resource type is an image one
+    pub fn is_image(&self) -> bool {
+        self.category() == ResourceCategory::Image
+    }
+
+    /// Returns whether the resource type is a text one
+    pub fn is_text(&self) -> bool {
+        self.category() == ResourceCategory::Text
+    }
+
+    /// Returns whether the resource type is an audio one
+    pub fn is_audio(&self) -> bool {
+        self.category() == ResourceCategory::Audio
+    }
+
+    /// Returns the signature of the zlib compressed variant of the resource type, if it has one.
+    /// A resource with such a type may be stored either compressed or not, its signature tells which.
+    pub fn typical_compression(&self) -> Option<&'static str> {
+        match self {
+            ResourceType::Bam => Some(BAMC_SIGNATURE),
+            ResourceType::Mos => Some(MOSC_SIGNATURE),
+            _ => None,
+        }
+    }
+
+    /// Returns whether a resource of this type may be stored compressed
+    pub fn may_be_compressed(&self) -> bool {
+        self.typical_compression().is_some()
+    }
+}
+
+/// The kind of content of a resource type
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ResourceCategory {
+    Image,
+    Text,
+    Audio,
+    /// The game data, e.g. items, creatures and areas
+    Data,
+    Other,
+}
+
+impl std::fmt::Display for ResourceType {
+    /// Writes the uppercase extension, or `UNKNOWN(0x...)` with the type value if it is unknown
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.get_extension() {
+            Some(ext) => write!(f, "{}", ext.to_uppercase()),
+            None => write!(f, "UNKNOWN({:#x})", self.to_u16()),
+        }
+    }
+}
+
+impl std::str::FromStr for ResourceType {
+    type Err = std::convert::Infallible;
+
+    /// Parses an extension as `from_extension` does, or the `UNKNOWN(0x...)` form written by `Display`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let unknown = s
+            .strip_prefix("UNKNOWN(0x")
+            .and_then(|s| s.strip_suffix(')'))
+            .and_then(|value| u16::from_str_radix(value, 16).ok());
+        Ok(match unknown {
+            Some(value) => ResourceType::from(value),
+            None => ResourceType::from_extension(s),
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use insta::assert_json_snapshot;
 
-    use crate::{fs::CaseInsensitiveFS, test_utils::ALL_RESOURCES_DIRS};
+    use crate::{
+        fs::CaseInsensitiveFS,
+        test_utils::{ALL_RESOURCES_DIRS, BG_RESOURCES_DIR},
+    };
 
     use super::*;
 
@@ -464,10 +741,148 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_resource_entry_locator() {
+        let path = CaseInsensitiveFS::new(BG_RESOURCES_DIR)
+            .unwrap()
+            .get_path(&CaseInsensitivePath::new("CHITIN.KEY"))
+            .unwrap();
+        let key = KeyImporter::import(&DataSource::new(path)).unwrap();
+        let entry = |name: &str, ty: ResourceType| {
+            key.resource_entries
+                .iter()
+                .find(|entry| entry.resource_name == name && entry.r#type == ty)
+                .unwrap()
+        };
+
+        let action = entry("ACTION", ResourceType::Ids);
+        assert_eq!(action.locator, 0);
+        assert_eq!(action.bif_index(), 0);
+        assert_eq!(action.file_index(), 0);
+        assert_eq!(action.tileset_index(), 0);
+
+        let tis = entry("AR3802", ResourceType::Tis);
+        assert_eq!(tis.locator, 87048192);
+        assert_eq!(tis.bif_index(), 83);
+        assert_eq!(tis.file_index(), 0);
+        assert_eq!(tis.tileset_index(), 1);
+        assert_eq!(
+            key.bif_entries[tis.bif_index()].file_name.as_str(),
+            "data/area380x.bif"
+        );
+
+        let mos = entry("AR3802", ResourceType::Mos);
+        assert_eq!(mos.locator, 87031812);
+        assert_eq!(mos.bif_index(), 83);
+        assert_eq!(mos.file_index(), 4);
+        assert_eq!(mos.tileset_index(), 0);
+    }
+
     #[test]
     fn test_resource_type_roundtrip() {
+        for resource_type in ResourceType::all() {
+            assert_eq!(ResourceType::from(resource_type.to_u16()), *resource_type);
+        }
+        assert_eq!(ResourceType::from(0x7d0), ResourceType::Unknown(0x7d0));
+        assert_eq!(ResourceType::Unknown(0x7d0).to_u16(), 0x7d0);
+    }
+
+    #[test]
+    fn test_resource_type_all() {
+        let values = ResourceType::all()
+            .iter()
+            .map(|resource_type| resource_type.to_u16())
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(values.len(), ResourceType::all().len());
+        assert!(
+            !ResourceType::all()
+                .iter()
+                .any(|resource_type| matches!(resource_type, ResourceType::Unknown(_)))
+        );
+        // Every value mapped to a known type is listed
+        let known = (0..16u16.pow(3))
+            .filter(|i| !matches!(ResourceType::from(*i), ResourceType::Unknown(_)))
+            .count();
+        assert_eq!(known, ResourceType::all().len());
+    }
+
+    #[test]
+    fn test_resource_type_extension_roundtrip() {
+        for resource_type in ResourceType::all() {
+            let ext = resource_type.get_extension().unwrap();
+            assert_eq!(ResourceType::from_extension(ext), *resource_type);
+            assert_eq!(
+                ResourceType::from_extension(&ext.to_uppercase()),
+                *resource_type
+            );
+        }
+    }
+
+    #[test]
+    fn test_resource_type_display() {
+        assert_eq!(ResourceType::Itm.to_string(), "ITM");
+        assert_eq!(ResourceType::TwoDA.to_string(), "TWO");
+        assert_eq!(ResourceType::Pvrz.to_string(), "PVRZ");
+        assert_eq!(ResourceType::Unknown(0x7d0).to_string(), "UNKNOWN(0x7d0)");
+    }
+
+    #[test]
+    fn test_resource_type_from_str() {
+        assert_eq!("itm".parse(), Ok(ResourceType::Itm));
+        assert_eq!("PVRZ".parse(), Ok(ResourceType::Pvrz));
+        assert_eq!("txt".parse(), Ok(ResourceType::Unknown(0)));
+        assert_eq!("UNKNOWN(0x7d0)".parse(), Ok(ResourceType::Unknown(0x7d0)));
+
         for i in 0..16u16.pow(3) {
-            assert_eq!(ResourceType::from(i).to_u16(), i);
+            let resource_type = ResourceType::from(i);
+            assert_eq!(resource_type.to_string().parse(), Ok(resource_type));
         }
     }
+
+    #[test]
+    fn test_resource_type_category() {
+        assert_eq!(ResourceType::Bam.category(), ResourceCategory::Image);
+        assert_eq!(ResourceType::TwoDA.category(), ResourceCategory::Text);
+        assert_eq!(ResourceType::Acm.category(), ResourceCategory::Audio);
+        assert_eq!(ResourceType::Itm.category(), ResourceCategory::Data);
+        assert_eq!(ResourceType::Ttf.category(), ResourceCategory::Other);
+        assert_eq!(ResourceType::Unknown(0).category(), ResourceCategory::Other);
+
+        assert!(ResourceType::Pvrz.is_image());
+        assert!(!ResourceType::Pvrz.is_text());
+        assert!(ResourceType::Ids.is_text());
+        assert!(ResourceType::Wav.is_audio());
+        assert!(!ResourceType::Cre.is_audio());
+    }
+
+    #[test]
+    fn test_resource_type_compression() {
+        assert_eq!(ResourceType::Bam.typical_compression(), Some("BAMCV1  "));
+        assert_eq!(ResourceType::Mos.typical_compression(), Some("MOSCV1  "));
+        assert_eq!(ResourceType::TwoDA.typical_compression(), None);
+
+        assert!(ResourceType::Bam.may_be_compressed());
+        assert!(ResourceType::Mos.may_be_compressed());
+        assert!(!ResourceType::TwoDA.may_be_compressed());
+    }
+
+    #[test]
+    fn test_resource_type_from_path() {
+        assert_eq!(
+            ResourceType::from_path(Path::new("override/SW1H01.ITM")),
+            ResourceType::Itm
+        );
+        assert_eq!(
+            ResourceType::from_path(Path::new("override/xpcap.2da")),
+            ResourceType::TwoDA
+        );
+        assert_eq!(
+            ResourceType::from_path(Path::new("override/readme.txt")),
+            ResourceType::Unknown(0)
+        );
+        assert_eq!(
+            ResourceType::from_path(Path::new("override/noextension")),
+            ResourceType::Unknown(0)
+        );
+    }
 }
@@ -0,0 +1,173 @@
+use std::io::Read;
+
+use serde::{Deserialize, Serialize};
+
+use crate::datasource::{DataSource, Importer, Reader};
+
+/// The number of rooms on each side of the maze grid
+const GRID_SIZE: u32 = 8;
+
+/// The size of a room entry
+const ROOM_SIZE: u64 = 28;
+
+/// A MAZE file importer.
+///
+/// The file starts with the 8x8 grid of rooms, stored row by row, followed by the header.
+pub struct MazeImporter;
+
+impl Importer for MazeImporter {
+    type T = Maze;
+
+    fn import(source: &DataSource) -> std::io::Result<Maze> {
+        let mut reader = source.reader()?;
+
+        let mut rooms = Vec::with_capacity((GRID_SIZE * GRID_SIZE) as usize);
+        for _ in 0..GRID_SIZE * GRID_SIZE {
+            let override_area = reader.read_u32()? != 0;
+            let accessible = reader.read_u32()? != 0;
+            let valid = reader.read_u32()? != 0;
+            let trapped = reader.read_u32()? != 0;
+            let trap_type = reader.read_u32()?;
+            let walls = MazeWalls::from_bits_retain(reader.read_u16()?);
+            let visited = reader.read_u16()? != 0;
+            let _unknown = reader.read_u32()?;
+            rooms.push(MazeRoom {
+                override_area,
+                accessible,
+                valid,
+                trapped,
+                trap_type,
+                walls,
+                visited,
+            });
+        }
+
+        reader.set_position(ROOM_SIZE * (GRID_SIZE * GRID_SIZE) as u64)?;
+        let width = reader.read_u32()?;
+        let height = reader.read_u32()?;
+        if width > GRID_SIZE || height > GRID_SIZE {
+            return Err(std::io::Error::other(format!(
+                "Invalid maze size: {}x{}",
+                width, height
+            )));
+        }
+
+        Ok(Maze {
+            width,
+            height,
+            wizard_room: read_position(&mut reader)?,
+            nordom_room: read_position(&mut reader)?,
+            foyer_room: read_position(&mut reader)?,
+            engine_room: read_position(&mut reader)?,
+            traps_count: reader.read_u32()?,
+            initialized: reader.read_u32()? != 0,
+            rooms,
+        })
+    }
+}
+
+/// Reads the grid coordinates of a room
+fn read_position<R: Read>(reader: &mut Reader<R>) -> std::io::Result<MazePosition> {
+    Ok(MazePosition {
+        x: reader.read_u32()?,
+        y: reader.read_u32()?,
+    })
+}
+
+/// A MAZE file, the layout of the Modron maze of Planescape: Torment
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Maze {
+    /// The number of columns of the grid used by the maze
+    pub width: u32,
+    /// The number of rows of the grid used by the maze
+    pub height: u32,
+    pub wizard_room: MazePosition,
+    pub nordom_room: MazePosition,
+    /// The room where the maze is entered
+    pub foyer_room: MazePosition,
+    pub engine_room: MazePosition,
+    pub traps_count: u32,
+    pub initialized: bool,
+    /// The 8x8 grid of rooms, stored row by row
+    pub rooms: Vec<MazeRoom>,
+}
+
+impl Maze {
+    /// Returns the room at the given grid coordinates
+    pub fn room(&self, x: u32, y: u32) -> Option<&MazeRoom> {
+        if x >= GRID_SIZE || y >= GRID_SIZE {
+            return None;
+        }
+        self.rooms.get((y * GRID_SIZE + x) as usize)
+    }
+}
+
+/// The coordinates of a room in the maze grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MazePosition {
+    pub x: u32,
+    pub y: u32,
+}
+
+/// A room of the maze
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MazeRoom {
+    /// Whether the room uses its override area instead of the standard one
+    pub override_area: bool,
+    pub accessible: bool,
+    /// Whether the room is part of the maze
+    pub valid: bool,
+    pub trapped: bool,
+    pub trap_type: u32,
+    /// The sides of the room closed by a wall, the other ones are exits
+    pub walls: MazeWalls,
+    pub visited: bool,
+}
+
+bitflags::bitflags! {
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    pub struct MazeWalls: u16 {
+        const North = 1 << 0;
+        const East = 1 << 1;
+        const South = 1 << 2;
+        const West = 1 << 3;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::RESOURCES_DIR;
+    use insta::assert_json_snapshot;
+    use std::path::Path;
+
+    #[test]
+    fn test_parse_maze() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/MAZE/MODRON.MAZE"
+        )));
+
+        let maze = MazeImporter::import(&data).unwrap();
+
+        assert_eq!((maze.width, maze.height), (5, 4));
+        assert_eq!(maze.rooms.len(), 64);
+        assert_eq!(maze.wizard_room, MazePosition { x: 4, y: 3 });
+        assert_eq!(maze.nordom_room, MazePosition { x: 1, y: 2 });
+        assert_eq!(maze.traps_count, 1);
+
+        let corner = maze.room(0, 0).unwrap();
+        assert!(corner.valid && corner.visited);
+        assert_eq!(corner.walls, MazeWalls::North | MazeWalls::West);
+
+        let trapped = maze.room(3, 2).unwrap();
+        assert!(trapped.trapped);
+        assert_eq!(trapped.trap_type, 2);
+        assert!(trapped.walls.is_empty());
+
+        assert!(maze.room(2, 1).unwrap().override_area);
+        assert!(!maze.room(6, 6).unwrap().valid);
+        assert!(maze.room(8, 0).is_none());
+
+        assert_json_snapshot!(maze);
+    }
+}
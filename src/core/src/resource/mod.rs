@@ -1,11 +1,45 @@
+pub mod ability;
+pub mod acm;
+pub mod are;
 pub mod bam;
+pub mod bam_font;
+pub mod bcs;
 pub mod bif;
 pub mod bmp;
+pub mod chu;
 pub mod common;
+pub mod cre;
+pub mod dlg;
+pub mod eff;
+pub mod effect;
+pub mod fnt;
+pub mod gam;
+pub mod glsl;
+pub mod ids;
+pub mod ini;
+pub mod itm;
 pub mod key;
+pub mod maze;
+pub mod mos;
+pub mod mve;
+pub mod plt;
+pub mod png;
+pub mod pro;
 pub mod pvr;
+pub mod sav;
+pub mod spl;
+pub mod src;
+pub mod sto;
+pub mod tga;
+pub mod tlk;
+pub mod toh;
+pub mod ttf;
 pub mod two_da;
+pub mod vvc;
+pub mod wav;
 pub mod wed;
+pub mod wfx;
+pub mod wmp;
 
 #[cfg(test)]
 pub mod test_utils;
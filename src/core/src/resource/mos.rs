@@ -0,0 +1,224 @@
+use std::io::{BufRead, Seek};
+
+use image::{ImageBuffer, Rgba};
+
+use crate::{
+    datasource::{DataSource, Importer, Reader},
+    resource::common::Rgb,
+};
+
+pub const MOS_V1_SIGNATURE: &str = "MOS V1  ";
+pub const MOSC_SIGNATURE: &str = "MOSCV1  ";
+
+/// A MOS file importer
+pub struct MosImporter;
+
+impl Importer for MosImporter {
+    type T = Mos;
+
+    fn import(source: &DataSource) -> std::io::Result<Mos> {
+        let reader = &mut source.reader()?;
+        Self::from_reader(reader)
+    }
+}
+
+impl MosImporter {
+    /// Imports a MOS V1 file, decompressing it first if it is a MOSC file
+    fn from_reader<R: BufRead + Seek>(reader: &mut Reader<R>) -> std::io::Result<Mos> {
+        let position = reader.position()?;
+        let signature = reader.read_string(8)?;
+
+        match signature.as_str() {
+            MOS_V1_SIGNATURE => {
+                reader.set_position(position)?;
+                Self::parse(reader)
+            }
+            MOSC_SIGNATURE => {
+                let _uncompressed_size = reader.read_u32()?;
+                let mut uncompressed_reader = reader.as_zip_reader().decode_all()?;
+                Self::parse(&mut uncompressed_reader)
+            }
+            signature => Err(std::io::Error::other(format!(
+                "Unsupported MOS file: {}",
+                signature
+            ))),
+        }
+    }
+
+    fn parse<R: BufRead + Seek>(reader: &mut Reader<R>) -> std::io::Result<Mos> {
+        let start = reader.position()?;
+        let _signature = reader.read_string(8)?;
+
+        let width = reader.read_u16()? as u32;
+        let height = reader.read_u16()? as u32;
+        let columns = reader.read_u16()? as u32;
+        let rows = reader.read_u16()? as u32;
+        let block_size = reader.read_u32()?;
+        let palettes_offset = reader.read_u32()? as u64;
+
+        // The tiles cover the image, the ones of the last column and row are cropped to its size
+        if block_size == 0
+            || columns != width.div_ceil(block_size)
+            || rows != height.div_ceil(block_size)
+        {
+            return Err(std::io::Error::other(format!(
+                "Wrong tile layout: {columns}x{rows} tiles of {block_size} pixels for a {width}x{height} image"
+            )));
+        }
+        let tiles_count = (columns * rows) as usize;
+
+        // Each tile has its own 256 colors palette
+        let palettes = reader.read_table(start + palettes_offset, tiles_count, |reader| {
+            let mut palette = Vec::with_capacity(256);
+            for _ in 0..256 {
+                let b = reader.read_u8()?;
+                let g = reader.read_u8()?;
+                let r = reader.read_u8()?;
+                let _alpha = reader.read_u8()?;
+                palette.push(Rgb {
+                    r,
+                    g,
+                    b,
+                    alpha: 255,
+                });
+            }
+            Ok(palette)
+        })?;
+
+        // The tile offsets follow the palettes
+        let tile_offsets_offset = reader.position()?;
+        let tile_offsets = reader.read_table(tile_offsets_offset, tiles_count, |reader| {
+            Ok(reader.read_u32()? as u64)
+        })?;
+        let tiles_data_offset = reader.position()?;
+
+        let mut tiles = Vec::with_capacity(tiles_count);
+        for (index, (palette, tile_offset)) in palettes.into_iter().zip(tile_offsets).enumerate() {
+            let column = index as u32 % columns;
+            let row = index as u32 / columns;
+            let tile_width = block_size.min(width - column * block_size);
+            let tile_height = block_size.min(height - row * block_size);
+            let pixels_count = tile_width as u64 * tile_height as u64;
+
+            reader.set_position(tiles_data_offset + tile_offset)?;
+            let pixel_palette_indexes = reader.take_to_vec(pixels_count)?;
+            if pixel_palette_indexes.len() as u64 != pixels_count {
+                return Err(std::io::Error::other(format!(
+                    "Tile {} is truncated",
+                    index
+                )));
+            }
+
+            tiles.push(MosTile {
+                width: tile_width,
+                height: tile_height,
+                palette,
+                pixel_palette_indexes,
+            });
+        }
+
+        Ok(Mos {
+            width,
+            height,
+            columns,
+            rows,
+            block_size,
+            tiles,
+        })
+    }
+}
+
+/// A MOS V1 file, an image split in paletted tiles
+#[derive(Debug, PartialEq, Eq)]
+pub struct Mos {
+    pub width: u32,
+    pub height: u32,
+    pub columns: u32,
+    pub rows: u32,
+    /// The size of the tiles side, usually 64 pixels
+    pub block_size: u32,
+    /// The tiles, row by row
+    pub tiles: Vec<MosTile>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct MosTile {
+    pub width: u32,
+    pub height: u32,
+    /// The colors palette of the tile
+    pub palette: Vec<Rgb>,
+    /// The indexes of the pixels in the palette
+    pub pixel_palette_indexes: Vec<u8>,
+}
+
+impl Mos {
+    /// Exports the MOS to an image.
+    /// Pure green pixels are exported as fully transparent.
+    pub fn to_image(&self) -> ImageBuffer<Rgba<u8>, Vec<u8>> {
+        let mut image = ImageBuffer::new(self.width, self.height);
+        for (index, tile) in self.tiles.iter().enumerate() {
+            let origin_x = (index as u32 % self.columns) * self.block_size;
+            let origin_y = (index as u32 / self.columns) * self.block_size;
+            for y in 0..tile.height {
+                for x in 0..tile.width {
+                    let palette_index = tile.pixel_palette_indexes[(y * tile.width + x) as usize];
+                    let p = &tile.palette[palette_index as usize];
+                    let alpha = if (p.r, p.g, p.b) == (0, 255, 0) {
+                        0
+                    } else {
+                        255
+                    };
+                    image.put_pixel(origin_x + x, origin_y + y, Rgba([p.r, p.g, p.b, alpha]));
+                }
+            }
+        }
+        image
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::RESOURCES_DIR;
+    use std::path::Path;
+
+    #[test]
+    fn test_parse_mos() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/WMP/WORLDMAP.MOS"
+        )));
+
+        let mos = MosImporter::import(&data).unwrap();
+
+        assert_eq!((mos.width, mos.height), (200, 150));
+        assert_eq!((mos.columns, mos.rows), (4, 3));
+        assert_eq!(mos.tiles.len(), 12);
+        assert_eq!((mos.tiles[3].width, mos.tiles[3].height), (8, 64));
+        assert_eq!((mos.tiles[11].width, mos.tiles[11].height), (8, 22));
+
+        let image = mos.to_image();
+        assert_eq!(image.dimensions(), (200, 150));
+        // The top-left pixel of the second tile uses the first color of its palette
+        assert_eq!(image.get_pixel(64, 0), &Rgba([20, 0, 100, 255]));
+    }
+
+    #[test]
+    fn test_import_mos_with_wrong_tile_layout() {
+        let mut bytes =
+            std::fs::read(format!("{RESOURCES_DIR}/resources/WMP/WORLDMAP.MOS")).unwrap();
+        // 4 columns of 128 pixels do not fit the 200 pixels wide image
+        bytes[16..20].copy_from_slice(&128u32.to_le_bytes());
+
+        let err = MosImporter::import(&DataSource::new(bytes)).unwrap_err();
+        assert!(err.to_string().starts_with("Wrong tile layout"));
+
+        // A 65535x65535 grid of 1 pixel tiles is rejected before reading the missing palettes
+        let mut bytes = b"MOS V1  ".to_vec();
+        for value in [u16::MAX; 4] {
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes.extend(1u32.to_le_bytes());
+        bytes.extend(24u32.to_le_bytes());
+        assert!(MosImporter::import(&DataSource::new(bytes)).is_err());
+    }
+}
@@ -0,0 +1,736 @@
+use image::{ImageBuffer, Rgba};
+
+use crate::datasource::{DataSource, Importer};
+
+/// The signature at the start of an MVE file
+const MVE_SIGNATURE: &[u8; 20] = b"Interplay MVE File\x1a\0";
+
+/// The size of the MVE file header: the signature followed by three constant words
+const MVE_HEADER_SIZE: u64 = 26;
+
+/// The size of the side of the square blocks of the video frames
+const BLOCK_SIZE: usize = 8;
+
+/// The opcodes of the MVE stream used by the importer
+const OPCODE_END_OF_STREAM: u8 = 0x00;
+const OPCODE_INIT_VIDEO_BUFFERS: u8 = 0x05;
+const OPCODE_VIDEO_DATA_06: u8 = 0x06;
+const OPCODE_SET_PALETTE: u8 = 0x0c;
+const OPCODE_SET_DECODING_MAP: u8 = 0x0f;
+const OPCODE_VIDEO_DATA_10: u8 = 0x10;
+const OPCODE_VIDEO_DATA_11: u8 = 0x11;
+
+/// The size of the header of the video data, before the block opcodes data
+const VIDEO_DATA_HEADER_SIZE: usize = 14;
+
+/// An MVE file importer.
+///
+/// An MVE file is a sequence of chunks, each one a sequence of opcodes.
+/// Only the video opcodes are read, the audio is ignored.
+pub struct MveImporter;
+
+impl Importer for MveImporter {
+    type T = Mve;
+
+    fn import(source: &DataSource) -> std::io::Result<Mve> {
+        let mut reader = source.reader()?;
+
+        let signature = reader.read_exact::<20>()?;
+        if &signature != MVE_SIGNATURE {
+            return Err(std::io::Error::other(format!(
+                "Wrong file type: {}",
+                String::from_utf8_lossy(&signature)
+            )));
+        }
+        reader.set_position(MVE_HEADER_SIZE)?;
+
+        let mut mve = Mve {
+            width: 0,
+            height: 0,
+            true_color: false,
+            events: Vec::new(),
+        };
+        let mut decoding_map = Vec::new();
+
+        'chunks: loop {
+            let chunk_size = match reader.read_u16() {
+                Ok(size) => size as u64,
+                Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => break,
+                Err(err) => return Err(err),
+            };
+            let _chunk_type = reader.read_u16()?;
+
+            let mut chunk_read = 0;
+            while chunk_read < chunk_size {
+                let opcode_size = reader.read_u16()? as u64;
+                let opcode = reader.read_u8()?;
+                let version = reader.read_u8()?;
+                let data = reader.take_to_vec(opcode_size)?;
+                if (data.len() as u64) < opcode_size {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::UnexpectedEof,
+                        format!("MVE opcode 0x{:02x} is truncated", opcode),
+                    ));
+                }
+                chunk_read += 4 + opcode_size;
+
+                match opcode {
+                    OPCODE_END_OF_STREAM => break 'chunks,
+                    OPCODE_INIT_VIDEO_BUFFERS => {
+                        mve.width = le16(&data, 0)? as u32 * BLOCK_SIZE as u32;
+                        mve.height = le16(&data, 2)? as u32 * BLOCK_SIZE as u32;
+                        mve.true_color = version >= 2 && le16(&data, 6)? != 0;
+                    }
+                    OPCODE_SET_PALETTE => {
+                        let start = le16(&data, 0)? as usize;
+                        let count = le16(&data, 2)? as usize;
+                        let colors = data
+                            .get(4..4 + count * 3)
+                            .ok_or_else(|| std::io::Error::other("MVE palette is truncated"))?
+                            .chunks_exact(3)
+                            .map(|rgb| [scale_6bit(rgb[0]), scale_6bit(rgb[1]), scale_6bit(rgb[2])])
+                            .collect();
+                        mve.events.push(MveEvent::Palette { start, colors });
+                    }
+                    OPCODE_SET_DECODING_MAP => decoding_map = data,
+                    OPCODE_VIDEO_DATA_11 => mve.events.push(MveEvent::Video {
+                        decoding_map: std::mem::take(&mut decoding_map),
+                        data,
+                    }),
+                    OPCODE_VIDEO_DATA_06 | OPCODE_VIDEO_DATA_10 => {
+                        return Err(std::io::Error::other(format!(
+                            "Unsupported MVE video data opcode: 0x{:02x}",
+                            opcode
+                        )));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(mve)
+    }
+}
+
+/// An MVE movie.
+/// The frames are stored compressed and decoded on demand.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Mve {
+    pub width: u32,
+    pub height: u32,
+    /// Whether the pixels are 16 bits RGB555 values instead of 8 bits palette indexes
+    pub true_color: bool,
+    events: Vec<MveEvent>,
+}
+
+/// A change of the video state, in stream order
+#[derive(Debug, PartialEq, Eq)]
+enum MveEvent {
+    Palette {
+        start: usize,
+        colors: Vec<[u8; 3]>,
+    },
+    Video {
+        /// The opcode of each 8x8 block, 4 bits each with the low nibble first
+        decoding_map: Vec<u8>,
+        data: Vec<u8>,
+    },
+}
+
+impl Mve {
+    /// Returns the number of video frames
+    pub fn frame_count(&self) -> usize {
+        self.events
+            .iter()
+            .filter(|event| matches!(event, MveEvent::Video { .. }))
+            .count()
+    }
+
+    /// Decodes a video frame.
+    /// Each frame is built upon the previous ones, so the movie is decoded from its start.
+    pub fn frame(&self, index: usize) -> image::ImageResult<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+        let pixels_count = self.width as usize * self.height as usize;
+        let mut palette = [[0u8; 3]; 256];
+        let mut last_frame = vec![0u16; pixels_count];
+        let mut second_last_frame = vec![0u16; pixels_count];
+        let mut frame_index = 0;
+
+        for event in &self.events {
+            match event {
+                MveEvent::Palette { start, colors } => {
+                    for (entry, color) in palette.iter_mut().skip(*start).zip(colors) {
+                        *entry = *color;
+                    }
+                }
+                MveEvent::Video { decoding_map, data } => {
+                    let frame = FrameDecoder::new(self, data)?.decode(
+                        decoding_map,
+                        &last_frame,
+                        &second_last_frame,
+                    )?;
+                    if frame_index == index {
+                        return Ok(self.to_image(&frame, &palette));
+                    }
+                    second_last_frame = std::mem::replace(&mut last_frame, frame);
+                    frame_index += 1;
+                }
+            }
+        }
+
+        Err(std::io::Error::other(format!("Frame {} not found.", index)))?
+    }
+
+    /// Renders the pixels of a decoded frame
+    fn to_image(&self, frame: &[u16], palette: &[[u8; 3]; 256]) -> ImageBuffer<Rgba<u8>, Vec<u8>> {
+        ImageBuffer::from_fn(self.width, self.height, |x, y| {
+            let pixel = frame[(y * self.width + x) as usize];
+            if self.true_color {
+                let scale = |value: u16| {
+                    let value = (value & 0x1f) as u8;
+                    (value << 3) | (value >> 2)
+                };
+                Rgba([scale(pixel >> 10), scale(pixel >> 5), scale(pixel), 255])
+            } else {
+                let [r, g, b] = palette[pixel as usize & 0xff];
+                Rgba([r, g, b, 255])
+            }
+        })
+    }
+}
+
+/// Converts a 6 bits palette component to 8 bits
+fn scale_6bit(value: u8) -> u8 {
+    let value = value & 0x3f;
+    (value << 2) | (value >> 4)
+}
+
+/// Reads a little endian u16 from a slice
+fn le16(data: &[u8], offset: usize) -> std::io::Result<u16> {
+    data.get(offset..offset + 2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "MVE opcode data is truncated",
+            )
+        })
+}
+
+/// Decodes the blocks of a video frame.
+///
+/// Each block is either copied from a previous frame, or from another part of the current one,
+/// or encoded with a few colors. The 8 bits and the 16 bits videos share the same block encodings:
+/// the pixels are bytes or words, and the variant of an encoding is selected comparing two colors
+/// in 8 bits, or by the high bit of the first one in 16 bits.
+struct FrameDecoder<'a> {
+    width: usize,
+    height: usize,
+    true_color: bool,
+    data: &'a [u8],
+    position: usize,
+    /// The 16 bits videos store the motion vectors in a separate stream
+    motion_position: usize,
+}
+
+impl<'a> FrameDecoder<'a> {
+    fn new(mve: &Mve, data: &'a [u8]) -> std::io::Result<Self> {
+        let mut decoder = FrameDecoder {
+            width: mve.width as usize,
+            height: mve.height as usize,
+            true_color: mve.true_color,
+            data,
+            position: VIDEO_DATA_HEADER_SIZE,
+            motion_position: 0,
+        };
+        if decoder.true_color {
+            let offset = decoder.le16()? as usize;
+            decoder.motion_position = VIDEO_DATA_HEADER_SIZE + offset;
+        }
+        Ok(decoder)
+    }
+
+    fn bytes<const N: usize>(&mut self) -> std::io::Result<[u8; N]> {
+        let bytes = self
+            .data
+            .get(self.position..self.position + N)
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "MVE video data is truncated",
+                )
+            })?;
+        self.position += N;
+        Ok(bytes.try_into().unwrap())
+    }
+
+    fn byte(&mut self) -> std::io::Result<u8> {
+        Ok(self.bytes::<1>()?[0])
+    }
+
+    fn le16(&mut self) -> std::io::Result<u16> {
+        Ok(u16::from_le_bytes(self.bytes()?))
+    }
+
+    fn le32(&mut self) -> std::io::Result<u64> {
+        Ok(u32::from_le_bytes(self.bytes()?) as u64)
+    }
+
+    fn le64(&mut self) -> std::io::Result<u64> {
+        Ok(u64::from_le_bytes(self.bytes()?))
+    }
+
+    /// Reads a pixel, a palette index or a RGB555 value
+    fn pixel(&mut self) -> std::io::Result<u16> {
+        if self.true_color {
+            self.le16()
+        } else {
+            self.byte().map(u16::from)
+        }
+    }
+
+    fn pixels<const N: usize>(&mut self) -> std::io::Result<[u16; N]> {
+        let mut pixels = [0; N];
+        for pixel in &mut pixels {
+            *pixel = self.pixel()?;
+        }
+        Ok(pixels)
+    }
+
+    /// Reads a byte of a motion vector
+    fn motion_byte(&mut self) -> std::io::Result<u8> {
+        if !self.true_color {
+            return self.byte();
+        }
+        let byte = *self.data.get(self.motion_position).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "MVE motion vectors are truncated",
+            )
+        })?;
+        self.motion_position += 1;
+        Ok(byte)
+    }
+
+    /// Returns whether the first variant of a block encoding is used
+    fn first_variant(&self, p0: u16, p1: u16) -> bool {
+        if self.true_color {
+            p0 & 0x8000 == 0
+        } else {
+            p0 <= p1
+        }
+    }
+
+    /// Decodes a frame, given the opcode of each block and the two previous frames
+    fn decode(
+        mut self,
+        decoding_map: &[u8],
+        last_frame: &[u16],
+        second_last_frame: &[u16],
+    ) -> std::io::Result<Vec<u16>> {
+        let mut frame = vec![0u16; self.width * self.height];
+        let blocks_x = self.width / BLOCK_SIZE;
+        let blocks_y = self.height / BLOCK_SIZE;
+
+        for block in 0..blocks_x * blocks_y {
+            let opcode = decoding_map
+                .get(block / 2)
+                .map(|byte| (byte >> (4 * (block % 2))) & 0x0f)
+                .ok_or_else(|| std::io::Error::other("MVE decoding map is truncated"))?;
+            let block_x = (block % blocks_x) * BLOCK_SIZE;
+            let block_y = (block / blocks_x) * BLOCK_SIZE;
+            let mut pixels = [[0u16; BLOCK_SIZE]; BLOCK_SIZE];
+
+            match (opcode, self.true_color) {
+                (0x0, _) => {
+                    pixels = self.copy_block(last_frame, block_x, block_y, 0, 0)?;
+                }
+                (0x1, _) | (0xf, true) => {
+                    pixels = self.copy_block(second_last_frame, block_x, block_y, 0, 0)?;
+                }
+                (0x2, _) => {
+                    let (x, y) = motion_vector(self.motion_byte()?);
+                    pixels = self.copy_block(second_last_frame, block_x, block_y, x, y)?;
+                }
+                (0x3, _) => {
+                    let (x, y) = motion_vector(self.motion_byte()?);
+                    pixels = self.copy_block(&frame, block_x, block_y, -x, -y)?;
+                }
+                (0x4, _) => {
+                    let byte = self.motion_byte()?;
+                    let x = -8 + (byte & 0x0f) as i32;
+                    let y = -8 + (byte >> 4) as i32;
+                    pixels = self.copy_block(last_frame, block_x, block_y, x, y)?;
+                }
+                (0x5, _) => {
+                    let x = self.motion_byte()? as i8 as i32;
+                    let y = self.motion_byte()? as i8 as i32;
+                    pixels = self.copy_block(last_frame, block_x, block_y, x, y)?;
+                }
+                (0x6, true) => {
+                    let x = self.byte()? as i8 as i32;
+                    let y = self.byte()? as i8 as i32;
+                    pixels = self.copy_block(second_last_frame, block_x, block_y, x, y)?;
+                }
+                (0x7, _) => self.two_colors(&mut pixels)?,
+                (0x8, _) => self.two_colors_halves(&mut pixels)?,
+                (0x9, _) => self.four_colors(&mut pixels)?,
+                (0xa, _) => self.four_colors_halves(&mut pixels)?,
+                (0xb, _) => {
+                    for pixel in pixels.iter_mut().flatten() {
+                        *pixel = self.pixel()?;
+                    }
+                }
+                (0xc, _) => {
+                    for y in (0..BLOCK_SIZE).step_by(2) {
+                        for x in (0..BLOCK_SIZE).step_by(2) {
+                            fill(&mut pixels, x, y, 2, 2, self.pixel()?);
+                        }
+                    }
+                }
+                (0xd, _) => {
+                    for y in (0..BLOCK_SIZE).step_by(4) {
+                        for x in (0..BLOCK_SIZE).step_by(4) {
+                            fill(&mut pixels, x, y, 4, 4, self.pixel()?);
+                        }
+                    }
+                }
+                (0xe, _) => {
+                    let pixel = self.pixel()?;
+                    fill(&mut pixels, 0, 0, BLOCK_SIZE, BLOCK_SIZE, pixel);
+                }
+                (0xf, false) => {
+                    let p = self.pixels::<2>()?;
+                    for (y, row) in pixels.iter_mut().enumerate() {
+                        for (x, pixel) in row.iter_mut().enumerate() {
+                            *pixel = p[(x + y) & 1];
+                        }
+                    }
+                }
+                (opcode, _) => {
+                    return Err(std::io::Error::other(format!(
+                        "Unsupported MVE block opcode: 0x{:x}",
+                        opcode
+                    )));
+                }
+            }
+
+            for (y, row) in pixels.iter().enumerate() {
+                let start = (block_y + y) * self.width + block_x;
+                frame[start..start + BLOCK_SIZE].copy_from_slice(row);
+            }
+        }
+
+        Ok(frame)
+    }
+
+    /// Copies the block moved by `x`, `y` from a frame
+    fn copy_block(
+        &self,
+        frame: &[u16],
+        block_x: usize,
+        block_y: usize,
+        x: i32,
+        y: i32,
+    ) -> std::io::Result<[[u16; BLOCK_SIZE]; BLOCK_SIZE]> {
+        let source_x = block_x as i32 + x;
+        let source_y = block_y as i32 + y;
+        if source_x < 0
+            || source_y < 0
+            || source_x as usize + BLOCK_SIZE > self.width
+            || source_y as usize + BLOCK_SIZE > self.height
+        {
+            return Err(std::io::Error::other(format!(
+                "MVE motion vector ({}, {}) of block ({}, {}) is out of the frame",
+                x, y, block_x, block_y
+            )));
+        }
+
+        let mut pixels = [[0u16; BLOCK_SIZE]; BLOCK_SIZE];
+        for (row_index, row) in pixels.iter_mut().enumerate() {
+            let start = (source_y as usize + row_index) * self.width + source_x as usize;
+            row.copy_from_slice(&frame[start..start + BLOCK_SIZE]);
+        }
+        Ok(pixels)
+    }
+
+    /// Block opcode 0x7: each pixel, or each 2x2 square, is one of two colors
+    fn two_colors(&mut self, pixels: &mut [[u16; 8]; 8]) -> std::io::Result<()> {
+        let p = self.pixels::<2>()?;
+        if self.first_variant(p[0], p[1]) {
+            for row in pixels.iter_mut() {
+                let flags = self.byte()?;
+                for (x, pixel) in row.iter_mut().enumerate() {
+                    *pixel = p[((flags >> x) & 1) as usize];
+                }
+            }
+        } else {
+            let flags = self.le16()?;
+            for square in 0..16 {
+                let color = p[((flags >> square) & 1) as usize];
+                fill(pixels, (square % 4) * 2, (square / 4) * 2, 2, 2, color);
+            }
+        }
+        Ok(())
+    }
+
+    /// Block opcode 0x8: the quadrants, or the halves, of the block use two colors each
+    fn two_colors_halves(&mut self, pixels: &mut [[u16; 8]; 8]) -> std::io::Result<()> {
+        let mut p = self.pixels::<2>()?;
+        if self.first_variant(p[0], p[1]) {
+            // The quadrants are top left, bottom left, top right and bottom right
+            for quadrant in 0..4 {
+                if quadrant > 0 {
+                    p = self.pixels::<2>()?;
+                }
+                let flags = self.le16()? as u64;
+                paint(
+                    pixels,
+                    (quadrant / 2) * 4,
+                    (quadrant % 2) * 4,
+                    4,
+                    4,
+                    &p,
+                    1,
+                    flags,
+                );
+            }
+        } else {
+            let flags = self.le32()?;
+            let q = self.pixels::<2>()?;
+            let vertical = self.first_variant(q[0], q[1]);
+            let (width, height) = if vertical { (4, 8) } else { (8, 4) };
+            paint(pixels, 0, 0, width, height, &p, 1, flags);
+            let flags = self.le32()?;
+            let (x, y) = if vertical { (4, 0) } else { (0, 4) };
+            paint(pixels, x, y, width, height, &q, 1, flags);
+        }
+        Ok(())
+    }
+
+    /// Block opcode 0x9: each pixel, 2x2 square, or 2x1 or 1x2 rectangle is one of four colors
+    fn four_colors(&mut self, pixels: &mut [[u16; 8]; 8]) -> std::io::Result<()> {
+        let p = self.pixels::<4>()?;
+        let first = self.first_variant(p[0], p[1]);
+        let second = self.first_variant(p[2], p[3]);
+        match (first, second) {
+            (true, true) => {
+                for y in 0..BLOCK_SIZE {
+                    let flags = self.le16()? as u64;
+                    paint(pixels, 0, y, 8, 1, &p, 2, flags);
+                }
+            }
+            (true, false) => {
+                let flags = self.le32()?;
+                for square in 0..16 {
+                    let color = p[((flags >> (square * 2)) & 3) as usize];
+                    fill(pixels, (square % 4) * 2, (square / 4) * 2, 2, 2, color);
+                }
+            }
+            (false, horizontal) => {
+                let flags = self.le64()?;
+                let (width, height) = if horizontal { (2, 1) } else { (1, 2) };
+                for rectangle in 0..32 {
+                    let color = p[((flags >> (rectangle * 2)) & 3) as usize];
+                    let columns = BLOCK_SIZE / width;
+                    fill(
+                        pixels,
+                        (rectangle % columns) * width,
+                        (rectangle / columns) * height,
+                        width,
+                        height,
+                        color,
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Block opcode 0xa: the quadrants, or the halves, of the block use four colors each
+    fn four_colors_halves(&mut self, pixels: &mut [[u16; 8]; 8]) -> std::io::Result<()> {
+        let mut p = self.pixels::<4>()?;
+        if self.first_variant(p[0], p[1]) {
+            for quadrant in 0..4 {
+                if quadrant > 0 {
+                    p = self.pixels::<4>()?;
+                }
+                let flags = self.le32()?;
+                paint(
+                    pixels,
+                    (quadrant / 2) * 4,
+                    (quadrant % 2) * 4,
+                    4,
+                    4,
+                    &p,
+                    2,
+                    flags,
+                );
+            }
+        } else {
+            let flags = self.le64()?;
+            let q = self.pixels::<4>()?;
+            let vertical = self.first_variant(q[0], q[1]);
+            let (width, height) = if vertical { (4, 8) } else { (8, 4) };
+            paint(pixels, 0, 0, width, height, &p, 2, flags);
+            let flags = self.le64()?;
+            let (x, y) = if vertical { (4, 0) } else { (0, 4) };
+            paint(pixels, x, y, width, height, &q, 2, flags);
+        }
+        Ok(())
+    }
+}
+
+/// Decodes the motion vector of the block opcodes 0x2 and 0x3
+fn motion_vector(byte: u8) -> (i32, i32) {
+    let byte = byte as i32;
+    if byte < 56 {
+        (8 + byte % 7, byte / 7)
+    } else {
+        (-14 + (byte - 56) % 29, 8 + (byte - 56) / 29)
+    }
+}
+
+/// Fills a rectangle of a block with a color
+fn fill(pixels: &mut [[u16; 8]; 8], x: usize, y: usize, width: usize, height: usize, color: u16) {
+    for row in &mut pixels[y..y + height] {
+        row[x..x + width].fill(color);
+    }
+}
+
+/// Paints a rectangle of a block, row by row, with the colors selected by `bits` wide flags
+#[allow(clippy::too_many_arguments)]
+fn paint(
+    pixels: &mut [[u16; 8]; 8],
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+    colors: &[u16],
+    bits: usize,
+    mut flags: u64,
+) {
+    let mask = (1 << bits) - 1;
+    for row in &mut pixels[y..y + height] {
+        for pixel in &mut row[x..x + width] {
+            *pixel = colors[(flags & mask) as usize];
+            flags >>= bits;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::RESOURCES_DIR;
+    use std::path::Path;
+
+    #[test]
+    fn test_motion_vector() {
+        assert_eq!(motion_vector(0), (8, 0));
+        assert_eq!(motion_vector(55), (14, 7));
+        assert_eq!(motion_vector(56), (-14, 8));
+        assert_eq!(motion_vector(255), (11, 14));
+    }
+
+    #[test]
+    fn test_decode_palette_frames() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/MVE/BLOCKS8.MVE"
+        )));
+
+        let mve = MveImporter::import(&data).unwrap();
+        assert_eq!((mve.width, mve.height), (16, 16));
+        assert!(!mve.true_color);
+        assert_eq!(mve.frame_count(), 3);
+
+        let black = Rgba([0, 0, 0, 255]);
+        let red = Rgba([255, 0, 0, 255]);
+        let green = Rgba([0, 255, 0, 255]);
+        let blue = Rgba([0, 0, 255, 255]);
+
+        let first = mve.frame(0).unwrap();
+        assert_eq!(first.dimensions(), (16, 16));
+        // Block 0x0b: raw pixels, the palette index is the column modulo 4
+        assert_eq!(first.get_pixel(0, 0), &black);
+        assert_eq!(first.get_pixel(1, 5), &red);
+        assert_eq!(first.get_pixel(7, 7), &blue);
+        // Block 0x0e: a single color
+        assert_eq!(first.get_pixel(8, 0), &green);
+        assert_eq!(first.get_pixel(15, 7), &green);
+        // Block 0x07: two colors, a diagonal of the second one
+        assert_eq!(first.get_pixel(0, 8), &blue);
+        assert_eq!(first.get_pixel(1, 8), &red);
+        assert_eq!(first.get_pixel(5, 13), &blue);
+        // Block 0x0c: 2x2 squares, the color index is the square row
+        assert_eq!(first.get_pixel(8, 8), &black);
+        assert_eq!(first.get_pixel(9, 11), &red);
+        assert_eq!(first.get_pixel(15, 15), &blue);
+
+        let second = mve.frame(1).unwrap();
+        // Block 0x00 and 0x04: copied from the previous frame
+        for (x, y) in [(0, 0), (1, 5), (7, 7), (8, 0), (15, 7)] {
+            assert_eq!(second.get_pixel(x, y), first.get_pixel(x, y));
+        }
+        // Block 0x0d: a color for each 4x4 quadrant
+        assert_eq!(second.get_pixel(0, 8), &red);
+        assert_eq!(second.get_pixel(7, 8), &green);
+        assert_eq!(second.get_pixel(0, 15), &blue);
+        assert_eq!(second.get_pixel(7, 15), &black);
+        // Block 0x03: copied from the block on its left
+        for y in 8..16 {
+            for x in 0..8 {
+                assert_eq!(second.get_pixel(x + 8, y), second.get_pixel(x, y));
+            }
+        }
+
+        // The palette changed: the second color is now white
+        let third = mve.frame(2).unwrap();
+        // Block 0x01: copied from two frames before
+        assert_eq!(third.get_pixel(0, 0), &black);
+        assert_eq!(third.get_pixel(1, 5), &Rgba([255, 255, 255, 255]));
+        assert_eq!(third.get_pixel(2, 0), &green);
+        // Block 0x0f: two colors in a checkerboard
+        assert_eq!(third.get_pixel(8, 0), &blue);
+        assert_eq!(third.get_pixel(9, 0), &black);
+        assert_eq!(third.get_pixel(8, 1), &black);
+
+        assert!(mve.frame(3).is_err());
+    }
+
+    #[test]
+    fn test_decode_true_color_frames() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/MVE/BLOCKS16.MVE"
+        )));
+
+        let mve = MveImporter::import(&data).unwrap();
+        assert_eq!((mve.width, mve.height), (16, 8));
+        assert!(mve.true_color);
+        assert_eq!(mve.frame_count(), 2);
+
+        let first = mve.frame(0).unwrap();
+        // Block 0x0e: RGB555 pure red
+        assert_eq!(first.get_pixel(0, 0), &Rgba([255, 0, 0, 255]));
+        // Block 0x07: the high bit of the first color selects the 2x2 squares variant
+        assert_eq!(first.get_pixel(8, 0), &Rgba([0, 0, 255, 255]));
+        assert_eq!(first.get_pixel(9, 1), &Rgba([0, 0, 255, 255]));
+        assert_eq!(first.get_pixel(10, 0), &Rgba([0, 255, 0, 255]));
+
+        // Block 0x05: the motion vector is read from the separate stream
+        let second = mve.frame(1).unwrap();
+        for y in 0..8 {
+            for x in 0..8 {
+                assert_eq!(second.get_pixel(x, y), first.get_pixel(x + 8, y));
+            }
+        }
+        assert_eq!(second.get_pixel(8, 0), &Rgba([255, 0, 0, 255]));
+    }
+
+    #[test]
+    fn test_import_mve_should_fail_if_wrong_signature() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/TGA/RLE32.TGA"
+        )));
+        assert!(MveImporter::import(&data).is_err());
+    }
+}
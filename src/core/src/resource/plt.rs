@@ -0,0 +1,183 @@
+use image::{ImageBuffer, Rgba};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    datasource::{DataSource, Importer},
+    resource::common::Rgb,
+};
+
+/// A PLT file importer
+pub struct PltImporter;
+
+impl Importer for PltImporter {
+    type T = Plt;
+
+    fn import(source: &DataSource) -> std::io::Result<Plt> {
+        let mut reader = source.reader()?;
+
+        let signature = reader.read_string(8)?;
+        if signature != "PLT V1  " {
+            return Err(std::io::Error::other(format!(
+                "Wrong file type: {}",
+                signature
+            )));
+        }
+
+        reader.skip(8)?; // unknown
+        let width = reader.read_u32()?;
+        let height = reader.read_u32()?;
+
+        // Each pixel is stored in 2 bytes
+        let data_size = (width as u64)
+            .checked_mul(height as u64)
+            .and_then(|pixels| pixels.checked_mul(2))
+            .ok_or_else(|| std::io::Error::other(format!("Wrong image size: {width}x{height}")))?;
+        let data = reader.take_to_vec(data_size)?;
+        if data.len() as u64 != data_size {
+            return Err(std::io::Error::other("The pixel data is truncated"));
+        }
+
+        // The rows are stored from the bottom to the top
+        let pixels = data
+            .rchunks((2 * width as usize).max(1))
+            .flat_map(|row| row.chunks_exact(2))
+            .map(|pixel| PltPixel {
+                color_index: pixel[0],
+                range_index: pixel[1],
+            })
+            .collect();
+
+        Ok(Plt {
+            width,
+            height,
+            pixels,
+        })
+    }
+}
+
+/// A PLT file, a paperdoll image whose colors are picked from the character color ranges
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Plt {
+    pub width: u32,
+    pub height: u32,
+    /// The pixels, row by row from the top
+    pub pixels: Vec<PltPixel>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PltPixel {
+    /// The index of the color in the color range
+    pub color_index: u8,
+    /// The index of the color range, see `PltColorRange`
+    pub range_index: u8,
+}
+
+/// The color ranges a PLT pixel can use, in the order expected by `Plt::to_image`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PltColorRange {
+    Skin = 0,
+    Hair = 1,
+    Metal = 2,
+    Leather = 3,
+    Armor = 4,
+    Minor = 5,
+    Major = 6,
+}
+
+impl Plt {
+    /// Exports the PLT to an image, resolving each pixel through the given color ranges,
+    /// indexed as `PltColorRange`.
+    /// Pixels referencing a missing color range are exported as fully transparent.
+    pub fn to_image(&self, color_ranges: &[[Rgb; 256]]) -> ImageBuffer<Rgba<u8>, Vec<u8>> {
+        ImageBuffer::from_fn(self.width, self.height, |x, y| {
+            let pixel = &self.pixels[(y * self.width + x) as usize];
+            match color_ranges.get(pixel.range_index as usize) {
+                Some(range) => {
+                    let p = &range[pixel.color_index as usize];
+                    Rgba([p.r, p.g, p.b, p.alpha])
+                }
+                None => Rgba([0, 0, 0, 0]),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{resource::test_utils::assert_images_are_equal, test_utils::RESOURCES_DIR};
+    use std::path::Path;
+
+    #[test]
+    fn test_parse_plt() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/PLT/DOLL01.PLT"
+        )));
+
+        let plt = PltImporter::import(&data).unwrap();
+
+        assert_eq!((plt.width, plt.height), (4, 3));
+        assert_eq!(plt.pixels.len(), 12);
+        // The first row of the file is the bottom row of the image
+        assert_eq!(
+            plt.pixels[8],
+            PltPixel {
+                color_index: 0,
+                range_index: PltColorRange::Skin as u8
+            }
+        );
+        assert_eq!(
+            plt.pixels[0],
+            PltPixel {
+                color_index: 200,
+                range_index: PltColorRange::Major as u8
+            }
+        );
+    }
+
+    #[test]
+    fn test_import_plt_with_wrong_size() {
+        let plt = |width: u32, height: u32, pixels: &[u8]| {
+            let mut bytes = b"PLT V1  ".to_vec();
+            bytes.extend([0; 8]);
+            bytes.extend(width.to_le_bytes());
+            bytes.extend(height.to_le_bytes());
+            bytes.extend(pixels);
+            PltImporter::import(&DataSource::new(bytes))
+        };
+
+        assert!(plt(u32::MAX, u32::MAX, &[0; 8]).is_err());
+        assert!(plt(2, 2, &[0; 6]).is_err());
+        assert_eq!(plt(0, 5, &[]).unwrap().pixels, vec![]);
+        assert_eq!(plt(1, 2, &[1, 2, 3, 4]).unwrap().pixels[0].color_index, 3);
+    }
+
+    #[test]
+    fn test_plt_to_image() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/PLT/DOLL01.PLT"
+        )));
+        let plt = PltImporter::import(&data).unwrap();
+
+        let color_ranges: Vec<[Rgb; 256]> = (0..7)
+            .map(|range| {
+                std::array::from_fn(|color| Rgb {
+                    r: range * 30,
+                    g: color as u8,
+                    b: 255 - color as u8,
+                    alpha: 255,
+                })
+            })
+            .collect();
+
+        let image = plt.to_image(&color_ranges);
+
+        assert_images_are_equal(
+            &image::open(Path::new(&format!(
+                "{RESOURCES_DIR}/resources/PLT/DOLL01.PNG"
+            )))
+            .unwrap(),
+            &image.into(),
+        );
+    }
+}
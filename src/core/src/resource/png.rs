@@ -0,0 +1,59 @@
+use image::{ImageBuffer, Rgba};
+
+use crate::datasource::DataSource;
+
+/// A PNG file importer
+pub struct PngImporter;
+
+impl PngImporter {
+    /// Decodes a PNG image.
+    /// The image is read through the data source reader, so it can be embedded in another file, like a BIF file.
+    pub fn to_image(source: &DataSource) -> image::ImageResult<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+        let reader = source.reader()?;
+        Ok(
+            image::ImageReader::with_format(reader.data, image::ImageFormat::Png)
+                .decode()?
+                .to_rgba8(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{resource::test_utils::assert_images_are_equal, test_utils::RESOURCES_DIR};
+    use std::path::Path;
+
+    const PNG_PATH: &str = "/resources/BAM_V2/1CHELM0300000.PNG";
+
+    #[test]
+    fn test_parse_png() {
+        let path = format!("{RESOURCES_DIR}{PNG_PATH}");
+        let data = DataSource::new(Path::new(&path));
+
+        let image = PngImporter::to_image(&data).unwrap();
+
+        assert_images_are_equal(&image.into(), &image::open(&path).unwrap());
+    }
+
+    #[test]
+    fn test_parse_embedded_png() {
+        let path = format!("{RESOURCES_DIR}{PNG_PATH}");
+        let png = std::fs::read(&path).unwrap();
+        let original = image::open(&path).unwrap();
+
+        // The PNG is stored between other data, as in a BIF file
+        let mut bytes = vec![0xaa; 100];
+        bytes.extend(&png);
+        bytes.extend([0xbb; 50]);
+
+        let data = DataSource::new(bytes.clone()).with_offset(100, Some(png.len() as u64));
+        let image = PngImporter::to_image(&data).unwrap();
+        assert_images_are_equal(&image.into(), &original);
+
+        // An embedded source without a limit reads up to the end of the data
+        let data = DataSource::new(bytes).with_offset(100, None);
+        let image = PngImporter::to_image(&data).unwrap();
+        assert_images_are_equal(&image.into(), &original);
+    }
+}
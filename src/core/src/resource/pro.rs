@@ -0,0 +1,256 @@
+use serde::{Deserialize, Serialize};
+
+use crate::datasource::{DataSource, Importer};
+
+/// The offset of the projectile extension block
+const PROJECTILE_OFFSET: u64 = 0x100;
+
+/// The offset of the area extension block
+const AREA_OFFSET: u64 = 0x200;
+
+/// A PRO file importer
+pub struct ProImporter;
+
+impl Importer for ProImporter {
+    type T = Pro;
+
+    fn import(source: &DataSource) -> std::io::Result<Pro> {
+        let mut reader = source.reader()?;
+
+        let signature = reader.read_string(8)?;
+        if signature != "PRO V1.0" {
+            return Err(std::io::Error::other(format!(
+                "Wrong file type: {}",
+                signature
+            )));
+        }
+
+        let projectile_type = ProType::from_u16(reader.read_u16()?);
+        let speed = reader.read_u16()?;
+        let spark_flags = reader.read_u32()?;
+        let travel_sound = reader.read_string(8)?;
+        let arrival_sound = reader.read_string(8)?;
+        let travel_vvc = reader.read_string(8)?;
+        let spark_color = reader.read_u32()?;
+
+        // The projectile extension is present for all the types but the ones without a BAM
+        let projectile = if projectile_type.has_projectile_extension() {
+            reader.set_position(PROJECTILE_OFFSET)?;
+            let bam_flags = reader.read_u32()?;
+            let bam = reader.read_string(8)?;
+            let shadow_bam = reader.read_string(8)?;
+            let bam_cycle = reader.read_u8()?;
+            let shadow_cycle = reader.read_u8()?;
+            let light_intensity = reader.read_u16()?;
+            let light_width = reader.read_u16()?;
+            let light_height = reader.read_u16()?;
+            let palette = reader.read_string(8)?;
+            let colors = reader.read_exact::<7>()?;
+            let smoke_period = reader.read_u8()?;
+            let smoke_colors = reader.read_exact::<7>()?;
+            let face_target_granularity = reader.read_u8()?;
+            let smoke_animation = reader.read_u16()?;
+            let mut trailing_bams = Vec::with_capacity(3);
+            for _ in 0..3 {
+                trailing_bams.push(reader.read_string(8)?);
+            }
+            let mut trailing_cycles = [0; 3];
+            for cycle in &mut trailing_cycles {
+                *cycle = reader.read_u16()?;
+            }
+            let puff_flags = reader.read_u32()?;
+            Some(ProProjectile {
+                bam_flags,
+                bam,
+                shadow_bam,
+                bam_cycle,
+                shadow_cycle,
+                light_intensity,
+                light_width,
+                light_height,
+                palette,
+                colors,
+                smoke_period,
+                smoke_colors,
+                face_target_granularity,
+                smoke_animation,
+                trailing_bams,
+                trailing_cycles,
+                puff_flags,
+            })
+        } else {
+            None
+        };
+
+        // The area extension is present only for the area of effect projectiles
+        let area = if projectile_type == ProType::AreaOfEffect {
+            reader.set_position(AREA_OFFSET)?;
+            let flags = reader.read_u32()?;
+            let trigger_radius = reader.read_u16()?;
+            let radius = reader.read_u16()?;
+            let trigger_sound = reader.read_string(8)?;
+            let explosion_delay = reader.read_u16()?;
+            let fragment_animation = reader.read_u16()?;
+            let secondary_projectile = reader.read_u16()?;
+            let trigger_count = reader.read_u8()?;
+            let explosion_effect = reader.read_u8()?;
+            let explosion_color = reader.read_u8()?;
+            reader.skip(1)?; // unused
+            let explosion_projectile = reader.read_u16()?;
+            let explosion_vvc = reader.read_string(8)?;
+            let cone_width = reader.read_u16()?;
+            Some(ProArea {
+                flags,
+                trigger_radius,
+                radius,
+                trigger_sound,
+                explosion_delay,
+                fragment_animation,
+                secondary_projectile,
+                trigger_count,
+                explosion_effect,
+                explosion_color,
+                explosion_projectile,
+                explosion_vvc,
+                cone_width,
+            })
+        } else {
+            None
+        };
+
+        Ok(Pro {
+            projectile_type,
+            speed,
+            spark_flags,
+            travel_sound,
+            arrival_sound,
+            travel_vvc,
+            spark_color,
+            projectile,
+            area,
+        })
+    }
+}
+
+/// A PRO file, describing a projectile
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pro {
+    pub projectile_type: ProType,
+    pub speed: u16,
+    pub spark_flags: u32,
+    pub travel_sound: String,
+    pub arrival_sound: String,
+    /// The VVC file played while the projectile travels
+    pub travel_vvc: String,
+    pub spark_color: u32,
+    /// The projectile extension, present unless the type is `ProType::NoBam`
+    pub projectile: Option<ProProjectile>,
+    /// The area extension, present only if the type is `ProType::AreaOfEffect`
+    pub area: Option<ProArea>,
+}
+
+/// The projectile type, it selects which extension blocks are stored in the file
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum ProType {
+    NoBam,
+    SingleTarget,
+    AreaOfEffect,
+    Unknown(u16),
+}
+
+impl ProType {
+    pub fn from_u16(value: u16) -> ProType {
+        match value {
+            1 => ProType::NoBam,
+            2 => ProType::SingleTarget,
+            3 => ProType::AreaOfEffect,
+            value => ProType::Unknown(value),
+        }
+    }
+
+    /// Returns whether the projectile extension block is stored for this type
+    pub fn has_projectile_extension(&self) -> bool {
+        matches!(self, ProType::SingleTarget | ProType::AreaOfEffect)
+    }
+}
+
+/// The projectile extension, describing the travelling animation
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProProjectile {
+    pub bam_flags: u32,
+    /// The BAM file of the travelling projectile
+    pub bam: String,
+    pub shadow_bam: String,
+    pub bam_cycle: u8,
+    pub shadow_cycle: u8,
+    pub light_intensity: u16,
+    pub light_width: u16,
+    pub light_height: u16,
+    /// The BMP file of the palette
+    pub palette: String,
+    pub colors: [u8; 7],
+    /// The number of frames between two smoke puffs
+    pub smoke_period: u8,
+    pub smoke_colors: [u8; 7],
+    pub face_target_granularity: u8,
+    pub smoke_animation: u16,
+    pub trailing_bams: Vec<String>,
+    pub trailing_cycles: [u16; 3],
+    pub puff_flags: u32,
+}
+
+/// The area extension, describing the explosion of an area of effect projectile
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProArea {
+    pub flags: u32,
+    pub trigger_radius: u16,
+    pub radius: u16,
+    pub trigger_sound: String,
+    pub explosion_delay: u16,
+    pub fragment_animation: u16,
+    pub secondary_projectile: u16,
+    pub trigger_count: u8,
+    /// The explosion graphics, see FIREBALL.IDS
+    pub explosion_effect: u8,
+    pub explosion_color: u8,
+    pub explosion_projectile: u16,
+    /// The VVC file played on explosion
+    pub explosion_vvc: String,
+    pub cone_width: u16,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::RESOURCES_DIR;
+    use insta::assert_json_snapshot;
+    use std::path::Path;
+
+    #[test]
+    fn test_parse_pro() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/PRO/FIREBALL.PRO"
+        )));
+
+        let pro = ProImporter::import(&data).unwrap();
+
+        assert_eq!(pro.projectile_type, ProType::AreaOfEffect);
+        assert_eq!(pro.projectile.as_ref().unwrap().bam, "SPFIREPR");
+        assert_eq!(pro.area.as_ref().unwrap().radius, 256);
+
+        assert_json_snapshot!(pro);
+    }
+
+    #[test]
+    fn test_parse_pro_without_extensions() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/PRO/NOBAM.PRO"
+        )));
+
+        let pro = ProImporter::import(&data).unwrap();
+
+        assert_eq!(pro.projectile_type, ProType::NoBam);
+        assert_eq!(pro.projectile, None);
+        assert_eq!(pro.area, None);
+    }
+}
@@ -1,7 +1,12 @@
 // To decode PVR texture files check: https://crates.io/crates/texture2ddecoder
 
-use crate::datasource::{DataSource, Importer};
-use image::{ImageBuffer, Rgba};
+use std::io::Read;
+
+use crate::{
+    cache::ResourceCache,
+    datasource::{DataSource, Importer, Reader},
+};
+use image::{ImageBuffer, ImageError, Rgba, RgbaImage};
 
 /// A PVRZ file importer
 pub struct PvrzImporter;
@@ -14,7 +19,7 @@ impl Importer for PvrzImporter {
     /// PVR is essentially a texture container that contains a header and data is compressed by a DDS algorithm
     /// (see: https://crates.io/crates/dds).
     /// The specific algorithm for the data is speficified in the PVR pixel_format header field. Games based on the Infinity engine
-    /// only use pixel_format 7 (DXT1/BC1) and 11 (DXT5/BC3).
+    /// mostly use pixel_format 7 (DXT1/BC1) and 11 (DXT5/BC3), while pixel_format 9 (DXT3/BC2) appears in some assets.
     fn import(source: &DataSource) -> std::io::Result<PvrzHeader> {
         let mut reader = source.reader()?;
 
@@ -22,28 +27,13 @@ impl Importer for PvrzImporter {
         // gemrb use BigEndianess if this value is equal to 0x50565203.
         let _size = reader.read_u32()?;
 
-        let mut reader = reader.as_zip_reader();
-
-        // header
-        Ok(PvrzHeader {
-            version: reader.read_u32()?,
-            flags: reader.read_u32()?,
-            pixel_format: PvrDataCompression::from_u64(reader.read_u64()?)?,
-            color_space: reader.read_u32()?,
-            channel_type: reader.read_u32()?,
-            height: reader.read_u32()?,
-            width: reader.read_u32()?,
-            depth: reader.read_u32()?,
-            surfaces_number: reader.read_u32()?,
-            faces_number: reader.read_u32()?,
-            mip_map_count: reader.read_u32()?,
-            metadata_size: reader.read_u32()?,
-        })
+        read_header(&mut reader.as_zip_reader())
     }
 }
 
 impl PvrzImporter {
-    /// Converts a PVRZ file to an image
+    /// Converts a PVRZ file to an image.
+    /// If the header has not been imported yet, prefer `decode` which reads the file only once.
     pub fn to_image(
         header: &PvrzHeader,
         source: &DataSource,
@@ -55,48 +45,184 @@ impl PvrzImporter {
 
         let mut reader = reader.as_zip_reader();
 
+        // 52 is the size of the header
+        reader.skip(52)?;
+
+        read_image(header, &mut reader)
+    }
+
+    /// Converts a mip map level of a PVRZ file to an image.
+    /// Level 0 is the full size texture, each following level halves the size of the previous one.
+    /// Only the first surface and face of each level is converted.
+    pub fn to_image_level(
+        header: &PvrzHeader,
+        source: &DataSource,
+        level: u32,
+    ) -> image::ImageResult<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+        if level >= header.mip_map_count {
+            return Err(std::io::Error::other(format!(
+                "Mip map level {level} not found, the texture has {} levels",
+                header.mip_map_count
+            )))?;
+        }
+
+        let mut reader = source.reader()?;
+        // Not sure for what this is used.
+        // gemrb use BigEndianess if this value is equal to 0x50565203.
+        let _size = reader.read_u32()?;
+
+        let mut reader = reader.as_zip_reader();
+
         // 52 is the size of the header
         reader.skip(52 + header.metadata_size as u64)?;
 
-        let mut data = vec![];
-        reader.read_to_end(&mut data, u64::MAX)?;
-
-        let mut image = vec![0u32; header.width as usize * header.height as usize];
-
-        match header.pixel_format {
-            PvrDataCompression::DXT1 => {
-                // decode DXT1 aka BC1
-                texture2ddecoder::decode_bc1a(
-                    &data,
-                    header.width as usize,
-                    header.height as usize,
-                    &mut image,
-                )
-                .map_err(std::io::Error::other)?;
-            }
-            PvrDataCompression::DXT5 => {
-                // decode DXT5 aka BC3
-                texture2ddecoder::decode_bc3(
-                    &data,
-                    header.width as usize,
-                    header.height as usize,
-                    &mut image,
-                )
-                .map_err(std::io::Error::other)?;
-            }
+        // Each level stores all its surfaces and faces before the next level
+        let surfaces = header.surfaces_number.max(1) as u64 * header.faces_number.max(1) as u64;
+        for previous_level in 0..level {
+            let (width, height) = header.level_dimensions(previous_level);
+            reader.skip(header.pixel_format.data_size(width, height) as u64 * surfaces)?;
         }
 
-        Ok(ImageBuffer::from_fn(header.width, header.height, |x, y| {
-            let idx = (y * header.width + x) as usize;
-            let p = image[idx];
-            Rgba([
-                ((p >> 16) & 0xFF) as u8, // R
-                ((p >> 8) & 0xFF) as u8,  // G
-                (p & 0xFF) as u8,         // B
-                ((p >> 24) & 0xFF) as u8, // A
-            ])
-        }))
+        let (width, height) = header.level_dimensions(level);
+        let data = reader.take_to_vec(header.pixel_format.data_size(width, height) as u64)?;
+
+        decode_image(&header.pixel_format, &data, width, height)
     }
+
+    /// Imports the header of a PVRZ file and converts it to an image,
+    /// decompressing the file only once.
+    pub fn load(source: &DataSource) -> std::io::Result<PvrzPage> {
+        let mut reader = source.reader()?;
+        // Not sure for what this is used.
+        // gemrb use BigEndianess if this value is equal to 0x50565203.
+        let _size = reader.read_u32()?;
+
+        let mut reader = reader.as_zip_reader();
+
+        let header = read_header(&mut reader)?;
+        let image = read_image(&header, &mut reader).map_err(|err| match err {
+            ImageError::IoError(err) => err,
+            err => std::io::Error::other(err),
+        })?;
+        Ok((header, image))
+    }
+}
+
+/// A decoded PVRZ file: its header and its image
+pub type PvrzPage = (PvrzHeader, RgbaImage);
+
+/// A cache of decoded PVRZ files, keyed by file name
+pub type PvrzCache = ResourceCache<PvrzPage>;
+
+/// A PVRZ file importer returning the header together with the decoded image, to be stored in a `PvrzCache`
+pub struct PvrzPageImporter;
+
+impl Importer for PvrzPageImporter {
+    type T = PvrzPage;
+
+    fn import(source: &DataSource) -> std::io::Result<PvrzPage> {
+        PvrzImporter::load(source)
+    }
+}
+
+/// Reads a PVR header from the current position
+fn read_header<R: Read>(reader: &mut Reader<R>) -> std::io::Result<PvrzHeader> {
+    Ok(PvrzHeader {
+        version: reader.read_u32()?,
+        flags: reader.read_u32()?,
+        pixel_format: PvrDataCompression::from_u64(reader.read_u64()?)?,
+        color_space: reader.read_u32()?,
+        channel_type: reader.read_u32()?,
+        height: reader.read_u32()?,
+        width: reader.read_u32()?,
+        depth: reader.read_u32()?,
+        surfaces_number: reader.read_u32()?,
+        faces_number: reader.read_u32()?,
+        mip_map_count: reader.read_u32()?,
+        metadata_size: reader.read_u32()?,
+    })
+}
+
+/// Reads the PVR texture data from a reader positioned right after the header
+fn read_image<R: Read>(
+    header: &PvrzHeader,
+    reader: &mut Reader<R>,
+) -> image::ImageResult<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+    reader.skip(header.metadata_size as u64)?;
+
+    let mut data = vec![];
+    reader.read_to_end(&mut data, u64::MAX)?;
+
+    decode_image(&header.pixel_format, &data, header.width, header.height)
+}
+
+/// Decodes the pixel data of a PVR texture into an image
+fn decode_image(
+    pixel_format: &PvrDataCompression,
+    data: &[u8],
+    width: u32,
+    height: u32,
+) -> image::ImageResult<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+    type Decoder = fn(&[u8], usize, usize, &mut [u32]) -> Result<(), &'static str>;
+
+    let decoder: Decoder = match pixel_format {
+        // decode DXT1 aka BC1
+        PvrDataCompression::DXT1 => texture2ddecoder::decode_bc1a,
+        // decode DXT3 aka BC2
+        PvrDataCompression::DXT3 => texture2ddecoder::decode_bc2,
+        // decode DXT5 aka BC3
+        PvrDataCompression::DXT5 => texture2ddecoder::decode_bc3,
+        PvrDataCompression::Uncompressed { channels } => {
+            return decode_uncompressed(channels, data, width, height);
+        }
+    };
+
+    let mut image = vec![0u32; width as usize * height as usize];
+    decoder(data, width as usize, height as usize, &mut image).map_err(std::io::Error::other)?;
+
+    Ok(ImageBuffer::from_fn(width, height, |x, y| {
+        let idx = (y * width + x) as usize;
+        let p = image[idx];
+        Rgba([
+            ((p >> 16) & 0xFF) as u8, // R
+            ((p >> 8) & 0xFF) as u8,  // G
+            (p & 0xFF) as u8,         // B
+            ((p >> 24) & 0xFF) as u8, // A
+        ])
+    }))
+}
+
+/// Copies uncompressed pixels into an image, reordering the channels to RGBA.
+/// Missing color channels are set to 0 and a missing alpha channel is set to 255.
+fn decode_uncompressed(
+    channels: &[PvrChannel],
+    data: &[u8],
+    width: u32,
+    height: u32,
+) -> image::ImageResult<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+    let pixel_size = channels.len();
+    let expected_size = width as usize * height as usize * pixel_size;
+    if data.len() < expected_size {
+        return Err(std::io::Error::other(format!(
+            "Not enough pixel data: expected {expected_size} bytes, found {}",
+            data.len()
+        ))
+        .into());
+    }
+
+    Ok(ImageBuffer::from_fn(width, height, |x, y| {
+        let start = (y * width + x) as usize * pixel_size;
+        let mut pixel = Rgba([0, 0, 0, 255]);
+        for (channel, value) in channels.iter().zip(&data[start..start + pixel_size]) {
+            match channel {
+                PvrChannel::Red => pixel[0] = *value,
+                PvrChannel::Green => pixel[1] = *value,
+                PvrChannel::Blue => pixel[2] = *value,
+                PvrChannel::Alpha => pixel[3] = *value,
+            }
+        }
+        pixel
+    }))
 }
 
 /// A PVR header
@@ -116,36 +242,201 @@ pub struct PvrzHeader {
     pub metadata_size: u32,
 }
 
+impl PvrzHeader {
+    /// Returns the width and height of a mip map level, each one at least 1 pixel
+    pub fn level_dimensions(&self, level: u32) -> (u32, u32) {
+        let halve = |size: u32| size.checked_shr(level).unwrap_or(0).max(1);
+        (halve(self.width), halve(self.height))
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum PvrDataCompression {
     /// DXT1 aka BC1 compressed texture
     DXT1,
+    /// DXT3 aka BC2 compressed texture
+    DXT3,
     /// DXT5 aka BC3 compressed texture
     DXT5,
+    /// Uncompressed texture (e.g. RGBA8888).
+    /// The channels are listed in the order in which they are stored, each one being 8 bits wide.
+    Uncompressed { channels: Vec<PvrChannel> },
+}
+
+/// A color channel of an uncompressed PVR texture
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PvrChannel {
+    Red,
+    Green,
+    Blue,
+    Alpha,
+}
+
+impl PvrChannel {
+    fn from_u8(value: u8) -> Option<PvrChannel> {
+        match value {
+            b'r' => Some(PvrChannel::Red),
+            b'g' => Some(PvrChannel::Green),
+            b'b' => Some(PvrChannel::Blue),
+            b'a' => Some(PvrChannel::Alpha),
+            _ => None,
+        }
+    }
+
+    fn to_u8(self) -> u8 {
+        match self {
+            PvrChannel::Red => b'r',
+            PvrChannel::Green => b'g',
+            PvrChannel::Blue => b'b',
+            PvrChannel::Alpha => b'a',
+        }
+    }
 }
 
 impl PvrDataCompression {
     /// Converts a u64 value to a `PvrDataCompression` enum variant.
+    ///
+    /// When the most significant 4 bytes are not zero, the pixel format describes an uncompressed texture:
+    /// the least significant 4 bytes contain the channel names (e.g. `rgba`) and
+    /// the most significant 4 bytes contain the size in bits of each channel.
+    /// Only 1 to 4 channels of 8 bits are supported.
     pub fn from_u64(value: u64) -> std::io::Result<PvrDataCompression> {
+        let unexpected = || std::io::Error::other(format!("Unexpected pixel_format: {}", value));
+
+        if value >> 32 != 0 {
+            let bytes = value.to_le_bytes();
+            let mut channels = Vec::with_capacity(4);
+            for (name, bits) in bytes[0..4].iter().zip(&bytes[4..8]) {
+                match (name, bits) {
+                    (0, 0) => break,
+                    (name, 8) => channels.push(PvrChannel::from_u8(*name).ok_or_else(unexpected)?),
+                    _ => return Err(unexpected()),
+                }
+            }
+            if channels.is_empty() {
+                return Err(unexpected());
+            }
+            return Ok(PvrDataCompression::Uncompressed { channels });
+        }
+
         match value {
             7 => Ok(PvrDataCompression::DXT1),
+            9 => Ok(PvrDataCompression::DXT3),
             11 => Ok(PvrDataCompression::DXT5),
-            _ => Err(std::io::Error::other(format!(
-                "Unexpected pixel_format: {}",
-                value
-            ))),
+            _ => Err(unexpected()),
+        }
+    }
+
+    /// Returns the size in bytes of the data of a texture with the given dimensions
+    pub fn data_size(&self, width: u32, height: u32) -> usize {
+        let blocks = width.div_ceil(4) as usize * height.div_ceil(4) as usize;
+        match self {
+            PvrDataCompression::DXT1 => blocks * 8,
+            PvrDataCompression::DXT3 | PvrDataCompression::DXT5 => blocks * 16,
+            PvrDataCompression::Uncompressed { channels } => {
+                width as usize * height as usize * channels.len()
+            }
         }
     }
 
-    /// Converts a `PvrDataCompression` enum variant to a u32 value
-    pub fn to_u64(&self) -> u64 {
+    /// Converts a `PvrDataCompression` enum variant to a u64 value.
+    /// Returns an error for an uncompressed texture without channels or with more than 4 channels.
+    pub fn to_u64(&self) -> std::io::Result<u64> {
         match self {
-            PvrDataCompression::DXT1 => 7,
-            PvrDataCompression::DXT5 => 11,
+            PvrDataCompression::DXT1 => Ok(7),
+            PvrDataCompression::DXT3 => Ok(9),
+            PvrDataCompression::DXT5 => Ok(11),
+            PvrDataCompression::Uncompressed { channels } => {
+                if channels.is_empty() || channels.len() > 4 {
+                    return Err(std::io::Error::other(format!(
+                        "Unsupported number of channels: {}",
+                        channels.len()
+                    )));
+                }
+                let mut bytes = [0u8; 8];
+                for (i, channel) in channels.iter().enumerate() {
+                    bytes[i] = channel.to_u8();
+                    bytes[i + 4] = 8;
+                }
+                Ok(u64::from_le_bytes(bytes))
+            }
         }
     }
 }
 
+/// A rectangular block of pixels copied from a decoded PVRZ image into a target image.
+/// BAM V2, MOS V2 and TIS V2 files build their images from these blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PvrzBlock {
+    pub source_x: u32,
+    pub source_y: u32,
+    pub target_x: u32,
+    pub target_y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Copies a block of RGBA pixels from the `source` image, `source_width` pixels wide,
+/// into the `target` image, `target_width` pixels wide.
+/// Returns an error, without copying anything, if the block does not fit in one of the images.
+pub fn blit_block(
+    target: &mut [u8],
+    target_width: u32,
+    source: &[u8],
+    source_width: u32,
+    block: &PvrzBlock,
+) -> std::io::Result<()> {
+    check_block_bounds(
+        "source",
+        source.len(),
+        source_width,
+        (block.source_x, block.source_y),
+        block,
+    )?;
+    check_block_bounds(
+        "target",
+        target.len(),
+        target_width,
+        (block.target_x, block.target_y),
+        block,
+    )?;
+
+    let row_size = block.width as usize * 4;
+    for row in 0..block.height as usize {
+        let source_start =
+            ((block.source_y as usize + row) * source_width as usize + block.source_x as usize) * 4;
+        let target_start =
+            ((block.target_y as usize + row) * target_width as usize + block.target_x as usize) * 4;
+
+        target[target_start..target_start + row_size]
+            .copy_from_slice(&source[source_start..source_start + row_size]);
+    }
+    Ok(())
+}
+
+/// Checks that a block placed at `position` fits in an RGBA image of `len` bytes and `width` pixels per row
+fn check_block_bounds(
+    image: &str,
+    len: usize,
+    width: u32,
+    (x, y): (u32, u32),
+    block: &PvrzBlock,
+) -> std::io::Result<()> {
+    let height = len as u64 / (width.max(1) as u64 * 4);
+    let right = x as u64 + block.width as u64;
+    let bottom = y as u64 + block.height as u64;
+    if right > width as u64 || bottom > height {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "PVRZ block of {}x{} at ({}, {}) exceeds the {} image of {}x{}",
+                block.width, block.height, x, y, image, width, height
+            ),
+        ));
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -156,6 +447,96 @@ mod tests {
     };
     use std::path::Path;
 
+    #[test]
+    fn test_blit_block() {
+        // A 4x3 source image where every pixel holds its index
+        let source = (0..12u8).flat_map(|i| [i, i, i, 255]).collect::<Vec<_>>();
+        let mut target = vec![0u8; 3 * 3 * 4];
+
+        // Copies the pixels 6, 7, 10, 11 to the bottom right corner of the target
+        blit_block(
+            &mut target,
+            3,
+            &source,
+            4,
+            &PvrzBlock {
+                source_x: 2,
+                source_y: 1,
+                target_x: 1,
+                target_y: 1,
+                width: 2,
+                height: 2,
+            },
+        )
+        .unwrap();
+
+        let pixels = target.chunks(4).map(|pixel| pixel[0]).collect::<Vec<_>>();
+        assert_eq!(pixels, vec![0, 0, 0, 0, 6, 7, 0, 10, 11]);
+        assert_eq!(&target[4 * 4..5 * 4], &[6, 6, 6, 255]);
+        assert_eq!(&target[7 * 4..8 * 4], &[10, 10, 10, 255]);
+        // The pixels outside the block are untouched
+        assert_eq!(&target[3 * 4..4 * 4], &[0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_blit_block_out_of_bounds() {
+        let source = vec![0u8; 4 * 3 * 4];
+        let mut target = vec![0u8; 3 * 3 * 4];
+        let block = PvrzBlock {
+            source_x: 0,
+            source_y: 0,
+            target_x: 0,
+            target_y: 0,
+            width: 2,
+            height: 2,
+        };
+
+        // The block overflows the right column of the target
+        let err = blit_block(
+            &mut target,
+            3,
+            &source,
+            4,
+            &PvrzBlock {
+                target_x: 2,
+                ..block
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("target"));
+
+        // The block overflows the bottom row of the source
+        let err = blit_block(
+            &mut target,
+            3,
+            &source,
+            4,
+            &PvrzBlock {
+                source_y: 2,
+                ..block
+            },
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("source"));
+
+        // Coordinates overflowing u32 arithmetic are rejected as well
+        assert!(
+            blit_block(
+                &mut target,
+                3,
+                &source,
+                4,
+                &PvrzBlock {
+                    source_x: u32::MAX,
+                    ..block
+                },
+            )
+            .is_err()
+        );
+        assert!(target.iter().all(|byte| *byte == 0));
+    }
+
     #[test]
     fn test_parse_pvrz_dxt1() {
         let data = DataSource::new(Path::new(&format!(
@@ -235,4 +616,198 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_load_pvrz_should_match_import_and_to_image() {
+        for path in [
+            "MOS_DXT1/A004602.PVRZ",
+            "MOS_DXT5/MOS0000.PVRZ",
+            "PVRZ_DXT3/DXT3.PVRZ",
+        ] {
+            let data = DataSource::new(Path::new(&format!("{RESOURCES_DIR}/resources/{path}")));
+
+            let header = PvrzImporter::import(&data).unwrap();
+            let image = PvrzImporter::to_image(&header, &data).unwrap();
+
+            assert_eq!(PvrzImporter::load(&data).unwrap(), (header, image));
+        }
+
+        let err = PvrzImporter::load(&DataSource::new(vec![0u8; 4])).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_parse_pvrz_dxt3() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/PVRZ_DXT3/DXT3.PVRZ"
+        )));
+
+        let pvrz_header = PvrzImporter::import(&data).unwrap();
+
+        assert_eq!(pvrz_header.pixel_format, PvrDataCompression::DXT3);
+        assert_eq!(pvrz_header.width, 16);
+        assert_eq!(pvrz_header.height, 16);
+
+        // Assert that the image is the same as the reference
+        {
+            let image = PvrzImporter::to_image(&pvrz_header, &data).unwrap();
+
+            assert_images_are_equal(
+                &image::open(Path::new(&format!(
+                    "{RESOURCES_DIR}/resources/PVRZ_DXT3/DXT3.PNG"
+                )))
+                .unwrap(),
+                &image.into(),
+            );
+        }
+    }
+
+    #[test]
+    fn test_pvr_data_compression_roundtrip() {
+        for value in [7, 9, 11] {
+            assert_eq!(
+                PvrDataCompression::from_u64(value)
+                    .unwrap()
+                    .to_u64()
+                    .unwrap(),
+                value
+            );
+        }
+        assert!(PvrDataCompression::from_u64(8).is_err());
+
+        // An uncompressed format needs 1 to 4 channels
+        let no_channels = u64::from_le_bytes([0, b'g', b'b', b'a', 0, 8, 8, 8]);
+        assert!(PvrDataCompression::from_u64(no_channels).is_err());
+        let channels = |count| PvrDataCompression::Uncompressed {
+            channels: vec![PvrChannel::Red; count],
+        };
+        assert!(channels(0).to_u64().is_err());
+        assert!(channels(4).to_u64().is_ok());
+        assert!(channels(5).to_u64().is_err());
+    }
+
+    #[test]
+    fn test_parse_pvrz_uncompressed() {
+        let rgba8888 = u64::from_le_bytes([b'r', b'g', b'b', b'a', 8, 8, 8, 8]);
+        let pixels = [
+            [255, 0, 0, 255],
+            [0, 255, 0, 128],
+            [0, 0, 255, 0],
+            [10, 20, 30, 40],
+        ];
+
+        let data = DataSource::new(pvrz_bytes(rgba8888, 2, 2, 1, pixels.as_flattened()));
+
+        let pvrz_header = PvrzImporter::import(&data).unwrap();
+
+        assert_eq!(
+            pvrz_header.pixel_format,
+            PvrDataCompression::Uncompressed {
+                channels: vec![
+                    PvrChannel::Red,
+                    PvrChannel::Green,
+                    PvrChannel::Blue,
+                    PvrChannel::Alpha
+                ]
+            }
+        );
+        assert_eq!(pvrz_header.pixel_format.to_u64().unwrap(), rgba8888);
+
+        let image = PvrzImporter::to_image(&pvrz_header, &data).unwrap();
+        assert_eq!(image.dimensions(), (2, 2));
+        assert_eq!(image.get_pixel(0, 0), &Rgba(pixels[0]));
+        assert_eq!(image.get_pixel(1, 0), &Rgba(pixels[1]));
+        assert_eq!(image.get_pixel(0, 1), &Rgba(pixels[2]));
+        assert_eq!(image.get_pixel(1, 1), &Rgba(pixels[3]));
+    }
+
+    #[test]
+    fn test_parse_pvrz_uncompressed_bgr() {
+        let bgr888 = u64::from_le_bytes([b'b', b'g', b'r', 0, 8, 8, 8, 0]);
+
+        let data = DataSource::new(pvrz_bytes(bgr888, 1, 1, 1, &[1, 2, 3]));
+
+        let pvrz_header = PvrzImporter::import(&data).unwrap();
+        let image = PvrzImporter::to_image(&pvrz_header, &data).unwrap();
+        assert_eq!(image.get_pixel(0, 0), &Rgba([3, 2, 1, 255]));
+    }
+
+    #[test]
+    fn test_pvrz_to_image_level() {
+        let rgb888 = u64::from_le_bytes([b'r', b'g', b'b', 0, 8, 8, 8, 0]);
+
+        // level 0 is 4x2 red pixels, level 1 is 2x1 green pixels
+        let mut data = [255, 0, 0].repeat(8);
+        data.extend([0, 255, 0].repeat(2));
+
+        let data = DataSource::new(pvrz_bytes(rgb888, 4, 2, 2, &data));
+        let header = PvrzImporter::import(&data).unwrap();
+
+        let level_0 = PvrzImporter::to_image_level(&header, &data, 0).unwrap();
+        assert_eq!(level_0.dimensions(), (4, 2));
+        assert!(level_0.pixels().all(|p| p == &Rgba([255, 0, 0, 255])));
+        assert_eq!(level_0, PvrzImporter::to_image(&header, &data).unwrap());
+
+        let level_1 = PvrzImporter::to_image_level(&header, &data, 1).unwrap();
+        assert_eq!(level_1.dimensions(), (2, 1));
+        assert!(level_1.pixels().all(|p| p == &Rgba([0, 255, 0, 255])));
+
+        assert!(PvrzImporter::to_image_level(&header, &data, 2).is_err());
+    }
+
+    #[test]
+    fn test_pvrz_level_dimensions() {
+        let rgb888 = u64::from_le_bytes([b'r', b'g', b'b', 0, 8, 8, 8, 0]);
+        let data = DataSource::new(pvrz_bytes(rgb888, 4, 2, 40, &[0; 4 * 2 * 3]));
+        let header = PvrzImporter::import(&data).unwrap();
+
+        assert_eq!(header.level_dimensions(1), (2, 1));
+        assert_eq!(header.level_dimensions(31), (1, 1));
+        assert_eq!(header.level_dimensions(32), (1, 1));
+        assert_eq!(header.level_dimensions(u32::MAX), (1, 1));
+        // The levels past the first one are missing from the data
+        assert!(PvrzImporter::to_image_level(&header, &data, 35).is_err());
+    }
+
+    #[test]
+    fn test_pvr_data_size() {
+        assert_eq!(PvrDataCompression::DXT1.data_size(256, 1024), 131072);
+        assert_eq!(PvrDataCompression::DXT5.data_size(512, 512), 262144);
+        // Block compressed levels are padded to a whole 4x4 block
+        assert_eq!(PvrDataCompression::DXT1.data_size(2, 1), 8);
+        assert_eq!(PvrDataCompression::DXT3.data_size(1, 1), 16);
+    }
+
+    /// Builds a PVRZ file with a single surface and no metadata
+    fn pvrz_bytes(
+        pixel_format: u64,
+        width: u32,
+        height: u32,
+        mip_map_count: u32,
+        data: &[u8],
+    ) -> Vec<u8> {
+        use std::io::Write;
+
+        let mut pvr = Vec::new();
+        pvr.extend_from_slice(&55727696u32.to_le_bytes()); // version
+        pvr.extend_from_slice(&0u32.to_le_bytes()); // flags
+        pvr.extend_from_slice(&pixel_format.to_le_bytes());
+        pvr.extend_from_slice(&0u32.to_le_bytes()); // color space
+        pvr.extend_from_slice(&0u32.to_le_bytes()); // channel type
+        pvr.extend_from_slice(&height.to_le_bytes());
+        pvr.extend_from_slice(&width.to_le_bytes());
+        pvr.extend_from_slice(&1u32.to_le_bytes()); // depth
+        pvr.extend_from_slice(&1u32.to_le_bytes()); // surfaces
+        pvr.extend_from_slice(&1u32.to_le_bytes()); // faces
+        pvr.extend_from_slice(&mip_map_count.to_le_bytes());
+        pvr.extend_from_slice(&0u32.to_le_bytes()); // metadata size
+        pvr.extend_from_slice(data);
+
+        let mut pvrz = (pvr.len() as u32).to_le_bytes().to_vec();
+        let mut encoder =
+            flate2::write::ZlibEncoder::new(&mut pvrz, flate2::Compression::default());
+        encoder.write_all(&pvr).unwrap();
+        encoder.finish().unwrap();
+        pvrz
+    }
 }
@@ -0,0 +1,163 @@
+use std::{io::Read, path::PathBuf, sync::Arc};
+
+use flate2::bufread::ZlibDecoder;
+
+use crate::{
+    datasource::{Data, DataSource, Importer},
+    fs::{CaseInsensitivePath, FileSystem},
+};
+
+/// A SAV file importer
+pub struct SavImporter;
+
+impl Importer for SavImporter {
+    type T = Sav;
+
+    /// Imports a SAV V1.0 file, decompressing all its files in memory
+    fn import(source: &DataSource) -> std::io::Result<Sav> {
+        let mut reader = source.reader()?;
+
+        let signature = reader.read_string(8)?;
+        if signature != "SAV V1.0" {
+            return Err(std::io::Error::other(format!(
+                "Wrong file type: {}",
+                signature
+            )));
+        }
+
+        let mut entries = Vec::new();
+        loop {
+            // The files follow each other up to the end of the archive
+            let name_length = match reader.read_u32() {
+                Ok(length) => length as u64,
+                Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => break,
+                Err(err) => return Err(err),
+            };
+            let name = reader.read_string(name_length)?;
+            let uncompressed_size = reader.read_u32()? as usize;
+            let compressed_size = reader.read_u32()? as u64;
+            let compressed = reader.take_to_vec(compressed_size)?;
+
+            let mut data = Vec::with_capacity(uncompressed_size);
+            ZlibDecoder::new(compressed.as_slice()).read_to_end(&mut data)?;
+            if data.len() != uncompressed_size {
+                return Err(std::io::Error::other(format!(
+                    "Wrong uncompressed size of {}: expected {}, found {}",
+                    name,
+                    uncompressed_size,
+                    data.len()
+                )));
+            }
+
+            entries.push(SavEntry {
+                name,
+                data: Arc::new(data),
+            });
+        }
+
+        Ok(Sav {
+            encoding: source.encoding(),
+            entries,
+        })
+    }
+}
+
+/// A SAV file, the archive of the area and creature files of a saved game
+#[derive(Debug, Clone)]
+pub struct Sav {
+    encoding: &'static encoding_rs::Encoding,
+    pub entries: Vec<SavEntry>,
+}
+
+/// A decompressed file of a SAV archive
+#[derive(Debug, Clone)]
+pub struct SavEntry {
+    /// The file name, including its extension
+    pub name: String,
+    pub data: Arc<Vec<u8>>,
+}
+
+impl Sav {
+    /// Returns the data of a file of the archive, e.g. `AR0602.ARE`.
+    /// The name is matched case insensitively and the data source has the encoding of the SAV source.
+    pub fn open(&self, name: &str) -> Option<DataSource> {
+        self.entries
+            .iter()
+            .find(|entry| entry.name.eq_ignore_ascii_case(name))
+            .map(|entry| {
+                DataSource::new(Data::MemorySource(entry.data.clone())).with_encoding(self.encoding)
+            })
+    }
+}
+
+/// The files of a SAV archive, in its root directory
+impl FileSystem for Sav {
+    fn get_path_opt(&self, _path: &CaseInsensitivePath) -> Option<PathBuf> {
+        None
+    }
+
+    fn search_path_opt(&self, _path: &CaseInsensitivePath) -> Option<PathBuf> {
+        None
+    }
+
+    fn open_opt(&self, path: &CaseInsensitivePath) -> Option<DataSource> {
+        self.open(path.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        resource::{
+            cre::CreImporter,
+            gam::{GamImporter, GamVersion},
+        },
+        test_utils::RESOURCES_DIR,
+    };
+    use std::path::Path;
+
+    fn baldur_sav() -> Sav {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/SAV/BALDUR.SAV"
+        )));
+        SavImporter::import(&data).unwrap()
+    }
+
+    #[test]
+    fn test_import_sav() {
+        let sav = baldur_sav();
+
+        let names = sav
+            .entries
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["AR9999.ARE", "BALDUR.GAM", "MINSC6.CRE"]);
+        assert_eq!(sav.entries[1].data.len(), 1780);
+    }
+
+    #[test]
+    fn test_open_and_parse_embedded_resources() {
+        let sav = baldur_sav();
+
+        let gam = GamImporter::import(&sav.open("baldur.gam").unwrap()).unwrap();
+        assert_eq!(gam.version, GamVersion::V2_0);
+        assert_eq!(gam.party_gold, 1250);
+
+        let cre = sav
+            .open_opt(&CaseInsensitivePath::new("MINSC6.CRE"))
+            .unwrap();
+        assert!(CreImporter::import(&cre).is_ok());
+
+        assert!(sav.open("MISSING.CRE").is_none());
+    }
+
+    #[test]
+    fn test_import_sav_should_fail_if_wrong_signature() {
+        let data = DataSource::new(Path::new(&format!(
+            "{RESOURCES_DIR}/resources/GAM/BALDUR.GAM"
+        )));
+        assert!(SavImporter::import(&data).is_err());
+    }
+}